@@ -3,89 +3,90 @@ use std::hint::black_box;
 use criterion::{criterion_group, criterion_main, Criterion};
 use crossandra::{common, Tokenizer};
 
+const SAMARIUM_LITERALS: &[(&str, &str)] = &[
+    ("ad", "+"),
+    ("an", "&&"),
+    ("ar", "@@@"),
+    ("as", ":"),
+    ("at", "."),
+    ("ba", "&"),
+    ("bn", "~"),
+    ("bo", "|"),
+    ("brace_c", "}"),
+    ("brace_o", "{"),
+    ("brack_c", "]"),
+    ("brack_o", "["),
+    ("bx", "^"),
+    ("cas", "%"),
+    ("cat", "!!"),
+    ("cl", "@"),
+    ("da", "@!"),
+    ("de", "<>"),
+    ("di", "--"),
+    ("e", ",,"),
+    ("end", ";"),
+    ("ent", "=>"),
+    ("enu", "#"),
+    ("eq", "::"),
+    ("ex", "=>!"),
+    ("fi_a", "&~~>"),
+    ("fi_b_a", "&%~>"),
+    ("fi_b_r", "<~%"),
+    ("fi_b_r_w", "<%>"),
+    ("fi_b_w", "%~>"),
+    ("fi_c", "?~>"),
+    ("fi_q_a", "&~>"),
+    ("fi_q_b_a", "&%>"),
+    ("fi_q_b_r", "<%"),
+    ("fi_q_b_w", "%>"),
+    ("fi_q_r", "<~"),
+    ("fi_q_w", "~>"),
+    ("fi_r_w", "<~>"),
+    ("fi_r", "<~~"),
+    ("fi_w", "~~>"),
+    ("fo", "..."),
+    ("fr", "<-"),
+    ("fu", "*"),
+    ("ge", ">:"),
+    ("gt", ">"),
+    ("h", "##"),
+    ("if", "?"),
+    ("im", "<="),
+    ("in", "->?"),
+    ("ins", "'"),
+    ("le", "<:"),
+    ("lt", "<"),
+    ("mo", "---"),
+    ("mu", "++"),
+    ("ne", ":::"),
+    ("no", "~~"),
+    ("o", "||"),
+    ("p_c", ")"),
+    ("p_o", "("),
+    ("pa", "!?"),
+    ("po", "+++"),
+    ("pr", "!"),
+    ("r", "???"),
+    ("se", ","),
+    ("sle", ",.,"),
+    ("s_c", ">>"),
+    ("s_o", "<<"),
+    ("sp", "$"),
+    ("su", "-"),
+    ("t_c", "}}"),
+    ("t_o", "{{"),
+    ("th", "!!!"),
+    ("to", "->"),
+    ("tr", "??"),
+    ("ty", "?!"),
+    ("u", "@@"),
+    ("w", ".."),
+    ("x", "^^"),
+    ("y", "**"),
+    ("z", "><"),
+];
+
 pub fn samarium(c: &mut Criterion) {
-    let literals = [
-        ("ad", "+"),
-        ("an", "&&"),
-        ("ar", "@@@"),
-        ("as", ":"),
-        ("at", "."),
-        ("ba", "&"),
-        ("bn", "~"),
-        ("bo", "|"),
-        ("brace_c", "}"),
-        ("brace_o", "{"),
-        ("brack_c", "]"),
-        ("brack_o", "["),
-        ("bx", "^"),
-        ("cas", "%"),
-        ("cat", "!!"),
-        ("cl", "@"),
-        ("da", "@!"),
-        ("de", "<>"),
-        ("di", "--"),
-        ("e", ",,"),
-        ("end", ";"),
-        ("ent", "=>"),
-        ("enu", "#"),
-        ("eq", "::"),
-        ("ex", "=>!"),
-        ("fi_a", "&~~>"),
-        ("fi_b_a", "&%~>"),
-        ("fi_b_r", "<~%"),
-        ("fi_b_r_w", "<%>"),
-        ("fi_b_w", "%~>"),
-        ("fi_c", "?~>"),
-        ("fi_q_a", "&~>"),
-        ("fi_q_b_a", "&%>"),
-        ("fi_q_b_r", "<%"),
-        ("fi_q_b_w", "%>"),
-        ("fi_q_r", "<~"),
-        ("fi_q_w", "~>"),
-        ("fi_r_w", "<~>"),
-        ("fi_r", "<~~"),
-        ("fi_w", "~~>"),
-        ("fo", "..."),
-        ("fr", "<-"),
-        ("fu", "*"),
-        ("ge", ">:"),
-        ("gt", ">"),
-        ("h", "##"),
-        ("if", "?"),
-        ("im", "<="),
-        ("in", "->?"),
-        ("ins", "'"),
-        ("le", "<:"),
-        ("lt", "<"),
-        ("mo", "---"),
-        ("mu", "++"),
-        ("ne", ":::"),
-        ("no", "~~"),
-        ("o", "||"),
-        ("p_c", ")"),
-        ("p_o", "("),
-        ("pa", "!?"),
-        ("po", "+++"),
-        ("pr", "!"),
-        ("r", "???"),
-        ("se", ","),
-        ("sle", ",.,"),
-        ("s_c", ">>"),
-        ("s_o", "<<"),
-        ("sp", "$"),
-        ("su", "-"),
-        ("t_c", "}}"),
-        ("t_o", "{{"),
-        ("th", "!!!"),
-        ("to", "->"),
-        ("tr", "??"),
-        ("ty", "?!"),
-        ("u", "@@"),
-        ("w", ".."),
-        ("x", "^^"),
-        ("y", "**"),
-        ("z", "><"),
-    ];
     let patterns = [
         common::DOUBLE_QUOTED_STRING.clone(),
         ("number".into(), r"[\\/]+`?[\\/]*|`[\\/]*".into()),
@@ -93,12 +94,18 @@ pub fn samarium(c: &mut Criterion) {
         ("line_comment".into(), r"==[^\n]*".into()),
         ("variable".into(), r"\w+".into()),
     ];
-    let tok = Tokenizer::default()
-        .with_literals(&literals)
-        .unwrap()
-        .with_patterns(patterns.into())
-        .unwrap()
-        .with_ignore_whitespace(true);
+    let build = || {
+        Tokenizer::default()
+            .with_literals(SAMARIUM_LITERALS)
+            .unwrap()
+            .with_patterns(patterns.to_vec())
+            .unwrap()
+            .with_ignore_whitespace(true)
+    };
+    let tok = build();
+
+    // compare with and without the `regex-cache` feature
+    c.bench_function("construction", |b| b.iter(|| black_box(build())));
 
     // one module from the samarium std
     let datetime = include_str!("assets/samarium/datetime.sm");
@@ -132,5 +139,52 @@ pub fn samarium(c: &mut Criterion) {
     });
 }
 
-criterion_group!(benches, samarium);
+/// Compares various configurations on the same source, as well as counting and collecting tokens.
+pub fn configurations(c: &mut Criterion) {
+    let datetime = include_str!("assets/samarium/datetime.sm");
+    let numbers = Tokenizer::default()
+        .with_literals(SAMARIUM_LITERALS)
+        .unwrap()
+        .with_ignore_whitespace(true)
+        .with_patterns(vec![
+            common::DOUBLE_QUOTED_STRING.clone(),
+            common::NUMBER.clone(),
+            ("variable".into(), r"\w+".into()),
+        ])
+        .unwrap();
+    let longest = numbers.clone().with_longest_match(true);
+
+    let mut group = c.benchmark_group("configurations");
+    for (name, tok) in [("first match", &numbers), ("longest match", &longest)] {
+        group.bench_function(format!("{name} collect"), |b| {
+            b.iter(|| black_box(tok.tokenize(black_box(datetime)).collect::<Vec<_>>()))
+        });
+        group.bench_function(format!("{name} count"), |b| {
+            b.iter(|| black_box(tok.count(black_box(datetime))))
+        });
+    }
+}
+
+/// Tokenizes deeply indented source, where most of the time is spent skipping whitespace.
+pub fn indentation(c: &mut Criterion) {
+    let tok = Tokenizer::default()
+        .with_literals(&[
+            ("let", "let"),
+            ("name", "x"),
+            ("assign", "="),
+            ("one", "1"),
+            ("end", ";"),
+        ])
+        .unwrap()
+        .with_ignore_whitespace(true);
+    let source: String = (0..2_000)
+        .map(|i| format!("{}let x = 1;\n", " ".repeat(4 * (i % 20))))
+        .collect();
+
+    c.bench_function("indented", |b| {
+        b.iter(|| black_box(tok.tokenize(black_box(&source)).collect::<Vec<_>>()))
+    });
+}
+
+criterion_group!(benches, samarium, configurations, indentation);
 criterion_main!(benches);
@@ -0,0 +1,70 @@
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use crossandra::Tree;
+
+/// Matches the literals of the tree at every position of the source, returning the number of
+/// matched literals.
+fn match_all(tree: &Tree<&str>, source: &str) -> usize {
+    let mut count = 0;
+    let mut rest = source;
+    while let Some(c) = rest.chars().next() {
+        match tree.match_longest_prefix(rest) {
+            Some((value, _)) => {
+                count += 1;
+                rest = &rest[value.len()..];
+            }
+            None => rest = &rest[c.len_utf8()..],
+        }
+    }
+    count
+}
+
+pub fn flat_tree(c: &mut Criterion) {
+    let operators = [
+        ("add", "+", "➕"),
+        ("sub", "-", "➖"),
+        ("left", "<", "←"),
+        ("right", ">", "→"),
+        ("read", ",", "⍞"),
+        ("write", ".", "⎕"),
+        ("begin_loop", "[", "⟦"),
+        ("end_loop", "]", "⟧"),
+    ];
+
+    // 50k chars-long program that solves the hanoi towers problem
+    let hanoi = include_str!("assets/brainfuck/hanoi.bf");
+
+    let mut ascii_tree = Tree::default();
+    let mut unicode_tree = Tree::default();
+    let mut unicode_hanoi = hanoi.to_string();
+    for (name, ascii, unicode) in operators {
+        ascii_tree.insert(ascii, name);
+        unicode_tree.insert(unicode, name);
+        unicode_hanoi = unicode_hanoi.replace(ascii, unicode);
+    }
+
+    // the same operators along with a non-ASCII literal that never matches, which makes the tree
+    // walk the source char by char instead of byte by byte (kept out of the root, so that it
+    // still has few enough children to stay a sorted `Vec`)
+    let mut mixed_tree = ascii_tree.clone();
+    mixed_tree.insert("+⍝", "comment");
+
+    // the byte path
+    c.bench_function("flat tree ascii", |b| {
+        b.iter(|| black_box(match_all(&ascii_tree, black_box(hanoi))))
+    });
+
+    // the char path on the same source
+    c.bench_function("flat tree ascii (char path)", |b| {
+        b.iter(|| black_box(match_all(&mixed_tree, black_box(hanoi))))
+    });
+
+    // the char path with multi-byte characters
+    c.bench_function("flat tree unicode", |b| {
+        b.iter(|| black_box(match_all(&unicode_tree, black_box(&unicode_hanoi))))
+    });
+}
+
+criterion_group!(benches, flat_tree);
+criterion_main!(benches);
@@ -0,0 +1,147 @@
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    hint::black_box,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use criterion::{
+    criterion_group, criterion_main,
+    measurement::{Measurement, ValueFormatter},
+    Criterion, SamplingMode, Throughput,
+};
+use crossandra::Tree;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static DEALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+/// The system allocator, counting the allocated and deallocated bytes.
+struct Counting;
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        DEALLOCATED.fetch_add(layout.size(), Ordering::Relaxed);
+        System.dealloc(ptr, layout);
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+/// Measures the bytes still allocated at the end of the measurement, i.e. the memory retained by
+/// the outputs of the benchmarked routine when they are dropped only afterwards.
+struct Retained;
+
+impl Measurement for Retained {
+    type Intermediate = (usize, usize);
+    type Value = usize;
+
+    fn start(&self) -> Self::Intermediate {
+        (
+            ALLOCATED.load(Ordering::SeqCst),
+            DEALLOCATED.load(Ordering::SeqCst),
+        )
+    }
+
+    fn end(&self, (allocated, deallocated): Self::Intermediate) -> Self::Value {
+        let allocated = ALLOCATED.load(Ordering::SeqCst) - allocated;
+        let deallocated = DEALLOCATED.load(Ordering::SeqCst) - deallocated;
+        allocated.saturating_sub(deallocated)
+    }
+
+    fn add(&self, v1: &Self::Value, v2: &Self::Value) -> Self::Value {
+        v1 + v2
+    }
+
+    fn zero(&self) -> Self::Value {
+        0
+    }
+
+    fn to_f64(&self, value: &Self::Value) -> f64 {
+        *value as f64
+    }
+
+    fn formatter(&self) -> &dyn ValueFormatter {
+        &BytesFormatter
+    }
+}
+
+struct BytesFormatter;
+
+impl ValueFormatter for BytesFormatter {
+    fn scale_values(&self, typical_value: f64, values: &mut [f64]) -> &'static str {
+        let (denominator, unit) = if typical_value < 1024.0 {
+            (1.0, "B")
+        } else if typical_value < 1024.0 * 1024.0 {
+            (1024.0, "KiB")
+        } else {
+            (1024.0 * 1024.0, "MiB")
+        };
+        for value in values {
+            *value /= denominator;
+        }
+        unit
+    }
+
+    fn scale_throughputs(
+        &self,
+        _typical_value: f64,
+        _throughput: &Throughput,
+        _values: &mut [f64],
+    ) -> &'static str {
+        "B"
+    }
+
+    fn scale_for_machines(&self, _values: &mut [f64]) -> &'static str {
+        "B"
+    }
+}
+
+/// Generates `count` pseudo-random lowercase keywords of 3 to 12 characters.
+fn keywords(count: usize) -> Vec<String> {
+    let mut state = 0x2545_f491_4f6c_dd1d_u64;
+    let mut next = move || {
+        // xorshift64
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    };
+    (0..count)
+        .map(|_| {
+            let len = 3 + next() % 10;
+            (0..len)
+                .map(|_| char::from(b'a' + (next() % 26) as u8))
+                .collect()
+        })
+        .collect()
+}
+
+fn tree_memory(c: &mut Criterion<Retained>) {
+    let keywords = keywords(50_000);
+
+    let mut group = c.benchmark_group("tree memory");
+    group.sample_size(10).sampling_mode(SamplingMode::Flat);
+    group.bench_function("50k keywords", |b| {
+        b.iter_with_large_drop(|| {
+            let mut tree = Tree::default();
+            for keyword in &keywords {
+                tree.insert(keyword, black_box(keyword.as_str()));
+            }
+            tree
+        })
+    });
+    group.finish();
+}
+
+criterion_group! {
+    name = benches;
+    // the retained memory is deterministic, so there is no distribution to plot
+    config = Criterion::default().with_measurement(Retained).without_plots();
+    targets = tree_memory
+}
+criterion_main!(benches);
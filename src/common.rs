@@ -1,8 +1,19 @@
 //! A collection of common patterns for use in tokenizers.
+use alloc::{format, string::String};
+
 use lazy_static::lazy_static;
 
 const STRING_BASE: &str = r"(?:\\.|[^\\])*?";
 const INT_BASE: &str = r"[0-9](?:[0-9_]*[0-9])?";
+const BIN_BASE: &str = r"[01](?:[01_]*[01])?";
+const OCT_BASE: &str = r"[0-7](?:[0-7_]*[0-7])?";
+const HEX_BASE: &str = r"[0-9A-Fa-f](?:[0-9A-Fa-f_]*[0-9A-Fa-f])?";
+const IPV4_OCTET: &str = r"(?:25[0-5]|2[0-4][0-9]|1[0-9][0-9]|[1-9]?[0-9])";
+const IPV6_GROUP: &str = r"[0-9A-Fa-f]{1,4}";
+const DATE_BASE: &str = r"[0-9]{4}-(?:0[1-9]|1[0-2])-(?:0[1-9]|[12][0-9]|3[01])";
+const SEMVER_NUMBER: &str = r"(?:0|[1-9][0-9]*)";
+const SEMVER_IDENTIFIER: &str = r"(?:0|[1-9][0-9]*|[0-9]*[A-Za-z\-][0-9A-Za-z\-]*)";
+const TIME_BASE: &str = r"(?:[01][0-9]|2[0-3]):[0-5][0-9](?::[0-5][0-9](?:\.[0-9]+)?)?";
 const FLOAT_BASE: &str = concat!(
     r"[0-9](?:[0-9_]*[0-9])?",                // integer part (required)
     r"(?:[eE][+\-]?[0-9](?:[0-9_]*[0-9])?)",  // exponent (required)
@@ -15,9 +26,21 @@ const FLOAT_BASE: &str = concat!(
     r")",                                     // }
     r"(?:[eE][+\-]?[0-9](?:[0-9_]*[0-9])?)?"  // exponent (optional)
 );
+const FLOAT_BASE_NO_TRAILING_DOT: &str = concat!(
+    r"[0-9](?:[0-9_]*[0-9])?",                // integer part (required)
+    r"(?:[eE][+\-]?[0-9](?:[0-9_]*[0-9])?)",  // exponent (required)
+    r"|",                                     // or
+    r"(?:[0-9](?:[0-9_]*[0-9])?)?",           // integer part (optional)
+    r"\.[0-9](?:[0-9_]*[0-9])?",              // decimal part (required)
+    r"(?:[eE][+\-]?[0-9](?:[0-9_]*[0-9])?)?"  // exponent (optional)
+);
 
 lazy_static! {
     /// A single character enclosed in single quotes (e.g. `'h'`).
+    ///
+    /// [`struct@SINGLE_QUOTED_STRING`] matches single characters as well, so when using both,
+    /// declare this pattern first. Longer strings then fall through to the string pattern, with or
+    /// without [`longest_match`](crate::Tokenizer#longest_match).
     pub static ref CHAR: (String, String) = ("char".into(), r"'(?:\\'|[^'])'".into());
     /// A string enclosed in single quotes (e.g. `'nice fish'`).
     pub static ref SINGLE_QUOTED_STRING: (String, String) =
@@ -32,6 +55,11 @@ lazy_static! {
     /// A C-like variable name (e.g. `crossandra_rocks`). Can consist of
     /// English letters, digits, and underscores. Cannot start with a digit.
     pub static ref C_NAME: (String, String) = ("c_name".into(), r"[_A-Za-z][_A-Za-z\d]*".into());
+    /// A Unicode-aware variable name (e.g. `café` or `λ`), following the Unicode `XID_Start` and
+    /// `XID_Continue` rules. Can also start with an underscore. For ASCII identifiers, this is
+    /// equivalent to [`struct@C_NAME`].
+    pub static ref UNICODE_NAME: (String, String) =
+        ("unicode_name".into(), r"[_\p{XID_Start}]\p{XID_Continue}*".into());
     /// A newline (either `\n` or `\r\n`).
     pub static ref NEWLINE: (String, String) = ("newline".into(), r"\r?\n".into());
     /// A single digit (e.g. `7`).
@@ -43,14 +71,25 @@ lazy_static! {
     /// A signed integer (e.g. `-1`). Underscores can be used as separators.
     pub static ref SIGNED_INT: (String, String) =
         ("signed_int".into(), format!(r"[+\-]{INT_BASE}"));
+    /// A binary integer (e.g. `0b1010`). Underscores can be used as separators.
+    pub static ref BIN_INT: (String, String) = ("bin_int".into(), format!("0[bB]{BIN_BASE}"));
+    /// An octal integer (e.g. `0o755`). Underscores can be used as separators.
+    pub static ref OCT_INT: (String, String) = ("oct_int".into(), format!("0[oO]{OCT_BASE}"));
+    /// A hexadecimal integer (e.g. `0xDE_AD`). Underscores can be used as separators. Case
+    /// insensitive.
+    pub static ref HEX_INT: (String, String) = ("hex_int".into(), format!("0[xX]{HEX_BASE}"));
     /// A decimal value (e.g. `3.14`).
     pub static ref DECIMAL: (String, String) = (
         "decimal".into(),
         format!(r"{INT_BASE}\.(?:{INT_BASE})?|\.{INT_BASE}")
     );
     /// An unsigned floating point value (e.g. `1e3`).
+    ///
+    /// A trailing dot is part of the float, so `1.` and `1.e5` are single floats. Use
+    /// [`float_pattern`] with [`TrailingDot::Separate`] to leave the dot for a separate token.
     pub static ref UNSIGNED_FLOAT: (String, String) = ("unsigned_float".into(), FLOAT_BASE.into());
-    /// A signed floating point value (e.g. `+4.3`).
+    /// A signed floating point value (e.g. `+4.3`). Like [`struct@UNSIGNED_FLOAT`], it includes
+    /// a trailing dot.
     pub static ref SIGNED_FLOAT: (String, String) =
         ("signed_float".into(), format!(r"[+\-](?:{FLOAT_BASE})"));
     /// A string enclosed in either single or double quotes.
@@ -58,6 +97,17 @@ lazy_static! {
         "string".into(),
         format!(r#""{STRING_BASE}"|'{STRING_BASE}'"#)
     );
+    /// A string enclosed in triple double or single quotes (e.g. `"""docstring"""`). Can span
+    /// multiple lines and contain unescaped quotes, as long as there are no three in a row.
+    pub static ref TRIPLE_QUOTED_STRING: (String, String) = (
+        "triple_quoted_string".into(),
+        format!(r#""""{STRING_BASE}"""|'''{STRING_BASE}'''"#)
+    );
+    /// A Rust-like raw string (e.g. `r"C:\path"` or `r#"say "hi""#`). The body is not processed
+    /// for escapes and ends at the first `"` followed by the same number of `#`s as the opening
+    /// quote was preceded by.
+    pub static ref RAW_STRING: (String, String) =
+        ("raw_string".into(), r#"r(#*)"(?s:.)*?"\1"#.into());
     /// An unsigned number (either an integer or a float).
     pub static ref UNSIGNED_NUMBER: (String, String) =
         ("unsigned_number".into(), format!("{FLOAT_BASE}|{INT_BASE}"));
@@ -68,16 +118,442 @@ lazy_static! {
     );
     /// Any integer value (optional sign).
     pub static ref INT: (String, String) = ("int".into(), format!(r"[+\-]?{INT_BASE}"));
+    /// Any integer value (optional sign) with commas as thousands separators (e.g. `12,345,678`).
+    /// The first group has one to three digits and every following group exactly three, so `1,00`
+    /// and `1,0000` aren't single integers. A number of four or more digits without separators
+    /// isn't matched either, use [`struct@INT`] for those.
+    pub static ref GROUPED_INT: (String, String) = (
+        "grouped_int".into(),
+        r"[+\-]?[0-9]{1,3}(?:,[0-9]{3})*(?![0-9])".into()
+    );
     /// Any floating point value (optional sign).
     pub static ref FLOAT: (String, String) = ("float".into(), format!(r"[+\-]?(?:{FLOAT_BASE})"));
+    /// A floating point value in scientific notation (e.g. `6.022e23` or `-1E-3`), i.e. with the
+    /// same syntax as [`struct@FLOAT`], but with a mandatory exponent, so plain decimals like
+    /// `3.14` aren't matched.
+    pub static ref SCIENTIFIC: (String, String) = (
+        "scientific".into(),
+        format!(r"[+\-]?(?:{INT_BASE}(?:\.(?:{INT_BASE})?)?|\.{INT_BASE})[eE][+\-]?{INT_BASE}")
+    );
     /// Any number (optional sign).
     pub static ref NUMBER: (String, String) = (
         "number".into(),
         format!(r"[+\-]?(?:(?:{FLOAT_BASE})|{INT_BASE})")
     );
+    /// A boolean value, i.e. `true` or `false` (case sensitive). Unlike a literal, it doesn't
+    /// match the beginning of a longer name (e.g. `truer`), as it can't be followed by a letter,
+    /// digit, or underscore.
+    pub static ref BOOL: (String, String) =
+        ("bool".into(), r"(?:true|false)(?![_A-Za-z0-9])".into());
+    /// The null value, i.e. `null` (case sensitive). Just like [`struct@BOOL`], it doesn't match
+    /// the beginning of a longer name (e.g. `nullable`).
+    pub static ref NULL: (String, String) = ("null".into(), r"null(?![_A-Za-z0-9])".into());
+    /// A number as defined by the JSON grammar (e.g. `-12.5e3`). Unlike [`struct@NUMBER`], it
+    /// can't start with `+` or with a leading zero (e.g. `01`), the dot has to be both preceded
+    /// and followed by digits, and underscores aren't allowed.
+    pub static ref JSON_NUMBER: (String, String) = (
+        "json_number".into(),
+        r"-?(?:0|[1-9][0-9]*)(?:\.[0-9]+)?(?:[eE][+\-]?[0-9]+)?(?![0-9])".into()
+    );
+    /// A string as defined by the JSON grammar, i.e. enclosed in double quotes, without control
+    /// characters, and only with the escapes `\"`, `\\`, `\/`, `\b`, `\f`, `\n`, `\r`, `\t`, and
+    /// `\uXXXX`.
+    pub static ref JSON_STRING: (String, String) = (
+        "json_string".into(),
+        r#""(?:[^"\\\x00-\x1f]|\\(?:["\\/bfnrt]|u[0-9A-Fa-f]{4}))*""#.into()
+    );
+    /// A hexadecimal color as used in CSS (e.g. `#fff` or `#ff000080`), i.e. `#` followed by
+    /// 3, 4, 6, or 8 hexadecimal digits.
+    pub static ref HEX_COLOR: (String, String) = (
+        "hex_color".into(),
+        r"#(?:[0-9A-Fa-f]{8}|[0-9A-Fa-f]{6}|[0-9A-Fa-f]{3,4})(?![0-9A-Fa-f])".into()
+    );
+    /// A number followed by `%` (e.g. `12.5%`), with the same syntax as [`struct@NUMBER`].
+    pub static ref PERCENTAGE: (String, String) = (
+        "percentage".into(),
+        format!(r"[+\-]?(?:(?:{FLOAT_BASE})|{INT_BASE})%")
+    );
+    /// A number followed by a unit (e.g. `10px` or `-1.5em`), with the same syntax as
+    /// [`struct@NUMBER`]. The unit consists of English letters.
+    pub static ref DIMENSION: (String, String) = (
+        "dimension".into(),
+        format!(r"[+\-]?(?:(?:{FLOAT_BASE})|{INT_BASE})[A-Za-z]+")
+    );
+    /// An IPv4 address in the dotted decimal notation (e.g. `192.168.0.1`). Octets above 255 and
+    /// octets with leading zeros aren't allowed.
+    pub static ref IPV4: (String, String) = (
+        "ipv4".into(),
+        format!(r"{IPV4_OCTET}(?:\.{IPV4_OCTET}){{3}}(?![0-9])")
+    );
+    /// An IPv6 address (e.g. `2001:db8:0:0:0:0:0:1`), optionally compressed with a single `::`
+    /// (e.g. `2001:db8::1`, `::1`, or `::`). Addresses with an embedded IPv4 address (e.g.
+    /// `::ffff:1.2.3.4`) aren't supported.
+    pub static ref IPV6: (String, String) = {
+        let group = IPV6_GROUP;
+        let alternatives = [
+            format!("(?:{group}:){{7}}{group}"),
+            format!("(?:{group}:){{1,7}}:"),
+            format!("(?:{group}:){{1,6}}:{group}"),
+            format!("(?:{group}:){{1,5}}(?::{group}){{1,2}}"),
+            format!("(?:{group}:){{1,4}}(?::{group}){{1,3}}"),
+            format!("(?:{group}:){{1,3}}(?::{group}){{1,4}}"),
+            format!("(?:{group}:){{1,2}}(?::{group}){{1,5}}"),
+            format!("{group}:(?::{group}){{1,6}}"),
+            format!("::(?:{group}(?::{group}){{0,6}})?"),
+        ];
+        // the lookahead makes the alternation backtrack instead of stopping at a shorter prefix
+        ("ipv6".into(), format!("(?:{})(?![0-9A-Fa-f:])", alternatives.join("|")))
+    };
+    /// A MAC address as six pairs of hexadecimal digits, separated either all by `:` or all by `-`
+    /// (e.g. `00:1a:2b:3c:4d:5e` or `00-1A-2B-3C-4D-5E`).
+    pub static ref MAC_ADDRESS: (String, String) = (
+        "mac_address".into(),
+        r"[0-9A-Fa-f]{2}(?:(?::[0-9A-Fa-f]{2}){5}|(?:-[0-9A-Fa-f]{2}){5})(?![0-9A-Fa-f])".into()
+    );
+    /// An ISO 8601 calendar date as `YYYY-MM-DD` (e.g. `2024-02-29`). Months are limited to
+    /// `01`–`12` and days to `01`–`31`, but the number of days in the month isn't checked.
+    pub static ref DATE_ISO: (String, String) =
+        ("date_iso".into(), format!("{DATE_BASE}(?![0-9])"));
+    /// An ISO 8601 time of day as `HH:MM`, optionally followed by `:SS` and a decimal fraction
+    /// (e.g. `23:59`, `08:30:00`, or `12:00:00.250`). Hours are limited to `00`–`23`.
+    pub static ref TIME_ISO: (String, String) =
+        ("time_iso".into(), format!("{TIME_BASE}(?![0-9])"));
+    /// An ISO 8601 date and time joined by `T`, as in [`struct@DATE_ISO`] and
+    /// [`struct@TIME_ISO`], optionally followed by a timezone, either `Z` or an offset like
+    /// `+02:00` (e.g. `2024-02-29T23:59:59.5+02:00`).
+    pub static ref DATETIME_ISO: (String, String) = (
+        "datetime_iso".into(),
+        format!(r"{DATE_BASE}T{TIME_BASE}(?:Z|[+\-](?:[01][0-9]|2[0-3]):[0-5][0-9])?(?![0-9])")
+    );
+    /// A UUID as 32 hexadecimal digits in groups of 8-4-4-4-12 separated by `-` (e.g.
+    /// `123e4567-e89b-12d3-a456-426614174000`). Both lowercase and uppercase digits are allowed.
+    pub static ref UUID: (String, String) = (
+        "uuid".into(),
+        concat!(
+            r"[0-9A-Fa-f]{8}-[0-9A-Fa-f]{4}-[0-9A-Fa-f]{4}-[0-9A-Fa-f]{4}-[0-9A-Fa-f]{12}",
+            r"(?![0-9A-Fa-f])",
+        )
+        .into()
+    );
+    /// A semantic version as defined by [semver.org](https://semver.org) (e.g. `1.0.0`,
+    /// `1.0.0-alpha.1`, or `1.0.0+build.5`). Numbers, including numeric pre-release identifiers,
+    /// can't have leading zeros.
+    pub static ref SEMVER: (String, String) = (
+        "semver".into(),
+        format!(
+            concat!(
+                r"{n}\.{n}\.{n}",                              // version core
+                r"(?:-{id}(?:\.{id})*)?",                      // pre-release (optional)
+                r"(?:\+[0-9A-Za-z\-]+(?:\.[0-9A-Za-z\-]+)*)?", // build metadata (optional)
+                r"(?![0-9A-Za-z\-])",
+            ),
+            n = SEMVER_NUMBER,
+            id = SEMVER_IDENTIFIER,
+        )
+    );
+    /// A shell-style path with `/` separators (e.g. `/usr/local/bin`, `./a/b`, or
+    /// `my\ dir/file`). It has to contain at least one `/` followed by a segment, so plain words
+    /// aren't paths, and may end with a `/`. A backslash escapes the following character (e.g. a
+    /// space), other whitespace and the shell metacharacters `;|&<>()'"` and `` ` `` end the path.
+    ///
+    /// Note that e.g. `a/b` is a path as well, so with a division operator, the path should only
+    /// be used in contexts where paths are expected.
+    pub static ref UNIX_PATH: (String, String) = (
+        "unix_path".into(),
+        format!(r"{c}*(?:/{c}+)+/?", c = r#"(?:[^\s/\\;|&<>()'"`]|\\.)"#)
+    );
+    /// A Windows path with `\` separators and an optional drive or UNC prefix (e.g.
+    /// `C:\Windows`, `C:\Program Files\x`, `\\server\share`, or `.\a\b`). It has to contain at
+    /// least one `\` followed by a segment (or be just a drive root like `C:\`) and may end with
+    /// a `\`.
+    ///
+    /// Segments can't contain the characters reserved by Windows (`<>:"/\|?*`). Segments after
+    /// the first `\` can contain spaces (but not start or end with them), so a path followed by
+    /// a space and another word on the same line is matched as a single path.
+    pub static ref WINDOWS_PATH: (String, String) = (
+        "windows_path".into(),
+        format!(
+            r"(?:[A-Za-z]:|\\\\)?{c}*(?:\\{c}(?:{s}*{c})?)+\\?|[A-Za-z]:\\",
+            c = r#"[^\s<>:"/\\|?*]"#,
+            s = r#"[^\r\n<>:"/\\|?*]"#,
+        )
+    );
+    /// A shell-style environment variable reference, either bare (e.g. `$HOME`) or braced (e.g.
+    /// `${PATH}`), where the name follows the rules of [`struct@C_NAME`]. The braced form can
+    /// contain a default value (e.g. `${EDITOR:-vi}`), which can't contain a `}` (i.e. defaults
+    /// can't be nested).
+    pub static ref ENV_VAR: (String, String) = (
+        "env_var".into(),
+        format!(r"\$(?:\{{{n}(?::-[^}}]*)?\}}|{n})", n = r"[_A-Za-z][_A-Za-z\d]*")
+    );
+    /// An operator (e.g. `<=` or `+`). Consists of common multi-character operators (e.g. `<<=`,
+    /// `==`, `=>`, `&&`, or `::`), which are preferred over the single-character ones
+    /// (`+-*/%<>=!&|^~`).
+    pub static ref OPERATOR: (String, String) = (
+        "operator".into(),
+        concat!(
+            r"<<=|>>=|\*\*=|\.\.\.|\.\.=|===|!==|<=>",
+            r"|==|!=|<=|>=|=>|->|<-|&&|\|\||<<|>>|\+\+|--|\*\*|::|\.\.|\?\?",
+            r"|\+=|-=|\*=|/=|%=|&=|\|=|\^=",
+            r"|[-+*/%<>=!&|^~]",
+        )
+        .into()
+    );
+    /// A single bracket, i.e. one of `()[]{}`.
+    pub static ref BRACKET: (String, String) = ("bracket".into(), r"[()\[\]{}]".into());
+    /// A single Unicode punctuation character (e.g. `,`, `¿`, or `»`).
+    pub static ref PUNCT: (String, String) = ("punct".into(), r"\p{P}".into());
+    /// A line comment starting with either `//` or `#` (e.g. `// TODO`). Doesn't include the
+    /// line break (not even the `\r` of `\r\n`), so it can still be matched by e.g.
+    /// [`struct@NEWLINE`].
+    pub static ref LINE_COMMENT: (String, String) =
+        ("line_comment".into(), r"(?://|#)[^\r\n]*".into());
+    /// A C-like block comment (e.g. `/* hi */`). Can span multiple lines. Doesn't support nesting,
+    /// i.e. it ends at the first `*/`.
+    pub static ref BLOCK_COMMENT: (String, String) =
+        ("block_comment".into(), r"/\*(?s:.)*?\*/".into());
+    /// A documentation block comment (e.g. `/** Returns the answer. */`). Doesn't match regular
+    /// block comments (`/* ... */`), `/***/`, nor the empty `/**/`, so it can be safely given
+    /// a higher priority than a regular block comment pattern.
+    pub static ref DOC_BLOCK_COMMENT: (String, String) = (
+        "doc_block_comment".into(),
+        r"/\*\*(?![*/])(?:[^*]|\*(?!/))*\*/".into()
+    );
+}
+
+/// Generates an integer pattern for the given `radix` (e.g. `[0-2]+` for base 3). Digits above 9
+/// are English letters and are case insensitive, just like in [`struct@HEX_INT`].
+///
+/// If `allow_underscores` is set, underscores can be used as separators, following the same rules
+/// as [`struct@UNSIGNED_INT`]. If `signed` is set, the integer has to be prefixed by a sign, just
+/// like [`struct@SIGNED_INT`].
+///
+/// # Panics
+///
+/// Panics if `radix` is not in the range `2..=36`.
+///
+/// # Examples
+/// ```rust
+/// # use crossandra::common;
+/// let (name, pattern) = common::int_pattern(12, false, false);
+/// assert_eq!(name, "unsigned_base12_int");
+/// assert_eq!(pattern, "[0-9A-Ba-b]+");
+/// ```
+#[must_use]
+pub fn int_pattern(radix: u32, allow_underscores: bool, signed: bool) -> (String, String) {
+    assert!(
+        (2..=36).contains(&radix),
+        "radix must be in the range 2..=36, got {radix}"
+    );
+
+    let last_digit = |first: char, offset: u32| char::from_u32(first as u32 + offset).unwrap();
+    let digits = if radix <= 10 {
+        format!("0-{}", last_digit('0', radix - 1))
+    } else {
+        let upper = last_digit('A', radix - 11);
+        let lower = last_digit('a', radix - 11);
+        format!("0-9A-{upper}a-{lower}")
+    };
+    let body = if allow_underscores {
+        format!("[{digits}](?:[{digits}_]*[{digits}])?")
+    } else {
+        format!("[{digits}]+")
+    };
+
+    if signed {
+        (format!("signed_base{radix}_int"), format!(r"[+\-]{body}"))
+    } else {
+        (format!("unsigned_base{radix}_int"), body)
+    }
+}
+
+/// Whether a float generated by [`float_pattern`] can end with a dot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TrailingDot {
+    /// The dot belongs to the float, so `1.` is a float and `1.e5` is a float with an exponent,
+    /// just like in [`struct@UNSIGNED_FLOAT`]. Note that `3..3` then becomes `3.` and `.3`.
+    Float,
+    /// The dot has to be followed by a digit, so `1.` isn't a float and is left for an integer
+    /// pattern and a separate dot token (e.g. for method calls or ranges). `1.e5` isn't a float
+    /// either, and `3..3` starts with `3` and `.` rather than `3.`.
+    Separate,
+}
+
+/// Generates a floating point pattern, with the trailing dot handled according to the given
+/// [`TrailingDot`]. If `signed` is set, the float has to be prefixed by a sign, just like
+/// [`struct@SIGNED_FLOAT`].
+///
+/// # Examples
+/// ```rust
+/// # use crossandra::common::{self, TrailingDot};
+/// # #[cfg(feature = "std")] {
+/// # use crossandra::Tokenizer;
+/// let tok = Tokenizer::default()
+///     .with_literals(&[("dot", ".")])
+///     .unwrap()
+///     .with_patterns(vec![
+///         common::float_pattern(false, TrailingDot::Separate),
+///         common::UNSIGNED_INT.clone(),
+///     ])
+///     .unwrap();
+/// let names: Vec<_> = tok.tokenize("1.").map(|t| t.unwrap().name).collect();
+/// assert_eq!(names, ["unsigned_int", "dot"]);
+/// # }
+///
+/// assert_eq!(
+///     common::float_pattern(false, TrailingDot::Float),
+///     *common::UNSIGNED_FLOAT
+/// );
+/// ```
+#[must_use]
+pub fn float_pattern(signed: bool, trailing_dot: TrailingDot) -> (String, String) {
+    let body = match trailing_dot {
+        TrailingDot::Float => FLOAT_BASE,
+        TrailingDot::Separate => FLOAT_BASE_NO_TRAILING_DOT,
+    };
+
+    if signed {
+        ("signed_float".into(), format!(r"[+\-](?:{body})"))
+    } else {
+        ("unsigned_float".into(), body.into())
+    }
+}
+
+/// Generates a line comment pattern for the given `prefix` (e.g. `--` or `;`), matching the prefix
+/// and the rest of the line. Just like [`struct@LINE_COMMENT`], it doesn't include the line break.
+///
+/// # Examples
+/// ```rust
+/// # use crossandra::common;
+/// let (name, pattern) = common::line_comment("--");
+/// assert_eq!(name, "line_comment");
+/// assert_eq!(pattern, r"--[^\r\n]*");
+/// ```
+#[must_use]
+pub fn line_comment(prefix: &str) -> (String, String) {
+    (
+        "line_comment".into(),
+        format!(r"{}[^\r\n]*", escape_regex(prefix)),
+    )
+}
+
+/// Generates a keyword pattern named after the given `word`, which only matches the word when
+/// it isn't followed by a letter, digit, or underscore, just like [`struct@BOOL`].
+///
+/// Patterns are only anchored to the start of the remaining source, so a plain `if` pattern (or
+/// literal) matches the beginning of `iffy`, splitting it into `if` and `fy`. With the keyword
+/// pattern, `iffy` is left for an identifier pattern instead.
+///
+/// # Examples
+/// ```rust
+/// # use crossandra::common;
+/// let (name, pattern) = common::keyword("if");
+/// assert_eq!(name, "if");
+/// assert_eq!(pattern, "if(?![_A-Za-z0-9])");
+///
+/// # #[cfg(feature = "std")] {
+/// # use crossandra::Tokenizer;
+/// let tok = Tokenizer::default()
+///     .with_patterns(vec![common::keyword("if"), common::C_NAME.clone()])
+///     .unwrap()
+///     .with_ignore_whitespace(true);
+/// let names: Vec<_> = tok.tokenize("if iffy").map(|t| t.unwrap().name).collect();
+/// assert_eq!(names, ["if", "c_name"]);
+/// # }
+/// ```
+#[must_use]
+pub fn keyword(word: &str) -> (String, String) {
+    (
+        word.into(),
+        format!("{}(?![_A-Za-z0-9])", escape_regex(word)),
+    )
+}
+
+/// Generates a character literal pattern enclosed in the given `quote` (e.g. `` `h` ``), matching
+/// exactly one character or an escaped quote, just like [`struct@CHAR`] does for single quotes.
+///
+/// # Examples
+/// ```rust
+/// # use crossandra::common;
+/// let (name, pattern) = common::char_in('`');
+/// assert_eq!(name, "char");
+/// assert_eq!(pattern, r"`(?:\\`|[^`])`");
+/// assert_eq!(common::char_in('\''), *common::CHAR);
+/// ```
+#[must_use]
+pub fn char_in(quote: char) -> (String, String) {
+    let quote = escape_regex(quote.encode_utf8(&mut [0; 4]));
+    (
+        "char".into(),
+        format!(r"{quote}(?:\\{quote}|[^{quote}]){quote}"),
+    )
+}
+
+/// How a quote is escaped inside a string generated by [`quoted_string`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EscapeStyle {
+    /// A backslash escapes any character, including the quote (e.g. `'it\'s'`), just like in
+    /// [`struct@SINGLE_QUOTED_STRING`].
+    Backslash,
+    /// The quote is escaped by doubling it (e.g. `'it''s'` in SQL or `"a""b"` in CSV), backslashes
+    /// have no special meaning.
+    Doubled,
+}
+
+/// Generates a string pattern enclosed in the given `quote`, with the quote escaped according to
+/// the given [`EscapeStyle`].
+///
+/// # Examples
+/// ```rust
+/// # use crossandra::common::{self, EscapeStyle};
+/// let (name, pattern) = common::quoted_string('\'', EscapeStyle::Doubled);
+/// assert_eq!(name, "quoted_string");
+/// assert_eq!(pattern, "'(?:[^']|'')*'");
+/// assert_eq!(
+///     common::quoted_string('\'', EscapeStyle::Backslash).1,
+///     common::SINGLE_QUOTED_STRING.1
+/// );
+/// ```
+#[must_use]
+pub fn quoted_string(quote: char, escape: EscapeStyle) -> (String, String) {
+    let quote = escape_regex(quote.encode_utf8(&mut [0; 4]));
+    let pattern = match escape {
+        EscapeStyle::Backslash => format!("{quote}{STRING_BASE}{quote}"),
+        EscapeStyle::Doubled => format!("{quote}(?:[^{quote}]|{quote}{quote})*{quote}"),
+    };
+    ("quoted_string".into(), pattern)
+}
+
+/// Escapes the characters with a special meaning in a regex, so that the result matches exactly
+/// the given `text`.
+fn escape_regex(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(
+            c,
+            '\\' | '.'
+                | '+'
+                | '*'
+                | '?'
+                | '('
+                | ')'
+                | '|'
+                | '['
+                | ']'
+                | '{'
+                | '}'
+                | '^'
+                | '$'
+                | '#'
+        ) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "std"))]
 mod tests {
     use crate::{common, error::Error, Tokenizer};
 
@@ -99,6 +575,13 @@ mod tests {
                     assert_eq!(err_value, expected_err_value);
                     assert_eq!(err_position, expected_err_position);
                 }
+                (
+                    Some(Err(Error::Unterminated { start, .. })),
+                    Err((expected_err_value, expected_err_position)),
+                ) => {
+                    assert_eq!(inp[start..].chars().next(), Some(expected_err_value));
+                    assert_eq!(start, expected_err_position);
+                }
                 (None, Ok(expected_values)) => {
                     let values = tokenizer
                         .tokenize(inp)
@@ -177,6 +660,90 @@ mod tests {
         );
     }
 
+    #[test]
+    fn keyword() {
+        test_patterns(
+            &prepare_tokenizer(common::keyword("if")),
+            vec![
+                ("if", Ok(vec!["if"])),
+                ("iffy", Err(('i', 0))),
+                ("if_", Err(('i', 0))),
+                ("if2", Err(('i', 0))),
+                ("if(", Err(('(', 2))),
+                ("If", Err(('I', 0))),
+            ],
+        );
+        assert_eq!(common::keyword("a.b").1, r"a\.b(?![_A-Za-z0-9])");
+
+        let tok = Tokenizer::default()
+            .with_patterns(vec![common::keyword("if"), common::C_NAME.clone()])
+            .unwrap();
+        let names: Vec<_> = tok.tokenize("iffy").map(|t| t.unwrap().name).collect();
+        assert_eq!(names, ["c_name"]);
+    }
+
+    #[test]
+    fn char_in() {
+        test_patterns(
+            &prepare_tokenizer(common::char_in('`')),
+            vec![
+                ("`t`", Ok(vec!["`t`"])),
+                ("`'`", Ok(vec!["`'`"])),
+                ("```", Err(('`', 0))),
+                ("`\\``", Ok(vec!["`\\``"])),
+                ("`t", Err(('`', 0))),
+                ("'t'", Err(('\'', 0))),
+                ("`tt`", Err(('`', 0))),
+                ("``", Err(('`', 0))),
+            ],
+        );
+        test_patterns(
+            &prepare_tokenizer(common::char_in('|')),
+            vec![
+                ("|t|", Ok(vec!["|t|"])),
+                ("|\\||", Ok(vec!["|\\||"])),
+                ("||", Err(('|', 0))),
+                ("t", Err(('t', 0))),
+            ],
+        );
+        assert_eq!(common::char_in('\''), *common::CHAR);
+    }
+
+    #[test]
+    fn quoted_string() {
+        use common::EscapeStyle;
+
+        test_patterns(
+            &prepare_tokenizer(common::quoted_string('\'', EscapeStyle::Doubled)),
+            vec![
+                ("'it''s'", Ok(vec!["'it''s'"])),
+                ("''", Ok(vec!["''"])),
+                ("''''", Ok(vec!["''''"])),
+                ("'a''b'''", Ok(vec!["'a''b'''"])),
+                ("'a\\'", Ok(vec!["'a\\'"])),
+                ("'a''", Err(('\'', 3))),
+                ("'a'b'", Err(('b', 3))),
+            ],
+        );
+        test_patterns(
+            &prepare_tokenizer(common::quoted_string('"', EscapeStyle::Doubled)),
+            vec![
+                (r#""a""b""#, Ok(vec![r#""a""b""#])),
+                (r#""x"",y""#, Ok(vec![r#""x"",y""#])),
+                (r#""a\"b"#, Err(('b', 4))),
+            ],
+        );
+        test_patterns(
+            &prepare_tokenizer(common::quoted_string('`', EscapeStyle::Backslash)),
+            vec![
+                ("`a\\`b`", Ok(vec!["`a\\`b`"])),
+                ("``", Ok(vec!["``"])),
+                ("`a``b`", Ok(vec!["`a`", "`b`"])),
+                ("`a", Err(('`', 0))),
+            ],
+        );
+    }
+
     #[test]
     fn letter() {
         test_patterns(
@@ -241,6 +808,279 @@ mod tests {
         );
     }
 
+    #[test]
+    fn triple_quoted_string() {
+        test_patterns(
+            &prepare_tokenizer(common::TRIPLE_QUOTED_STRING.clone()),
+            vec![
+                (r#""""test""""#, Ok(vec![r#""""test""""#])),
+                ("'''test'''", Ok(vec!["'''test'''"])),
+                ("''''''", Ok(vec!["''''''"])),
+                ("\"\"\"a\nb\r\nc\"\"\"", Ok(vec!["\"\"\"a\nb\r\nc\"\"\""])),
+                (r#""""a"b""c""""#, Ok(vec![r#""""a"b""c""""#])),
+                ("'''it's '' ok'''", Ok(vec!["'''it's '' ok'''"])),
+                (r#""""a\"""b""""#, Ok(vec![r#""""a\"""b""""#])),
+                (r#""""a"""b"#, Err(('b', 7))),
+                (r#""""a'''"#, Err(('"', 0))),
+                (r#""test""#, Err(('"', 0))),
+                ("'''test", Err(('\'', 0))),
+            ],
+        );
+    }
+
+    #[test]
+    fn raw_string() {
+        test_patterns(
+            &prepare_tokenizer(common::RAW_STRING.clone()),
+            vec![
+                (r#"r"x""#, Ok(vec![r#"r"x""#])),
+                (r#"r"""#, Ok(vec![r#"r"""#])),
+                (r##"r#"a"b"#"##, Ok(vec![r##"r#"a"b"#"##])),
+                (r###"r##"a"#b"##"###, Ok(vec![r###"r##"a"#b"##"###])),
+                (r#"r"\"r"\n""#, Ok(vec![r#"r"\""#, r#"r"\n""#])),
+                ("r\"a\nb\"", Ok(vec!["r\"a\nb\""])),
+                (r##"r"x"#"##, Err(('#', 4))),
+                (r##"r#"a""##, Err(('r', 0))),
+                (r#"r"x"#, Err(('r', 0))),
+                (r#""x""#, Err(('"', 0))),
+                ("rx", Err(('r', 0))),
+            ],
+        );
+    }
+
+    #[test]
+    fn line_comment() {
+        test_patterns(
+            &prepare_tokenizer(common::LINE_COMMENT.clone()),
+            vec![
+                ("// hello", Ok(vec!["// hello"])),
+                ("#!/bin/sh", Ok(vec!["#!/bin/sh"])),
+                ("//", Ok(vec!["//"])),
+                ("// a\n", Err(('\n', 4))),
+                ("# a\r\n", Err(('\r', 3))),
+                ("/ a", Err(('/', 0))),
+            ],
+        );
+        test_patterns(
+            &prepare_tokenizer(common::line_comment(";")),
+            vec![(";; x // y", Ok(vec![";; x // y"])), ("# x", Err(('#', 0)))],
+        );
+        test_patterns(
+            &prepare_tokenizer(common::line_comment("--")),
+            vec![("-- x", Ok(vec!["-- x"])), ("- x", Err(('-', 0)))],
+        );
+    }
+
+    #[test]
+    fn line_comment_before_newline() {
+        let tokenizer = Tokenizer::default()
+            .with_patterns(vec![
+                common::LINE_COMMENT.clone(),
+                common::NEWLINE.clone(),
+                common::C_NAME.clone(),
+            ])
+            .unwrap();
+        for (source, newline) in [("# hi\nx", "\n"), ("# hi\r\nx", "\r\n")] {
+            let tokens: Vec<_> = tokenizer
+                .tokenize(source)
+                .map(|token| token.map(|token| (token.name, token.value)))
+                .collect::<Result<_, _>>()
+                .unwrap();
+            assert_eq!(
+                tokens,
+                [
+                    ("line_comment", "# hi"),
+                    ("newline", newline),
+                    ("c_name", "x")
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn block_comment() {
+        test_patterns(
+            &prepare_tokenizer(common::BLOCK_COMMENT.clone()),
+            vec![
+                ("/* x */", Ok(vec!["/* x */"])),
+                ("/**/", Ok(vec!["/**/"])),
+                ("/* a\nb */", Ok(vec!["/* a\nb */"])),
+                ("/* /* */", Ok(vec!["/* /* */"])),
+                ("/* /* */ */", Err((' ', 8))),
+                ("/*/", Err(('/', 0))),
+                ("/* x", Err(('/', 0))),
+            ],
+        );
+    }
+
+    #[test]
+    fn unicode_name() {
+        let tokenizer = prepare_tokenizer(common::UNICODE_NAME.clone());
+        test_patterns(
+            &tokenizer,
+            vec![
+                ("café", Ok(vec!["café"])),
+                ("λ", Ok(vec!["λ"])),
+                ("wórd", Ok(vec!["wórd"])),
+                // `e` followed by a combining acute accent
+                ("cafe\u{301}", Ok(vec!["cafe\u{301}"])),
+                ("_ñ0", Ok(vec!["_ñ0"])),
+                ("\u{301}a", Err(('\u{301}', 0))),
+                ("٥x", Err(('٥', 0))),
+                ("a-b", Err(('-', 1))),
+            ],
+        );
+
+        let c_name = prepare_tokenizer(common::C_NAME.clone());
+        for source in ["crossandra_rocks", "_0", "x1_y", "__", "0a", "a b", "a-b"] {
+            let values = |tok: &Tokenizer| {
+                tok.tokenize(source)
+                    .map(|t| t.map(|t| t.value.to_string()).map_err(|e| e.to_string()))
+                    .collect::<Vec<_>>()
+            };
+            assert_eq!(values(&tokenizer), values(&c_name));
+        }
+    }
+
+    #[test]
+    fn operator() {
+        test_patterns(
+            &prepare_tokenizer(common::OPERATOR.clone()),
+            vec![
+                ("<=", Ok(vec!["<="])),
+                ("==", Ok(vec!["=="])),
+                ("=>", Ok(vec!["=>"])),
+                ("<<=", Ok(vec!["<<="])),
+                ("===", Ok(vec!["==="])),
+                ("...", Ok(vec!["..."])),
+                ("<", Ok(vec!["<"])),
+                ("=<", Ok(vec!["=", "<"])),
+                ("+++", Ok(vec!["++", "+"])),
+                ("a", Err(('a', 0))),
+                ("(", Err(('(', 0))),
+            ],
+        );
+    }
+
+    #[test]
+    fn bracket() {
+        test_patterns(
+            &prepare_tokenizer(common::BRACKET.clone()),
+            vec![
+                ("()[]{}", Ok(vec!["(", ")", "[", "]", "{", "}"])),
+                ("((", Ok(vec!["(", "("])),
+                ("<", Err(('<', 0))),
+            ],
+        );
+    }
+
+    #[test]
+    fn punct() {
+        test_patterns(
+            &prepare_tokenizer(common::PUNCT.clone()),
+            vec![
+                (",.;!?", Ok(vec![",", ".", ";", "!", "?"])),
+                ("¿«»", Ok(vec!["¿", "«", "»"])),
+                ("(-)", Ok(vec!["(", "-", ")"])),
+                ("+", Err(('+', 0))),
+                ("a", Err(('a', 0))),
+            ],
+        );
+    }
+
+    #[test]
+    fn int_pattern() {
+        assert_eq!(
+            common::int_pattern(10, true, false).1,
+            common::UNSIGNED_INT.1
+        );
+        assert_eq!(common::int_pattern(10, true, true).1, common::SIGNED_INT.1);
+        assert_eq!(
+            common::int_pattern(16, true, false).1,
+            common::HEX_INT.1.trim_start_matches("0[xX]")
+        );
+        assert_eq!(common::int_pattern(2, false, false).1, "[0-1]+");
+        assert_eq!(common::int_pattern(36, false, false).1, "[0-9A-Za-z]+");
+
+        test_patterns(
+            &prepare_tokenizer(common::int_pattern(3, false, false)),
+            vec![
+                ("0120", Ok(vec!["0120"])),
+                ("2_1", Err(('_', 1))),
+                ("123", Err(('3', 2))),
+            ],
+        );
+        test_patterns(
+            &prepare_tokenizer(common::int_pattern(12, true, true)),
+            vec![
+                ("+9aB", Ok(vec!["+9aB"])),
+                ("-1_b", Ok(vec!["-1_b"])),
+                ("+bc", Err(('c', 2))),
+                ("10", Err(('1', 0))),
+            ],
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "radix must be in the range 2..=36")]
+    fn int_pattern_invalid_radix() {
+        let _ = common::int_pattern(37, false, false);
+    }
+
+    #[test]
+    fn bin_int() {
+        test_patterns(
+            &prepare_tokenizer(common::BIN_INT.clone()),
+            vec![
+                ("0b1010", Ok(vec!["0b1010"])),
+                ("0B1", Ok(vec!["0B1"])),
+                ("0b1_000_0", Ok(vec!["0b1_000_0"])),
+                ("0b", Err(('0', 0))),
+                ("0b2", Err(('0', 0))),
+                ("0b_1", Err(('0', 0))),
+                ("0b1_", Err(('_', 3))),
+                ("1010", Err(('1', 0))),
+            ],
+        );
+    }
+
+    #[test]
+    fn oct_int() {
+        test_patterns(
+            &prepare_tokenizer(common::OCT_INT.clone()),
+            vec![
+                ("0o755", Ok(vec!["0o755"])),
+                ("0O0", Ok(vec!["0O0"])),
+                ("0o7_5__5", Ok(vec!["0o7_5__5"])),
+                ("0o", Err(('0', 0))),
+                ("0o8", Err(('0', 0))),
+                ("0o_7", Err(('0', 0))),
+                ("0o7_", Err(('_', 3))),
+                ("0o78", Err(('8', 3))),
+            ],
+        );
+    }
+
+    #[test]
+    fn hex_int() {
+        test_patterns(
+            &prepare_tokenizer(common::HEX_INT.clone()),
+            vec![
+                ("0xDE_AD", Ok(vec!["0xDE_AD"])),
+                ("0Xff", Ok(vec!["0Xff"])),
+                (
+                    "0x0123456789abcdefABCDEF",
+                    Ok(vec!["0x0123456789abcdefABCDEF"]),
+                ),
+                ("0x", Err(('0', 0))),
+                ("0xg", Err(('0', 0))),
+                ("0x_FF", Err(('0', 0))),
+                ("0xFF_", Err(('_', 4))),
+                ("0xFG", Err(('G', 3))),
+            ],
+        );
+    }
+
     #[test]
     fn signed_int() {
         test_patterns(
@@ -347,6 +1187,145 @@ mod tests {
         );
     }
 
+    #[test]
+    fn float_pattern() {
+        use common::TrailingDot;
+
+        assert_eq!(
+            common::float_pattern(false, TrailingDot::Float),
+            *common::UNSIGNED_FLOAT
+        );
+        assert_eq!(
+            common::float_pattern(true, TrailingDot::Float),
+            *common::SIGNED_FLOAT
+        );
+
+        test_patterns(
+            &prepare_tokenizer(common::float_pattern(false, TrailingDot::Separate)),
+            vec![
+                ("13", Err(('1', 0))),
+                ("13.", Err(('1', 0))),
+                (".13", Ok(vec![".13"])),
+                ("1.5", Ok(vec!["1.5"])),
+                ("1e3", Ok(vec!["1e3"])),
+                ("1.e5", Err(('1', 0))),
+                ("1_0.5_0e-3_0", Ok(vec!["1_0.5_0e-3_0"])),
+            ],
+        );
+        test_patterns(
+            &prepare_tokenizer(common::float_pattern(true, TrailingDot::Separate)),
+            vec![
+                ("-1.5", Ok(vec!["-1.5"])),
+                ("+.5", Ok(vec!["+.5"])),
+                ("-1.", Err(('-', 0))),
+            ],
+        );
+
+        let tok = Tokenizer::default()
+            .with_literals(&[("dot", ".")])
+            .unwrap()
+            .with_patterns(vec![
+                common::float_pattern(false, TrailingDot::Separate),
+                common::UNSIGNED_INT.clone(),
+            ])
+            .unwrap();
+        test_patterns(
+            &tok,
+            vec![
+                ("1.", Ok(vec!["1", "."])),
+                ("3..3", Ok(vec!["3", ".", ".", "3"])),
+                ("1.5.", Ok(vec!["1.5", "."])),
+            ],
+        );
+    }
+
+    #[test]
+    fn scientific() {
+        test_patterns(
+            &prepare_tokenizer(common::SCIENTIFIC.clone()),
+            vec![
+                ("6.022e23", Ok(vec!["6.022e23"])),
+                ("1e-3", Ok(vec!["1e-3"])),
+                ("-1E+3", Ok(vec!["-1E+3"])),
+                (".5e1", Ok(vec![".5e1"])),
+                ("1.e5", Ok(vec!["1.e5"])),
+                ("1_000.5e1_0", Ok(vec!["1_000.5e1_0"])),
+                ("3.14", Err(('3', 0))),
+                ("13.", Err(('1', 0))),
+                ("42", Err(('4', 0))),
+                ("1e", Err(('1', 0))),
+                ("1e3.5", Err(('.', 3))),
+            ],
+        );
+    }
+
+    #[test]
+    fn bool() {
+        test_patterns(
+            &prepare_tokenizer(common::BOOL.clone()),
+            vec![
+                ("true", Ok(vec!["true"])),
+                ("false", Ok(vec!["false"])),
+                ("truefalse", Err(('t', 0))),
+                ("True", Err(('T', 0))),
+                ("FALSE", Err(('F', 0))),
+                ("truer", Err(('t', 0))),
+                ("true_", Err(('t', 0))),
+                ("false1", Err(('f', 0))),
+                ("true)", Err((')', 4))),
+            ],
+        );
+    }
+
+    #[test]
+    fn null() {
+        test_patterns(
+            &prepare_tokenizer(common::NULL.clone()),
+            vec![
+                ("null", Ok(vec!["null"])),
+                ("Null", Err(('N', 0))),
+                ("nullable", Err(('n', 0))),
+                ("null,", Err((',', 4))),
+            ],
+        );
+    }
+
+    #[test]
+    fn json_number() {
+        test_patterns(
+            &prepare_tokenizer(common::JSON_NUMBER.clone()),
+            vec![
+                ("0", Ok(vec!["0"])),
+                ("-12.5e3", Ok(vec!["-12.5e3"])),
+                ("1E+2", Ok(vec!["1E+2"])),
+                ("0.5", Ok(vec!["0.5"])),
+                ("01", Err(('0', 0))),
+                ("+1", Err(('+', 0))),
+                ("1.", Err(('.', 1))),
+                (".5", Err(('.', 0))),
+                ("1_0", Err(('_', 1))),
+                ("-", Err(('-', 0))),
+            ],
+        );
+    }
+
+    #[test]
+    fn json_string() {
+        test_patterns(
+            &prepare_tokenizer(common::JSON_STRING.clone()),
+            vec![
+                (r#""""#, Ok(vec![r#""""#])),
+                (r#""a\"b""#, Ok(vec![r#""a\"b""#])),
+                (r#""\u00e9\n\/""#, Ok(vec![r#""\u00e9\n\/""#])),
+                (r#""žluť""#, Ok(vec![r#""žluť""#])),
+                (r#""\x""#, Err(('"', 0))),
+                (r#""\u12""#, Err(('"', 0))),
+                ("\"a\nb\"", Err(('"', 0))),
+                ("'a'", Err(('\'', 0))),
+            ],
+        );
+    }
+
     #[test]
     fn signed_float() {
         test_patterns(
@@ -406,6 +1385,24 @@ mod tests {
         );
     }
 
+    #[test]
+    fn grouped_int() {
+        test_patterns(
+            &prepare_tokenizer(common::GROUPED_INT.clone()),
+            vec![
+                ("1,000", Ok(vec!["1,000"])),
+                ("12,345,678", Ok(vec!["12,345,678"])),
+                ("-999+100,000", Ok(vec!["-999", "+100,000"])),
+                ("0", Ok(vec!["0"])),
+                ("1,00", Err((',', 1))),
+                ("1,0000", Err((',', 1))),
+                ("12,345,67", Err((',', 6))),
+                ("1000", Err(('1', 0))),
+                ("1_000", Err(('_', 1))),
+            ],
+        );
+    }
+
     #[test]
     fn float() {
         test_patterns(
@@ -449,4 +1446,315 @@ mod tests {
             ],
         );
     }
+
+    #[test]
+    fn hex_color() {
+        test_patterns(
+            &prepare_tokenizer(common::HEX_COLOR.clone()),
+            vec![
+                ("#fff", Ok(vec!["#fff"])),
+                ("#fFf0", Ok(vec!["#fFf0"])),
+                ("#a0B1c2", Ok(vec!["#a0B1c2"])),
+                ("#ffffffff", Ok(vec!["#ffffffff"])),
+                ("#fff#000", Ok(vec!["#fff", "#000"])),
+                ("#fffff", Err(('#', 0))),
+                ("#fffffffff", Err(('#', 0))),
+                ("#ff", Err(('#', 0))),
+                ("#ggg", Err(('#', 0))),
+                ("fff", Err(('f', 0))),
+            ],
+        );
+    }
+
+    #[test]
+    fn percentage() {
+        test_patterns(
+            &prepare_tokenizer(common::PERCENTAGE.clone()),
+            vec![
+                ("12.5%", Ok(vec!["12.5%"])),
+                ("100%", Ok(vec!["100%"])),
+                ("-.5%+1e2%", Ok(vec!["-.5%", "+1e2%"])),
+                ("12.5", Err(('1', 0))),
+                ("%", Err(('%', 0))),
+                ("5%%", Err(('%', 2))),
+            ],
+        );
+    }
+
+    #[test]
+    fn dimension() {
+        test_patterns(
+            &prepare_tokenizer(common::DIMENSION.clone()),
+            vec![
+                ("10px", Ok(vec!["10px"])),
+                ("-1.5em", Ok(vec!["-1.5em"])),
+                ("1em2rem", Ok(vec!["1em", "2rem"])),
+                ("1e3px", Ok(vec!["1e3px"])),
+                ("10", Err(('1', 0))),
+                ("10%", Err(('1', 0))),
+                ("px", Err(('p', 0))),
+            ],
+        );
+    }
+
+    #[test]
+    fn ipv4() {
+        test_patterns(
+            &prepare_tokenizer(common::IPV4.clone()),
+            vec![
+                ("0.0.0.0", Ok(vec!["0.0.0.0"])),
+                ("192.168.1.1", Ok(vec!["192.168.1.1"])),
+                ("255.255.255.255", Ok(vec!["255.255.255.255"])),
+                ("10.249.199.100", Ok(vec!["10.249.199.100"])),
+                ("256.0.0.1", Err(('2', 0))),
+                ("1.2.3.256", Err(('1', 0))),
+                ("1.300.3.4", Err(('1', 0))),
+                ("01.2.3.4", Err(('0', 0))),
+                ("1.2.3", Err(('1', 0))),
+                ("1.2.3.", Err(('1', 0))),
+                ("1.2.3.4.5", Err(('.', 7))),
+                ("1..3.4", Err(('1', 0))),
+            ],
+        );
+    }
+
+    #[test]
+    fn ipv6() {
+        test_patterns(
+            &prepare_tokenizer(common::IPV6.clone()),
+            vec![
+                ("::1", Ok(vec!["::1"])),
+                ("::", Ok(vec!["::"])),
+                ("1::", Ok(vec!["1::"])),
+                ("fe80::1", Ok(vec!["fe80::1"])),
+                ("1::2:3", Ok(vec!["1::2:3"])),
+                (
+                    "2001:db8::8a2e:370:7334",
+                    Ok(vec!["2001:db8::8a2e:370:7334"]),
+                ),
+                (
+                    "2001:0DB8:85a3:0000:0000:8a2e:0370:7334",
+                    Ok(vec!["2001:0DB8:85a3:0000:0000:8a2e:0370:7334"]),
+                ),
+                ("1:2:3:4:5:6:7::", Ok(vec!["1:2:3:4:5:6:7::"])),
+                ("::2:3:4:5:6:7:8", Ok(vec!["::2:3:4:5:6:7:8"])),
+                ("1::2::3", Err(('1', 0))),
+                ("1:2:3:4:5:6:7", Err(('1', 0))),
+                ("1:2:3:4:5:6:7:8:9", Err(('1', 0))),
+                ("1:2:3:4::5:6:7:8", Err(('1', 0))),
+                ("12345::", Err(('1', 0))),
+                ("g::1", Err(('g', 0))),
+                (":1", Err((':', 0))),
+                (":::", Err((':', 0))),
+            ],
+        );
+    }
+
+    #[test]
+    fn mac_address() {
+        test_patterns(
+            &prepare_tokenizer(common::MAC_ADDRESS.clone()),
+            vec![
+                ("ff:ff:ff:ff:ff:ff", Ok(vec!["ff:ff:ff:ff:ff:ff"])),
+                ("00-1A-2b-3C-4d-5E", Ok(vec!["00-1A-2b-3C-4d-5E"])),
+                ("ff:ff-ff:ff:ff:ff", Err(('f', 0))),
+                ("ff:ff:ff:ff:ff", Err(('f', 0))),
+                ("ff:ff:ff:ff:ff:fff", Err(('f', 0))),
+                ("ff:ff:ff:ff:ff:ff:ff", Err((':', 17))),
+                ("f:ff:ff:ff:ff:ff", Err(('f', 0))),
+                ("gg:ff:ff:ff:ff:ff", Err(('g', 0))),
+            ],
+        );
+    }
+
+    #[test]
+    fn date_iso() {
+        test_patterns(
+            &prepare_tokenizer(common::DATE_ISO.clone()),
+            vec![
+                ("2020-01-31", Ok(vec!["2020-01-31"])),
+                ("1999-12-01", Ok(vec!["1999-12-01"])),
+                ("2020-13-01", Err(('2', 0))),
+                ("2020-00-10", Err(('2', 0))),
+                ("2020-02-32", Err(('2', 0))),
+                ("2020-02-00", Err(('2', 0))),
+                ("2020-1-01", Err(('2', 0))),
+                ("2020-01-011", Err(('2', 0))),
+                ("20200101", Err(('2', 0))),
+            ],
+        );
+    }
+
+    #[test]
+    fn time_iso() {
+        test_patterns(
+            &prepare_tokenizer(common::TIME_ISO.clone()),
+            vec![
+                ("23:59", Ok(vec!["23:59"])),
+                ("00:00:00", Ok(vec!["00:00:00"])),
+                ("12:30:45.123", Ok(vec!["12:30:45.123"])),
+                ("24:00", Err(('2', 0))),
+                ("12:60", Err(('1', 0))),
+                ("12:30:60", Err((':', 5))),
+                ("12:30:45.", Err(('.', 8))),
+                ("1:30", Err(('1', 0))),
+            ],
+        );
+    }
+
+    #[test]
+    fn datetime_iso() {
+        test_patterns(
+            &prepare_tokenizer(common::DATETIME_ISO.clone()),
+            vec![
+                ("2020-01-31T23:59:59Z", Ok(vec!["2020-01-31T23:59:59Z"])),
+                (
+                    "2020-01-31T23:59:59.5+02:00",
+                    Ok(vec!["2020-01-31T23:59:59.5+02:00"]),
+                ),
+                ("2020-01-31T12:00-05:30", Ok(vec!["2020-01-31T12:00-05:30"])),
+                ("2020-01-31T12:00", Ok(vec!["2020-01-31T12:00"])),
+                ("2020-13-01T00:00", Err(('2', 0))),
+                ("2020-01-31T24:00", Err(('2', 0))),
+                ("2020-01-31 12:00", Err(('2', 0))),
+                ("2020-01-31", Err(('2', 0))),
+                ("2020-01-31T12:00+24:00", Err(('+', 16))),
+            ],
+        );
+    }
+
+    #[test]
+    fn uuid() {
+        test_patterns(
+            &prepare_tokenizer(common::UUID.clone()),
+            vec![
+                (
+                    "123e4567-e89b-12d3-a456-426614174000",
+                    Ok(vec!["123e4567-e89b-12d3-a456-426614174000"]),
+                ),
+                (
+                    "123E4567-E89B-12D3-A456-426614174000",
+                    Ok(vec!["123E4567-E89B-12D3-A456-426614174000"]),
+                ),
+                ("123e4567-e89b-12d3-a456-42661417400", Err(('1', 0))),
+                ("123e4567-e89b-12d3-a456-4266141740000", Err(('1', 0))),
+                ("123e4567e89b12d3a456426614174000", Err(('1', 0))),
+                ("g23e4567-e89b-12d3-a456-426614174000", Err(('g', 0))),
+            ],
+        );
+    }
+
+    #[test]
+    fn semver() {
+        test_patterns(
+            &prepare_tokenizer(common::SEMVER.clone()),
+            vec![
+                ("1.0.0", Ok(vec!["1.0.0"])),
+                ("0.10.20", Ok(vec!["0.10.20"])),
+                ("1.0.0-alpha.1", Ok(vec!["1.0.0-alpha.1"])),
+                ("1.0.0+build.5", Ok(vec!["1.0.0+build.5"])),
+                ("1.0.0-rc.1+001", Ok(vec!["1.0.0-rc.1+001"])),
+                ("1.0.0-0.3.7", Ok(vec!["1.0.0-0.3.7"])),
+                ("1.0.0-x-y-z.--", Ok(vec!["1.0.0-x-y-z.--"])),
+                ("1.0.0-0a.01b", Ok(vec!["1.0.0-0a.01b"])),
+                ("01.0.0", Err(('0', 0))),
+                ("1.02.0", Err(('1', 0))),
+                ("1.0.0-01", Err(('1', 0))),
+                ("1.0.0-", Err(('1', 0))),
+                ("1.0.0+", Err(('+', 5))),
+                ("1.0", Err(('1', 0))),
+                ("1.0.0-alpha..1", Err(('.', 11))),
+            ],
+        );
+    }
+
+    #[test]
+    fn unix_path() {
+        test_patterns(
+            &prepare_tokenizer(common::UNIX_PATH.clone()),
+            vec![
+                ("/usr/local/bin", Ok(vec!["/usr/local/bin"])),
+                ("./a/b", Ok(vec!["./a/b"])),
+                ("../a", Ok(vec!["../a"])),
+                ("~/.config/", Ok(vec!["~/.config/"])),
+                ("a/b.txt", Ok(vec!["a/b.txt"])),
+                (r"/my\ dir/a\ b", Ok(vec![r"/my\ dir/a\ b"])),
+                ("/a;/b", Err((';', 2))),
+                ("/a b", Err((' ', 2))),
+                ("a", Err(('a', 0))),
+                ("/", Err(('/', 0))),
+                ("a//b", Err(('a', 0))),
+            ],
+        );
+    }
+
+    #[test]
+    fn windows_path() {
+        test_patterns(
+            &prepare_tokenizer(common::WINDOWS_PATH.clone()),
+            vec![
+                (r"C:\Program Files\x", Ok(vec![r"C:\Program Files\x"])),
+                (r"C:\Windows\", Ok(vec![r"C:\Windows\"])),
+                (r"C:\", Ok(vec![r"C:\"])),
+                (r"d:\a.txt", Ok(vec![r"d:\a.txt"])),
+                (r".\a\b", Ok(vec![r".\a\b"])),
+                (r"a\b", Ok(vec![r"a\b"])),
+                (r"\\server\share", Ok(vec![r"\\server\share"])),
+                (r"C:\a \b", Err((' ', 4))),
+                (r"C:\a|b", Err(('|', 4))),
+                ("C:", Err(('C', 0))),
+                ("a b", Err(('a', 0))),
+                ("C:/a", Err(('C', 0))),
+            ],
+        );
+    }
+
+    #[test]
+    fn env_var() {
+        test_patterns(
+            &prepare_tokenizer(common::ENV_VAR.clone()),
+            vec![
+                ("$HOME", Ok(vec!["$HOME"])),
+                ("${PATH}", Ok(vec!["${PATH}"])),
+                ("$_a1$B", Ok(vec!["$_a1", "$B"])),
+                ("${EDITOR:-vi}", Ok(vec!["${EDITOR:-vi}"])),
+                ("${A:-}", Ok(vec!["${A:-}"])),
+                ("${A:-a b:-c}", Ok(vec!["${A:-a b:-c}"])),
+                ("${}", Err(('$', 0))),
+                ("$", Err(('$', 0))),
+                ("$1", Err(('$', 0))),
+                ("${A B}", Err(('$', 0))),
+                ("${A:-${B}}", Err(('}', 9))),
+                ("${PATH", Err(('$', 0))),
+            ],
+        );
+    }
+
+    #[test]
+    fn doc_block_comment() {
+        test_patterns(
+            &prepare_tokenizer(common::DOC_BLOCK_COMMENT.clone()),
+            vec![
+                ("/** x */", Ok(vec!["/** x */"])),
+                ("/**x*/", Ok(vec!["/**x*/"])),
+                ("/** a * b **/", Ok(vec!["/** a * b **/"])),
+                (
+                    "/** multi\n * line\n */",
+                    Ok(vec!["/** multi\n * line\n */"]),
+                ),
+                ("/** x *//** y */", Ok(vec!["/** x */", "/** y */"])),
+                ("/* x */", Err(('/', 0))),
+                ("/**/", Err(('/', 0))),
+                ("/***/", Err(('/', 0))),
+                ("/** x", Err(('/', 0))),
+            ],
+        );
+    }
+
+    #[test]
+    fn escape_regex() {
+        for text in ["", "plain", r"a.b*c?(d)|[e]{f}^$#\\", "+-", "ü", "—#—"] {
+            assert_eq!(common::escape_regex(text), fancy_regex::escape(text));
+        }
+    }
 }
@@ -0,0 +1,280 @@
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use crate::{error::Error, patterns};
+
+/// A plain data description of a [`Tokenizer`](crate::Tokenizer), which can be turned into one
+/// using [`Tokenizer::from_config`](crate::Tokenizer::from_config).
+///
+/// With the `serde` feature enabled, the config can be (de)serialized, e.g. to define a tokenizer
+/// in a JSON or TOML file. All fields are optional when deserializing and default to the same
+/// values as in [`Tokenizer::default`](crate::Tokenizer::default).
+///
+/// ```rust
+/// # use crossandra::{Tokenizer, TokenizerConfig};
+/// let mut config = TokenizerConfig::default();
+/// config.literals.insert("add".into(), "+".into());
+/// config.patterns.push(("int".into(), r"\d+".into()));
+/// config.ignore_whitespace = true;
+///
+/// let tok = Tokenizer::from_config(&config).unwrap();
+/// assert_eq!(tok.try_tokenize("1 + 2").unwrap().len(), 3);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct TokenizerConfig {
+    /// The [literals](crate::Tokenizer#literals), mapping names to literal values.
+    pub literals: FxHashMap<String, String>,
+    /// The [patterns](crate::Tokenizer#patterns) as (name, regex) pairs, in matching order.
+    pub patterns: Vec<(String, String)>,
+    /// See [`ignored_characters`](crate::Tokenizer#ignored_characters).
+    pub ignored_characters: FxHashSet<char>,
+    /// See [`ignore_whitespace`](crate::Tokenizer#ignore_whitespace).
+    pub ignore_whitespace: bool,
+    /// See [`longest_match`](crate::Tokenizer#longest_match).
+    pub longest_match: bool,
+    /// See [`max_token_length`](crate::Tokenizer#max_token_length).
+    pub max_token_length: Option<usize>,
+    /// See [`backtrack_limit`](crate::Tokenizer#backtrack_limit).
+    pub backtrack_limit: Option<usize>,
+    /// See [`emit_eof`](crate::Tokenizer#emit_eof).
+    pub emit_eof: bool,
+    /// The allowed [escape sequences](crate::Tokenizer#escapes) as (pattern name, regex) pairs.
+    pub escapes: Vec<(String, String)>,
+    /// See [`allow_duplicate_names`](crate::Tokenizer#allow_duplicate_names).
+    pub allow_duplicate_names: bool,
+}
+
+impl TokenizerConfig {
+    /// Checks the config for problems without building a [`Tokenizer`](crate::Tokenizer), e.g. to
+    /// give a complete report on a user-provided config instead of failing on the first problem.
+    ///
+    /// Reports empty literals, duplicate pattern names and pattern names used by literals (unless
+    /// [`allow_duplicate_names`](TokenizerConfig::allow_duplicate_names) is set), invalid pattern
+    /// and escape regexes and patterns matching an empty string. Building a
+    /// [`Tokenizer`](crate::Tokenizer) from the config succeeds if there are no problems other than
+    /// [`Error::ZeroWidthPattern`]s.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use crossandra::{Error, TokenizerConfig};
+    /// let mut config = TokenizerConfig::default();
+    /// config.literals.insert("add".into(), String::new());
+    /// config.patterns.push(("int".into(), r"\d*".into()));
+    /// config.patterns.push(("int".into(), "(".into()));
+    ///
+    /// let errors = config.validate().unwrap_err();
+    /// assert!(matches!(
+    ///     &errors[..],
+    ///     [
+    ///         Error::EmptyLiteral,
+    ///         Error::DuplicatePattern(_),
+    ///         Error::ZeroWidthPattern(_),
+    ///         Error::InvalidRegex { .. },
+    ///     ]
+    /// ));
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// This function will return all the problems found: empty literals first, then duplicate
+    /// names, the problems of each pattern in their declaration order, and finally invalid
+    /// escape regexes.
+    pub fn validate(&self) -> Result<(), Vec<Error>> {
+        let mut errors = Vec::new();
+
+        if self.literals.values().any(String::is_empty) {
+            errors.push(Error::EmptyLiteral);
+        }
+
+        if !self.allow_duplicate_names {
+            let mut duplicates = FxHashSet::default();
+            for (i, (name, pattern)) in self.patterns.iter().enumerate() {
+                // a pattern matching just the literal of the same name, or repeating another
+                // pattern, is redundant rather than ambiguous
+                let clashes = self
+                    .literals
+                    .get(name)
+                    .is_some_and(|literal| *pattern != fancy_regex::escape(literal));
+                if clashes {
+                    if duplicates.insert(name) {
+                        errors.push(Error::DuplicateName(name.clone()));
+                    }
+                } else if self.patterns[..i]
+                    .iter()
+                    .any(|(other, other_pattern)| other == name && other_pattern != pattern)
+                    && duplicates.insert(name)
+                {
+                    errors.push(Error::DuplicatePattern(name.clone()));
+                }
+            }
+        }
+
+        for (name, pattern) in &self.patterns {
+            let pattern = vec![(name.clone(), pattern.clone())];
+            match patterns::prepare(pattern, self.backtrack_limit) {
+                Ok(prepared) if patterns::matches_empty(&prepared[0].1) => {
+                    errors.push(Error::ZeroWidthPattern(name.clone()));
+                }
+                Ok(_) => {}
+                Err(err) => errors.push(err),
+            }
+        }
+
+        errors.extend(
+            self.escapes
+                .iter()
+                .filter_map(|(name, escapes)| patterns::prepare_escapes(name, escapes).err()),
+        );
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Error, Tokenizer, TokenizerConfig};
+
+    fn sample_config() -> TokenizerConfig {
+        TokenizerConfig {
+            literals: [("add", "+"), ("sub", "-")]
+                .into_iter()
+                .map(|(name, literal)| (name.into(), literal.into()))
+                .collect(),
+            patterns: vec![("int".into(), r"\d+".into())],
+            ignored_characters: [' '].into_iter().collect(),
+            longest_match: true,
+            max_token_length: Some(16),
+            backtrack_limit: Some(10_000),
+            escapes: vec![("int".into(), "x".into())],
+            emit_eof: true,
+            ..TokenizerConfig::default()
+        }
+    }
+
+    #[test]
+    fn from_config() {
+        let config = sample_config();
+        let tok = Tokenizer::from_config(&config).unwrap();
+
+        let expected = Tokenizer::default()
+            .with_literals(&[("add", "+"), ("sub", "-")])
+            .unwrap()
+            .with_patterns(vec![("int".into(), r"\d+".into())])
+            .unwrap()
+            .with_ignored_characters([' '].into_iter().collect())
+            .with_longest_match(true)
+            .with_max_token_length(Some(16))
+            .with_backtrack_limit(Some(10_000))
+            .unwrap()
+            .with_emit_eof(true)
+            .with_escapes("int", Some("x"))
+            .unwrap();
+        assert_eq!(tok, expected);
+
+        assert_eq!(
+            Tokenizer::from_config(&TokenizerConfig::default()).unwrap(),
+            Tokenizer::default()
+        );
+    }
+
+    #[test]
+    fn from_invalid_config() {
+        let mut config = sample_config();
+        config.patterns.push(("bad".into(), "(".into()));
+        assert!(matches!(
+            Tokenizer::from_config(&config),
+            Err(Error::InvalidRegex { name, .. }) if name == "bad"
+        ));
+
+        let mut config = sample_config();
+        config.literals.insert("empty".into(), String::new());
+        assert!(matches!(
+            Tokenizer::from_config(&config),
+            Err(Error::EmptyLiteral)
+        ));
+    }
+
+    #[test]
+    fn validation() {
+        assert!(sample_config().validate().is_ok());
+        assert!(TokenizerConfig::default().validate().is_ok());
+
+        let mut config = sample_config();
+        config.literals.insert("empty".into(), String::new());
+        config.literals.insert("empty2".into(), String::new());
+        config.patterns.extend([
+            ("bad".into(), "(".into()),
+            ("int".into(), "a?".into()),
+            ("int".into(), "b?".into()),
+        ]);
+        config.escapes.push(("int".into(), "[".into()));
+        let errors = config.validate().unwrap_err();
+        assert!(matches!(
+            &errors[..],
+            [
+                Error::EmptyLiteral,
+                Error::DuplicatePattern(duplicate),
+                Error::InvalidRegex { name: invalid, .. },
+                Error::ZeroWidthPattern(first),
+                Error::ZeroWidthPattern(second),
+                Error::InvalidRegex { name: escapes, .. },
+            ] if duplicate == "int"
+                && first == "int"
+                && second == "int"
+                && escapes == "int"
+                && invalid == "bad"
+        ));
+
+        config.allow_duplicate_names = true;
+        assert_eq!(config.validate().unwrap_err().len(), 5);
+
+        let mut config = sample_config();
+        config.patterns.push(("add".into(), r"\++".into()));
+        assert!(matches!(
+            &config.validate().unwrap_err()[..],
+            [Error::DuplicateName(name)] if name == "add"
+        ));
+        assert!(matches!(
+            Tokenizer::from_config(&config),
+            Err(Error::DuplicateName(_))
+        ));
+        config.allow_duplicate_names = true;
+        assert!(Tokenizer::from_config(&config).is_ok());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_roundtrip() {
+        let config = sample_config();
+        let json = serde_json::to_string(&config).unwrap();
+        assert_eq!(
+            serde_json::from_str::<TokenizerConfig>(&json).unwrap(),
+            config
+        );
+
+        let config: TokenizerConfig = serde_json::from_str(
+            r#"{
+                "literals": {"add": "+"},
+                "patterns": [["int", "\\d+"]],
+                "ignore_whitespace": true
+            }"#,
+        )
+        .unwrap();
+        let tok = Tokenizer::from_config(&config).unwrap();
+        let names: Vec<_> = tok
+            .try_tokenize("1 + 23")
+            .unwrap()
+            .into_iter()
+            .map(|token| token.name)
+            .collect();
+        assert_eq!(names, ["int", "add", "int"]);
+    }
+}
@@ -1,18 +1,224 @@
+use std::ops::Range;
+
+/// An error that can occur when building a [`Tokenizer`](crate::Tokenizer) or during tokenization.
+///
+/// All positions are byte offsets into the source, consistent with
+/// [`Token::position`](crate::Token::position), so they can be used to slice the source directly.
 #[derive(Debug)]
 pub enum Error {
+    /// A character that couldn't be tokenized, along with its position.
     BadToken(char, usize),
+    /// Same as [`Error::BadToken`], with the name and span of the token directly preceding the
+    /// character (see
+    /// [`Tokenizer::tokenize_with_context`](crate::Tokenizer::tokenize_with_context)).
+    BadTokenAfter {
+        character: char,
+        position: usize,
+        previous: String,
+        previous_span: Range<usize>,
+    },
+    /// The given name is shared by several rules (see
+    /// [`allow_duplicate_names`](crate::Tokenizer#allow_duplicate_names)).
+    DuplicateName(String),
+    /// Several [patterns](crate::Tokenizer#patterns) (or
+    /// [skip patterns](crate::Tokenizer#skip_patterns)) set at once share the given name.
     DuplicatePattern(String),
+    /// The pattern of the given name matches an empty string (see
+    /// [`Tokenizer::validate`](crate::Tokenizer::validate)).
+    ZeroWidthPattern(String),
     EmptyLiteral,
-    InvalidRegex(Box<fancy_regex::Error>),
+    /// The regex of the pattern of the given name (or of the [escapes](crate::Tokenizer#escapes)
+    /// for patterns of that name) failed to compile.
+    InvalidRegex {
+        name: String,
+        source: Box<fancy_regex::Error>,
+    },
+    /// A pattern started matching at the `start` position, but the source ended before it could be
+    /// completed (e.g. a string literal with a missing closing quote). The opening sequence
+    /// (e.g. the quote) spans from `start` to `end` and is skipped, so tokenization resumes right
+    /// after it.
+    Unterminated {
+        name: String,
+        start: usize,
+        end: usize,
+    },
+    /// Reading the source failed (see
+    /// [`Tokenizer::tokenize_reader`](crate::Tokenizer::tokenize_reader)).
+    Io(std::io::Error),
+    /// A token of the given name, starting at `position`, was `length` bytes long, exceeding the
+    /// [`max_token_length`](crate::Tokenizer#max_token_length). The token is skipped. Matches are
+    /// usually cut off right past the limit, so `length` can be shorter than the whole token.
+    TokenTooLong {
+        name: String,
+        length: usize,
+        position: usize,
+    },
+    /// Matching the pattern of the given name at `position` was aborted, e.g. because it exceeded
+    /// the [`backtrack_limit`](crate::Tokenizer#backtrack_limit). The character at `position` is
+    /// skipped.
+    Timeout {
+        name: String,
+        position: usize,
+    },
+    /// A token of the given name contained a backslash at `position` not followed by any of the
+    /// allowed [escape sequences](crate::Tokenizer#escapes). The token is skipped.
+    InvalidEscape {
+        name: String,
+        position: usize,
+    },
+    /// The input contained an invalid UTF-8 sequence starting at `position` (see
+    /// [`Tokenizer::tokenize_bytes`](crate::Tokenizer::tokenize_bytes)). The sequence is skipped.
+    InvalidUtf8 {
+        position: usize,
+    },
+    /// A token starting at `position` didn't fit into the buffer of at most `limit` bytes (see
+    /// [`Tokenizer::tokenize_reader`](crate::Tokenizer::tokenize_reader)).
+    BufferOverflow {
+        position: usize,
+        limit: usize,
+    },
+    /// No token could be matched at `position`, but the pattern of the given name matched an empty
+    /// string there. Such a match would make no progress, so the character at `position` is skipped
+    /// instead.
+    ZeroWidthMatch {
+        name: String,
+        position: usize,
+    },
+    /// The input contained no token (see [`Tokenizer::match_full`](crate::Tokenizer::match_full)).
+    EmptyInput,
+    /// The input continued past its first token, starting at `position` (see
+    /// [`Tokenizer::match_full`](crate::Tokenizer::match_full)).
+    TrailingInput {
+        position: usize,
+    },
+    /// The token of the given name has no kind assigned (see
+    /// [`Tokenizer::typed`](crate::Tokenizer::typed)).
+    MissingKind(String),
+    /// The pattern of the given name can't be matched from the end of the source (see
+    /// [`Tokenizer::tokenize_rev`](crate::Tokenizer::tokenize_rev)).
+    UnsupportedPattern(String),
+    /// The given character can't be used as a delimiter (see
+    /// [`Tokenizer::csv`](crate::Tokenizer::csv)).
+    InvalidDelimiter(char),
+    /// The position of a [`TokenizerState`](crate::TokenizerState) is past the end of the source or
+    /// not on a UTF-8 character boundary (see
+    /// [`Tokenizer::tokenize_from`](crate::Tokenizer::tokenize_from)).
+    InvalidState {
+        position: usize,
+    },
+}
+
+impl Error {
+    /// Returns the position carried by the error (if any).
+    pub(crate) fn position(&self) -> Option<usize> {
+        match self {
+            Self::BadToken(_, position)
+            | Self::BadTokenAfter { position, .. }
+            | Self::Unterminated {
+                start: position, ..
+            }
+            | Self::TokenTooLong { position, .. }
+            | Self::Timeout { position, .. }
+            | Self::InvalidEscape { position, .. }
+            | Self::InvalidUtf8 { position }
+            | Self::BufferOverflow { position, .. }
+            | Self::ZeroWidthMatch { position, .. }
+            | Self::TrailingInput { position }
+            | Self::InvalidState { position } => Some(*position),
+            _ => None,
+        }
+    }
+
+    /// Shifts the position carried by the error (if any) by `offset` bytes.
+    pub(crate) fn shift(&mut self, offset: usize) {
+        match self {
+            Self::BadTokenAfter {
+                position,
+                previous_span,
+                ..
+            } => {
+                *position += offset;
+                *previous_span = previous_span.start + offset..previous_span.end + offset;
+            }
+            Self::Unterminated { start, end, .. } => {
+                *start += offset;
+                *end += offset;
+            }
+            Self::BadToken(_, position)
+            | Self::TokenTooLong { position, .. }
+            | Self::Timeout { position, .. }
+            | Self::InvalidEscape { position, .. }
+            | Self::InvalidUtf8 { position }
+            | Self::BufferOverflow { position, .. }
+            | Self::ZeroWidthMatch { position, .. }
+            | Self::TrailingInput { position }
+            | Self::InvalidState { position } => *position += offset,
+            _ => {}
+        }
+    }
 }
 
 impl std::fmt::Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::BadToken(c, p) => write!(f, "invalid token {c:?} at position {p}"),
+            Self::BadTokenAfter {
+                character,
+                position,
+                previous,
+                previous_span,
+            } => write!(
+                f,
+                "invalid token {character:?} at position {position} after {previous} at \
+                 {previous_span:?}"
+            ),
+            Self::DuplicateName(name) => write!(f, "duplicate name {name:?}"),
             Self::DuplicatePattern(name) => write!(f, "duplicate pattern {name:?}"),
+            Self::ZeroWidthPattern(name) => write!(f, "pattern {name:?} matches an empty string"),
             Self::EmptyLiteral => write!(f, "literals cannot be empty"),
-            Self::InvalidRegex(err) => err.fmt(f),
+            Self::InvalidRegex { name, source } => write!(f, "invalid regex for {name}: {source}"),
+            Self::Unterminated { name, start, .. } => {
+                write!(f, "unterminated {name} starting at position {start}")
+            }
+            Self::Io(err) => err.fmt(f),
+            Self::TokenTooLong {
+                name,
+                length,
+                position,
+            } => write!(
+                f,
+                "{name} at position {position} is {length} bytes long, exceeding the maximum token \
+                 length"
+            ),
+            Self::Timeout { name, position } => {
+                write!(f, "matching {name} at position {position} timed out")
+            }
+            Self::InvalidEscape { name, position } => {
+                write!(
+                    f,
+                    "invalid escape sequence in {name} at position {position}"
+                )
+            }
+            Self::InvalidUtf8 { position } => write!(f, "invalid UTF-8 at position {position}"),
+            Self::BufferOverflow { position, limit } => write!(
+                f,
+                "token at position {position} exceeds the buffer limit of {limit} bytes"
+            ),
+            Self::ZeroWidthMatch { name, position } => {
+                write!(f, "{name} matched an empty string at position {position}")
+            }
+            Self::EmptyInput => write!(f, "expected a token, found no input"),
+            Self::TrailingInput { position } => {
+                write!(f, "unexpected input after the token at position {position}")
+            }
+            Self::MissingKind(name) => write!(f, "no kind assigned to the token {name:?}"),
+            Self::UnsupportedPattern(name) => {
+                write!(f, "pattern {name:?} can't be matched in reverse")
+            }
+            Self::InvalidDelimiter(c) => write!(f, "{c:?} can't be used as a delimiter"),
+            Self::InvalidState { position } => {
+                write!(f, "can't resume tokenization at position {position}")
+            }
         }
     }
 }
@@ -27,14 +233,115 @@ mod tests {
             Error::BadToken('x', 7).to_string(),
             "invalid token 'x' at position 7"
         );
+        assert_eq!(
+            Error::BadTokenAfter {
+                character: '@',
+                position: 4,
+                previous: "name".into(),
+                previous_span: 0..3
+            }
+            .to_string(),
+            "invalid token '@' at position 4 after name at 0..3"
+        );
+        assert_eq!(
+            Error::DuplicateName("add".into()).to_string(),
+            "duplicate name \"add\""
+        );
         assert_eq!(
             Error::DuplicatePattern("string".into()).to_string(),
             "duplicate pattern \"string\""
         );
+        assert_eq!(
+            Error::ZeroWidthPattern("digits".into()).to_string(),
+            "pattern \"digits\" matches an empty string"
+        );
         assert_eq!(Error::EmptyLiteral.to_string(), "literals cannot be empty");
         assert_eq!(
-            Error::InvalidRegex(Box::new(fancy_regex::Regex::new("+").unwrap_err())).to_string(),
-            "Parsing error at position 0: Target of repeat operator is invalid"
+            Error::InvalidRegex {
+                name: "int".into(),
+                source: Box::new(fancy_regex::Regex::new("+").unwrap_err())
+            }
+            .to_string(),
+            "invalid regex for int: Parsing error at position 0: \
+             Target of repeat operator is invalid"
+        );
+        assert_eq!(
+            Error::Unterminated {
+                name: "string".into(),
+                start: 3,
+                end: 4
+            }
+            .to_string(),
+            "unterminated string starting at position 3"
+        );
+        assert_eq!(
+            Error::TokenTooLong {
+                name: "string".into(),
+                length: 70000,
+                position: 5
+            }
+            .to_string(),
+            "string at position 5 is 70000 bytes long, exceeding the maximum token length"
+        );
+        assert_eq!(
+            Error::Timeout {
+                name: "comment".into(),
+                position: 9
+            }
+            .to_string(),
+            "matching comment at position 9 timed out"
+        );
+        assert_eq!(
+            Error::InvalidEscape {
+                name: "string".into(),
+                position: 4
+            }
+            .to_string(),
+            "invalid escape sequence in string at position 4"
+        );
+        assert_eq!(
+            Error::InvalidUtf8 { position: 6 }.to_string(),
+            "invalid UTF-8 at position 6"
+        );
+        assert_eq!(
+            Error::ZeroWidthMatch {
+                name: "star".into(),
+                position: 2
+            }
+            .to_string(),
+            "star matched an empty string at position 2"
+        );
+        assert_eq!(
+            Error::BufferOverflow {
+                position: 12,
+                limit: 1024
+            }
+            .to_string(),
+            "token at position 12 exceeds the buffer limit of 1024 bytes"
+        );
+        assert_eq!(
+            Error::EmptyInput.to_string(),
+            "expected a token, found no input"
+        );
+        assert_eq!(
+            Error::TrailingInput { position: 4 }.to_string(),
+            "unexpected input after the token at position 4"
+        );
+        assert_eq!(
+            Error::MissingKind("int".into()).to_string(),
+            "no kind assigned to the token \"int\""
+        );
+        assert_eq!(
+            Error::UnsupportedPattern("int".into()).to_string(),
+            "pattern \"int\" can't be matched in reverse"
+        );
+        assert_eq!(
+            Error::InvalidDelimiter('"').to_string(),
+            "'\"' can't be used as a delimiter"
+        );
+        assert_eq!(
+            Error::InvalidState { position: 9 }.to_string(),
+            "can't resume tokenization at position 9"
         );
     }
 }
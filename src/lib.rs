@@ -3,9 +3,14 @@
 //! # Examples
 //! ## [Brainfuck](https://en.wikipedia.org/wiki/Brainfuck)
 //! ```rust
+//! # #[cfg(feature = "std")]
 //! use crossandra::Tokenizer;
+//! # #[cfg(feature = "std")]
 //! use rustc_hash::FxHashMap;
 //!
+//! # #[cfg(not(feature = "std"))]
+//! # fn main() {}
+//! # #[cfg(feature = "std")]
 //! # fn main() {
 //! let bf_tok = Tokenizer::default()
 //!     .with_literals(&[
@@ -25,26 +30,124 @@
 //! }
 //! # }
 //! ```
+//!
+//! # Features
+//! * `std` (enabled by default) — everything but the [`Tree`] and the [`common`] patterns, as the
+//!   regex engine ([`fancy_regex`](https://docs.rs/fancy-regex)) requires the standard library.
+//!   Without this feature, the crate is `no_std` and only provides the [`Tree`] of literals and
+//!   the [`common`] pattern strings, built on `alloc` alone (using a
+//!   [`BTreeMap`](alloc::collections::BTreeMap) in place of the hash map for nodes with many
+//!   children), e.g. to match literals in WASM or embedded contexts. All the other features
+//!   require `std` and enable it.
+//! * `rayon` — parallel tokenization of lines, see `Tokenizer::tokenize_lines`.
+//! * `log` — tracing of the matching decisions using the [`log`](https://docs.rs/log) crate at
+//!   the `trace` level, i.e. which literal and patterns matched at each position and which token
+//!   won, e.g. to find out why one of two overlapping patterns was picked. Without the feature,
+//!   no tracing code is compiled in at all.
+//! * `serde` — (de)serialization of [`Token`]s, [`OwnedToken`]s and [`TokenizerConfig`].
+//! * `regex-cache` — a global cache of compiled [patterns](Tokenizer#patterns), so that building
+//!   many [`Tokenizer`]s with the same patterns compiles each of them only once. Regex compilation
+//!   usually dominates the construction of pattern-heavy tokenizers, so repeated construction gets
+//!   much cheaper: the `construction` benchmark (a tokenizer with 5 patterns and 80 literals) went
+//!   from about 2.3 ms to about 18 µs. The cost is a global lock and up to 256 cached regexes,
+//!   of which the ones no longer used by any [`Tokenizer`] are evicted once the cache is full.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "std")]
 pub use rustc_hash::{FxHashMap, FxHashSet};
 
+#[cfg(feature = "std")]
+use std::{borrow::Cow, iter::FusedIterator, sync::Arc};
+
+#[cfg(feature = "std")]
 use fancy_regex::Regex;
+
+#[cfg(feature = "rayon")]
 use rayon::prelude::*;
 
 pub mod common;
 
+#[cfg(feature = "std")]
+mod config;
+#[cfg(feature = "std")]
+pub use config::TokenizerConfig;
+
+#[cfg(feature = "std")]
 mod error;
-use error::Error;
+#[cfg(feature = "std")]
+pub use error::Error;
+
+#[cfg(feature = "std")]
+mod line_index;
+#[cfg(feature = "std")]
+pub use line_index::LineIndex;
+
+#[cfg(feature = "std")]
+mod reader;
+#[cfg(feature = "std")]
+pub use reader::ReaderTokens;
 
+#[cfg(feature = "std")]
+mod rev;
+
+#[cfg(feature = "std")]
+mod remainder;
+#[cfg(feature = "std")]
+pub use remainder::TokensWithRemainder;
+
+#[cfg(feature = "std")]
+mod skipped;
+#[cfg(feature = "std")]
+pub use skipped::{SkipReason, TokenOrSkipped};
+
+#[cfg(feature = "std")]
+mod split;
+#[cfg(feature = "std")]
+pub use split::SplitOn;
+
+#[cfg(feature = "std")]
+mod state;
+#[cfg(feature = "std")]
+pub use state::TokenizerState;
+
+#[cfg(feature = "std")]
 mod stream;
 
+#[cfg(feature = "std")]
+mod summary;
+#[cfg(feature = "std")]
+pub use summary::TokenizeSummary;
+
+#[cfg(feature = "std")]
 mod token;
-pub use token::Token;
+#[cfg(feature = "std")]
+pub use token::{OwnedToken, Token};
+
+#[cfg(feature = "std")]
+mod token_stream;
+#[cfg(feature = "std")]
+pub use token_stream::TokenStream;
 
 mod tree;
-use tree::{generate_tree, Tree};
+#[cfg(feature = "std")]
+use tree::generate_tree;
+pub use tree::{Children, LiteralTree, Tree};
 
+#[cfg(feature = "std")]
 mod patterns;
+#[cfg(feature = "std")]
+pub use patterns::{anchor_pattern, AnchorMode, RegexOptions};
+#[cfg(feature = "std")]
+use patterns::{Combined, Pattern, PatternFn};
+
+#[cfg(feature = "std")]
+mod typed;
+#[cfg(feature = "std")]
+pub use typed::{Typed, TypedToken};
 
+#[cfg(feature = "std")]
 const WHITESPACE: [char; 6] = [' ', '\x0c', '\t', '\x0b', '\r', '\n'];
 
 /// The Crossandra tokenizer, operating on literals and patterns.
@@ -67,16 +170,24 @@ const WHITESPACE: [char; 6] = [' ', '\x0c', '\t', '\x0b', '\r', '\n'];
 /// ];
 /// # assert!(Tokenizer::default().with_literals(&literals).is_ok());
 /// ```
-/// Literals take precedence over patterns.
+/// Literals take precedence over patterns. Literals sharing a name are aliases producing the same
+/// token, e.g. to map several spellings to a single name (both `->` and `→` to `arrow`, see also
+/// [`Tokenizer::with_literal_aliases`]). A literal sharing its name with a
+/// [pattern](Tokenizer#patterns) or another rule is not allowed though (see
+/// [`allow_duplicate_names`](Tokenizer#allow_duplicate_names)) and will result in an error, as
+/// their tokens couldn't be told apart.
 ///
 /// ## Patterns
 /// Patterns are regular expressions that match more complex token structures. They are represented
 /// as pairs of strings (name, pattern) in a [`Vec`] to maintain a consistent matching order.
 ///
-/// The order of patterns matters as the tokenizer will use the first matching pattern it finds.
-/// Duplicate pattern names are not allowed and will result in an error. This crate also provides a
-/// collection of commonly used patterns in the [`common`] module. For example, patterns covering
-/// binary, octal, and hexadecimal literals could be defined like this:
+/// The order of patterns matters as the tokenizer will use the first matching pattern it finds
+/// (unless [`longest_match`](Tokenizer#longest_match) is enabled).
+/// Duplicate pattern names are not allowed (see
+/// [`allow_duplicate_names`](Tokenizer#allow_duplicate_names)) and will result in an error. This
+/// crate also provides a collection of commonly used patterns in the [`common`]
+/// module. For example, patterns covering binary, octal, and hexadecimal literals could be defined
+/// like this:
 /// ```rust
 /// # use crossandra::Tokenizer;
 /// let patterns = vec![
@@ -87,6 +198,92 @@ const WHITESPACE: [char; 6] = [' ', '\x0c', '\t', '\x0b', '\r', '\n'];
 /// # assert!(Tokenizer::default().with_patterns(patterns).is_ok());
 /// ```
 ///
+/// The patterns are owned by the [`Tokenizer`], so they can be built at runtime without having to
+/// outlive it. Only the [literals](Tokenizer#literals) are borrowed, so a [`Tokenizer`] without
+/// them (or with `'static` ones) is a `Tokenizer<'static>`, which can be freely stored and passed
+/// around:
+/// ```rust
+/// # use crossandra::Tokenizer;
+/// fn keywords(words: &[&str]) -> Tokenizer<'static> {
+///     let patterns = words.iter().map(|word| (word.to_string(), format!(r"{word}\b"))).collect();
+///     Tokenizer::default().with_patterns(patterns).unwrap()
+/// }
+/// # assert_eq!(keywords(&["if", "else"]).tokenize("else").count(), 1);
+/// ```
+///
+/// Unless [`longest_match`](Tokenizer#longest_match) is enabled, multiple patterns are combined
+/// into a single alternation regex, so that the first matching pattern is found in one pass.
+/// Patterns with backreferences can't be combined, in which case they are tried one by one.
+///
+/// ## Pattern functions
+/// Tokens that can't be described by a regex (e.g. operators defined at runtime by the parsed
+/// source itself) can be matched by a closure instead, added with [`Tokenizer::with_pattern_fn`].
+/// The closure is given the remaining source and returns the byte length of the token at its
+/// start, or [`None`] if there is no such token. A length of 0 is reported as
+/// [`Error::ZeroWidthMatch`], just like for an empty pattern match, and a length past the end of
+/// the source or inside of a character is treated as no match.
+///
+/// Pattern functions are tried right after the [patterns](Tokenizer#patterns), in the order they
+/// were added, and compete with all other rules when [`longest_match`](Tokenizer#longest_match)
+/// is enabled.
+/// ```rust
+/// # use crossandra::{common, Tokenizer};
+/// let operators = ["<|>", "<$"];
+/// let tok = Tokenizer::default()
+///     .with_patterns(vec![common::C_NAME.clone()])
+///     .unwrap()
+///     .with_pattern_fn("operator", move |source| {
+///         operators.iter().find(|op| source.starts_with(*op)).map(|op| op.len())
+///     })
+///     .unwrap();
+/// let values: Vec<_> = tok.tokenize("f<$x").map(|t| t.unwrap().value).collect();
+/// assert_eq!(values, ["f", "<$", "x"]);
+/// ```
+///
+/// ## Balanced spans
+/// Regular expressions can't match arbitrarily nested brackets, so a span enclosed in a pair of
+/// brackets (e.g. `((a)(b))`) can be matched by a dedicated rule instead, added with
+/// [`Tokenizer::with_balanced`]. At the opening character, the rule counts the nesting depth until
+/// the matching closing character and produces the whole span as a single token. A span that
+/// isn't closed before the end of the source is reported as [`Error::Unterminated`].
+///
+/// Balanced spans are tried after [literals](Tokenizer#literals), [patterns](Tokenizer#patterns)
+/// and [pattern functions](Tokenizer#pattern-functions), in the order they were added.
+///
+/// They only count the brackets, so e.g. a closing bracket inside a string literal within the span
+/// still closes it.
+/// ```rust
+/// # use crossandra::{common, Tokenizer};
+/// let tok = Tokenizer::default()
+///     .with_patterns(vec![common::C_NAME.clone()])
+///     .unwrap()
+///     .with_balanced("args", '(', ')')
+///     .unwrap();
+/// let values: Vec<_> = tok.tokenize("f((a)(b))").map(|t| t.unwrap().value).collect();
+/// assert_eq!(values, ["f", "((a)(b))"]);
+/// ```
+///
+/// ## Heredocs
+/// A heredoc (e.g. `<<END` in shell or Ruby) ends at a line equal to a marker given by its opening
+/// sequence, so it can't be matched by a regex either. A heredoc rule, added with
+/// [`Tokenizer::with_heredoc`], consists of a pattern matching the opening sequence, where the
+/// first capture group is the marker (or the whole match, if there are no groups). The body starts
+/// on the line after the opening sequence, so the rest of that line is part of the token too, and
+/// the token ends with the terminating marker (excluding the line break after it). A heredoc
+/// without the terminating line is reported as [`Error::Unterminated`].
+///
+/// Heredocs are tried after [balanced spans](Tokenizer#balanced-spans), so e.g. a `<<` literal
+/// takes precedence, unless [`longest_match`](Tokenizer#longest_match) is enabled.
+/// ```rust
+/// # use crossandra::Tokenizer;
+/// let tok = Tokenizer::default()
+///     .with_heredoc("heredoc", r"<<(\w+)")
+///     .unwrap()
+///     .with_ignore_whitespace(true);
+/// let values: Vec<_> = tok.tokenize("<<END\nhi\nEND\n").map(|t| t.unwrap().value).collect();
+/// assert_eq!(values, ["<<END\nhi\nEND"]);
+/// ```
+///
 /// ## Other options
 ///
 /// ### `ignore_whitespace`
@@ -101,11 +298,143 @@ const WHITESPACE: [char; 6] = [' ', '\x0c', '\t', '\x0b', '\r', '\n'];
 /// | `0xd`  | Carriage return (`\r`) |
 /// | `0x20` | Space (` `)            |
 ///
+/// as well as any other Unicode whitespace (i.e. characters for which [`char::is_whitespace`]
+/// returns `true`), such as the no-break space (`U+00A0`) or the ideographic space (`U+3000`).
+///
+/// Ignored characters are skipped before any literal or pattern is tried, so a
+/// [pattern](Tokenizer#patterns) can't match whitespace at the start of a token while this option
+/// is enabled, regardless of [`longest_match`](Tokenizer#longest_match). Whitespace inside
+/// a token (e.g. in a string literal) is unaffected. To tokenize whitespace, leave this option
+/// disabled and define a pattern for it instead.
+///
 /// Defaults to `false`.
 ///
 /// ### `ignored_characters`
 /// A set of characters to ignore during tokenization. Defaults to an empty [`Vec`].
 ///
+/// ### `skip_patterns`
+/// Patterns whose matches are consumed, but not emitted as tokens, e.g. comments or runs of
+/// whitespace. Unlike [`ignored_characters`](Tokenizer#ignored_characters), they can span
+/// multiple characters. Just like ignored characters, they are tried (in their declaration order)
+/// before any literal or pattern, so a skip pattern for `//` comments takes precedence over a `/`
+/// literal. The positions of the following tokens are unaffected, i.e. they still point into the
+/// original source. Set with [`Tokenizer::with_skip_patterns`], defaults to no skip patterns.
+///
+/// ```rust
+/// # use crossandra::{common, Tokenizer};
+/// let tok = Tokenizer::default()
+///     .with_literals(&[("div", "/")])
+///     .unwrap()
+///     .with_patterns(vec![common::C_NAME.clone()])
+///     .unwrap()
+///     .with_skip_patterns(&[("comment", r"//[^\n]*"), ("space", r"\s+")])
+///     .unwrap();
+/// let tokens: Vec<_> = tok
+///     .tokenize("a / b // c\nd")
+///     .map(|token| token.map(|token| (token.value, token.position)))
+///     .collect::<Result<_, _>>()
+///     .unwrap();
+/// assert_eq!(tokens, [("a", 0), ("/", 2), ("b", 4), ("d", 11)]);
+/// ```
+///
+/// ### `longest_match`
+/// Whether to try all literals and patterns at each position and pick the one producing the
+/// longest match, instead of stopping at the first one that matches. Ties are broken by
+/// precedence, i.e. literals first, then patterns in their declaration order. This is useful when
+/// e.g. both `<` and `<=` are defined as patterns, at the cost of evaluating every pattern at
+/// every position.
+///
+/// For patterns that can start the same way, but where at most one of them matches any given
+/// input (e.g. [`struct@common::CHAR`] and [`struct@common::SINGLE_QUOTED_STRING`] for `'ab'`),
+/// this isn't needed, as the first matching pattern is picked either way. Only the inputs both
+/// match (e.g. `'a'`) depend on the declaration order.
+///
+/// Defaults to `false`.
+///
+/// ### `max_token_length`
+/// The maximum length of a single token in bytes, applying to both literals and patterns. Longer
+/// matches are skipped and reported as [`Error::TokenTooLong`], which is useful as a safety valve
+/// when tokenizing untrusted input.
+///
+/// The limit is enforced before matching: the rules only see the source up to the first character
+/// past the limit, so a greedy match (e.g. of `[^\n]*` on a huge line) is cut off there. Only if
+/// nothing matches within that part, the rest of the source is considered, e.g. to report a long
+/// string literal as a whole. As a consequence, lookaheads can't see past that part either.
+///
+/// Defaults to [`None`], i.e. no limit.
+///
+/// ### `backtrack_limit`
+/// The maximum number of backtracking steps a single [pattern](Tokenizer#patterns) may take at
+/// a position, protecting against patterns with catastrophic backtracking (e.g. when they come from
+/// untrusted configuration). Exceeding it is reported as [`Error::Timeout`]. Note that only
+/// patterns using features like lookarounds or backreferences backtrack at all, the rest run in
+/// linear time.
+///
+/// Defaults to [`None`], i.e. the default limit of [`fancy_regex`] (1,000,000 steps).
+///
+/// ### `escapes`
+/// The escape sequences allowed in tokens of a given [pattern](Tokenizer#patterns) name, as
+/// a regex matching the text following a backslash. Every backslash in a matched token has to
+/// start one of the allowed sequences, otherwise the token is skipped and reported as
+/// [`Error::InvalidEscape`] pointing at the offending backslash. This way, patterns like
+/// [`common::STRING`](struct@common::STRING) can stay permissive while still rejecting e.g. `\q`.
+/// ```rust
+/// # use crossandra::{common, Error, Tokenizer};
+/// let tok = Tokenizer::default()
+///     .with_patterns(vec![common::DOUBLE_QUOTED_STRING.clone()])
+///     .unwrap()
+///     .with_escapes("double_quoted_string", Some(r#"[nt\\"]|u\{[0-9A-Fa-f]{1,6}\}"#))
+///     .unwrap();
+/// assert!(tok.tokenize(r#""a\tb\u{1F980}""#).all(|result| result.is_ok()));
+/// assert!(matches!(
+///     tok.tokenize(r#""a\qb""#).next(),
+///     Some(Err(Error::InvalidEscape { position: 2, .. }))
+/// ));
+/// ```
+///
+/// Defaults to no restrictions for all patterns.
+///
+/// ### `emit_eof`
+/// Whether to append a final [`Token`] named `"eof"` with an empty value at the end of the source,
+/// so that parsers can handle the end of the input like any other token. It is only emitted if
+/// no [`Error`] was encountered. This applies to all methods tokenizing the whole source, but not
+/// to the line-based `Tokenizer::tokenize_lines` (with the `rayon` feature).
+/// ```rust
+/// # use crossandra::{Token, Tokenizer};
+/// let tok = Tokenizer::default()
+///     .with_literals(&[("a", "a")])
+///     .unwrap()
+///     .with_emit_eof(true);
+/// let tokens = tok.try_tokenize("aa").unwrap();
+/// assert_eq!(tokens.last(), Some(&Token::from(("eof", "", 2))));
+/// ```
+///
+/// Defaults to `false`.
+///
+/// ### `allow_duplicate_names`
+/// Whether to allow rules to share the same name, e.g. to intentionally map several alternative
+/// spellings to a single token name. Otherwise, each name has to be unique among the
+/// [literals](Tokenizer#literals), [patterns](Tokenizer#patterns),
+/// [skip patterns](Tokenizer#skip_patterns), [pattern functions](Tokenizer#pattern-functions),
+/// [balanced spans](Tokenizer#balanced-spans) and [heredocs](Tokenizer#heredocs), with two
+/// exceptions: literals sharing a name are aliases of each other, and a pattern repeating another
+/// pattern or matching just a literal of the same name (e.g. `\+` for `+`) is redundant rather
+/// than ambiguous.
+///
+/// Every setter checks the names right away against the rules already set, returning
+/// [`Error::DuplicatePattern`] for (skip) patterns set at once sharing a name and
+/// [`Error::DuplicateName`] for any other clash, so this option has to be enabled before the rules
+/// are set.
+///
+/// Defaults to `false`.
+///
+/// ## Cloning
+/// Compiled [patterns](Tokenizer#patterns) and [escapes](Tokenizer#escapes) are reference-counted,
+/// so cloning a [`Tokenizer`] never recompiles any regex. Only the [literals](Tokenizer#literals)
+/// and their [`Tree`] are copied, which is cheap as the literals themselves are borrowed. This
+/// makes it viable to configure a base tokenizer once and customize a clone of it per use (e.g.
+/// with [`Tokenizer::remove_pattern`]).
+///
 /// ## Fast Mode
 /// When all literals are of length 1 and there are no patterns, Crossandra uses a simpler
 /// tokenization method.
@@ -114,31 +443,79 @@ const WHITESPACE: [char; 6] = [' ', '\x0c', '\t', '\x0b', '\r', '\n'];
 /// ~300x faster with Fast Mode (32.5s vs 110ms on Apple M2).
 ///
 /// Do note that this is a rather extreme case; for a 1KB file, the speedup is ~2.3x.
+#[cfg(feature = "std")]
 #[derive(Debug, Clone)]
 pub struct Tokenizer<'a> {
     literals: FxHashMap<&'a str, &'a str>,
-    patterns: Vec<(String, Regex)>,
+    patterns: Vec<Pattern>,
+    skip_patterns: Vec<Pattern>,
+    pattern_fns: Vec<PatternFn>,
+    balanced: Vec<(String, char, char)>,
+    heredocs: Vec<Pattern>,
     ignore_whitespace: bool,
     ignored_characters: FxHashSet<char>,
-    tree: Tree<'a>,
+    longest_match: bool,
+    max_token_length: Option<usize>,
+    backtrack_limit: Option<usize>,
+    escapes: FxHashMap<String, Arc<Regex>>,
+    emit_eof: bool,
+    allow_duplicate_names: bool,
+    tree: LiteralTree<'a>,
+    /// All patterns combined into one regex, if possible.
+    combined: Option<Combined>,
 }
 
+#[cfg(feature = "std")]
 impl PartialEq for Tokenizer<'_> {
     fn eq(&self, other: &Self) -> bool {
         self.literals == other.literals
+            && self.balanced == other.balanced
             && self.ignore_whitespace == other.ignore_whitespace
             && self.ignored_characters == other.ignored_characters
+            && self.longest_match == other.longest_match
+            && self.max_token_length == other.max_token_length
+            && self.backtrack_limit == other.backtrack_limit
+            && self.emit_eof == other.emit_eof
+            && self.escapes.len() == other.escapes.len()
+            && self.escapes.iter().all(|(name, regex)| {
+                other
+                    .escapes
+                    .get(name)
+                    .is_some_and(|other| other.as_str() == regex.as_str())
+            })
+            && self.allow_duplicate_names == other.allow_duplicate_names
             && self.patterns.len() == other.patterns.len()
             && self
                 .patterns
                 .iter()
                 .zip(&other.patterns)
+                .all(|(a, b)| a.0 == b.0 && a.1.as_str() == b.1.as_str() && a.2 == b.2)
+            && self.skip_patterns.len() == other.skip_patterns.len()
+            && self
+                .skip_patterns
+                .iter()
+                .zip(&other.skip_patterns)
+                .all(|(a, b)| a.0 == b.0 && a.1.as_str() == b.1.as_str())
+            && self.heredocs.len() == other.heredocs.len()
+            && self
+                .heredocs
+                .iter()
+                .zip(&other.heredocs)
                 .all(|(a, b)| a.0 == b.0 && a.1.as_str() == b.1.as_str())
+            // closures can't be compared, only the same (e.g. cloned) ones are equal
+            && self.pattern_fns.len() == other.pattern_fns.len()
+            && self
+                .pattern_fns
+                .iter()
+                .zip(&other.pattern_fns)
+                .all(|(a, b)| a.name == b.name && Arc::ptr_eq(&a.matcher, &b.matcher))
     }
 }
 
+#[cfg(feature = "std")]
 impl Eq for Tokenizer<'_> {}
 
+#[cfg(feature = "std")]
 impl<'a> Tokenizer<'a> {
     /// Creates a new [`Tokenizer`] with the specified configuration.
     ///
@@ -146,27 +523,349 @@ impl<'a> Tokenizer<'a> {
     ///
     /// This function will return an error if:
     /// * any [literal](Tokenizer#literals) is empty,
-    /// * there are duplicate [patterns](Tokenizer#patterns), or
-    /// * any [pattern](Tokenizer#patterns) regex is invalid.
+    /// * there are duplicate [patterns](Tokenizer#patterns),
+    /// * a pattern name is already used by a literal, or
+    /// * any pattern regex is invalid.
+    ///
+    /// As [`allow_duplicate_names`](Tokenizer#allow_duplicate_names) can't be enabled beforehand,
+    /// use [`Tokenizer::with_allow_duplicate_names`] followed by the setters to reuse names.
     pub fn new(
         literals: &[(&'a str, &'a str)],
         patterns: Vec<(String, String)>,
         ignored_characters: FxHashSet<char>,
         ignore_whitespace: bool,
     ) -> Result<Self, Error> {
-        validate_literals(literals)?;
-        let literals = stream::build_hashmap(literals);
-        Ok(Self {
-            tree: generate_tree(&literals),
-            literals,
-            patterns: patterns::prepare(patterns)?,
+        let mut tokenizer = Self {
+            literals: FxHashMap::default(),
+            patterns: Vec::new(),
+            skip_patterns: Vec::new(),
+            pattern_fns: Vec::new(),
+            balanced: Vec::new(),
+            heredocs: Vec::new(),
             ignored_characters,
             ignore_whitespace,
-        })
+            longest_match: false,
+            max_token_length: None,
+            backtrack_limit: None,
+            escapes: FxHashMap::default(),
+            emit_eof: false,
+            allow_duplicate_names: false,
+            tree: Tree::default(),
+            combined: None,
+        };
+        tokenizer.set_literals(literals)?;
+        tokenizer.set_patterns(patterns)?;
+        Ok(tokenizer)
+    }
+
+    /// Creates a new [`Tokenizer`] with the given [patterns](Tokenizer#patterns), typically the
+    /// ones from the [`common`] module, and the default options otherwise.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use crossandra::{common, Tokenizer};
+    /// let tok = Tokenizer::from_common(&[
+    ///     &common::INT,
+    ///     &common::DOUBLE_QUOTED_STRING,
+    ///     &common::C_NAME,
+    ///     &("plus".into(), r"\+".into()),
+    /// ])
+    /// .unwrap()
+    /// .with_ignore_whitespace(true);
+    /// let names: Vec<_> = tok
+    ///     .tokenize(r#"x + "y" + 3"#)
+    ///     .map(|token| token.unwrap().name)
+    ///     .collect();
+    /// assert_eq!(names, ["c_name", "plus", "double_quoted_string", "plus", "int"]);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if there are duplicate pattern names or any pattern
+    /// regex is invalid. Neither can happen with the patterns from the [`common`] module alone.
+    pub fn from_common(patterns: &[&(String, String)]) -> Result<Self, Error> {
+        Self::default().with_patterns(patterns.iter().map(|&pattern| pattern.clone()).collect())
+    }
+
+    /// Creates a new [`Tokenizer`] for the tokens of [JSON](https://www.json.org):
+    /// * the structural characters as literals named `lbrace`, `rbrace`, `lbracket`, `rbracket`,
+    ///   `colon`, and `comma`,
+    /// * strings (`string`) and numbers (`number`) following the JSON grammar, see
+    ///   [`struct@common::JSON_STRING`] and [`struct@common::JSON_NUMBER`], and
+    /// * `true`/`false` (`bool`) and `null` (`null`), see [`struct@common::BOOL`] and
+    ///   [`struct@common::NULL`].
+    ///
+    /// Only the whitespace allowed by JSON (space, tab, `\n`, and `\r`) is ignored.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use crossandra::Tokenizer;
+    /// let tok = Tokenizer::json();
+    /// let names: Vec<_> = tok
+    ///     .tokenize(r#"{"a": [1, true]}"#)
+    ///     .map(|token| token.unwrap().name)
+    ///     .collect();
+    /// assert_eq!(
+    ///     names,
+    ///     [
+    ///         "lbrace", "string", "colon", "lbracket", "number", "comma", "bool", "rbracket",
+    ///         "rbrace",
+    ///     ]
+    /// );
+    /// ```
+    #[must_use]
+    pub fn json() -> Self {
+        let patterns = vec![
+            ("string".into(), common::JSON_STRING.1.clone()),
+            ("number".into(), common::JSON_NUMBER.1.clone()),
+            common::BOOL.clone(),
+            common::NULL.clone(),
+        ];
+        Self::new(
+            &[
+                ("lbrace", "{"),
+                ("rbrace", "}"),
+                ("lbracket", "["),
+                ("rbracket", "]"),
+                ("colon", ":"),
+                ("comma", ","),
+            ],
+            patterns,
+            [' ', '\t', '\n', '\r'].into_iter().collect(),
+            false,
+        )
+        .expect("the JSON tokenizer should be valid")
+    }
+
+    /// Creates a new [`Tokenizer`] for the records of a CSV file as described by
+    /// [RFC 4180](https://www.rfc-editor.org/rfc/rfc4180), separated by the given `delimiter`
+    /// (usually `,`), producing these tokens:
+    /// * `field` — either a field enclosed in double quotes, which can contain the delimiter,
+    ///   line breaks, and quotes escaped by doubling them (e.g. `"say ""hi"""`), or an unquoted
+    ///   field, which can't contain any of them,
+    /// * `separator` — the `delimiter`, and
+    /// * `newline` — a record terminator, either `\r\n` or `\n`.
+    ///
+    /// Empty fields (e.g. between two delimiters or after a trailing one) don't produce any token,
+    /// so the fields of a record should be told apart by the separators. No characters are
+    /// ignored, i.e. spaces around unquoted fields are part of them.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidDelimiter`] if the `delimiter` is `"`, `\r`, or `\n`.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use crossandra::Tokenizer;
+    /// let tok = Tokenizer::csv(',')?;
+    /// let values: Vec<_> = tok
+    ///     .tokenize("id,\"name, full\"\r\n1,\"x\"\"y\"")
+    ///     .map(|token| token.unwrap().value)
+    ///     .collect();
+    /// assert_eq!(values, ["id", ",", "\"name, full\"", "\r\n", "1", ",", "\"x\"\"y\""]);
+    /// # Ok::<(), crossandra::Error>(())
+    /// ```
+    pub fn csv(delimiter: char) -> Result<Self, Error> {
+        if matches!(delimiter, '"' | '\r' | '\n') {
+            return Err(Error::InvalidDelimiter(delimiter));
+        }
+
+        let delimiter = fancy_regex::escape(delimiter.encode_utf8(&mut [0; 4])).into_owned();
+        let (_, quoted) = common::quoted_string('"', common::EscapeStyle::Doubled);
+        let patterns = vec![
+            ("field".into(), format!(r#"{quoted}|[^{delimiter}"\r\n]+"#)),
+            ("separator".into(), delimiter),
+            ("newline".into(), common::NEWLINE.1.clone()),
+        ];
+        Self::default().with_patterns(patterns)
+    }
+
+    /// Creates a new [`Tokenizer`] from the given [`TokenizerConfig`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error in the same cases as [`Tokenizer::new`] (unless
+    /// [`allow_duplicate_names`](TokenizerConfig::allow_duplicate_names) is set), or if any
+    /// [escapes](Tokenizer#escapes) regex is invalid.
+    pub fn from_config(config: &'a TokenizerConfig) -> Result<Self, Error> {
+        let literals: Vec<_> = config
+            .literals
+            .iter()
+            .map(|(name, literal)| (name.as_str(), literal.as_str()))
+            .collect();
+
+        let mut tokenizer = Self::default()
+            .with_allow_duplicate_names(config.allow_duplicate_names)
+            .with_longest_match(config.longest_match)
+            .with_max_token_length(config.max_token_length)
+            .with_backtrack_limit(config.backtrack_limit)?
+            .with_emit_eof(config.emit_eof)
+            .with_ignore_whitespace(config.ignore_whitespace)
+            .with_ignored_characters(config.ignored_characters.clone())
+            .with_literals(&literals)?
+            .with_patterns(config.patterns.clone())?;
+        for (name, escapes) in &config.escapes {
+            tokenizer.set_escapes(name, Some(escapes))?;
+        }
+        Ok(tokenizer)
+    }
+
+    /// Checks the (name, literal) pairs of the [literals](Tokenizer#literals) about to be set
+    /// against the other rules, unless [`allow_duplicate_names`](Tokenizer#allow_duplicate_names)
+    /// is enabled. Literals sharing a name are aliases of each other, so they aren't checked
+    /// against each other.
+    fn validate_literal_names<'n>(
+        &self,
+        literals: impl IntoIterator<Item = (&'n str, &'n str)>,
+    ) -> Result<(), Error> {
+        if self.allow_duplicate_names {
+            return Ok(());
+        }
+        literals
+            .into_iter()
+            .find(|&(name, literal)| {
+                self.patterns
+                    .iter()
+                    .any(|pattern| pattern.0 == name && !matches_literal(pattern, literal))
+                    || self.skip_patterns.iter().any(|(skip, _, _)| skip == name)
+                    || self.is_name_used_by_added_rules(name)
+            })
+            .map_or(Ok(()), |(name, _)| Err(Error::DuplicateName(name.into())))
+    }
+
+    /// Checks the names of the [patterns](Tokenizer#patterns) about to be set, unless
+    /// [`allow_duplicate_names`](Tokenizer#allow_duplicate_names) is enabled. The current
+    /// patterns are being replaced, so only the other rules are checked against.
+    fn validate_pattern_names(&self, patterns: &[Pattern]) -> Result<(), Error> {
+        if self.allow_duplicate_names {
+            return Ok(());
+        }
+        for (i, pattern) in patterns.iter().enumerate() {
+            let name = pattern.0.as_str();
+            if let Some(error) = self.pattern_name_error(pattern, &patterns[..i]) {
+                return Err(error);
+            }
+            if self.skip_patterns.iter().any(|(skip, _, _)| skip == name)
+                || self.is_name_used_by_added_rules(name)
+            {
+                return Err(Error::DuplicateName(name.into()));
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks the names of the [skip patterns](Tokenizer#skip_patterns) about to be set, unless
+    /// [`allow_duplicate_names`](Tokenizer#allow_duplicate_names) is enabled. The current skip
+    /// patterns are being replaced, so only the other rules are checked against.
+    fn validate_skip_pattern_names(&self, skip_patterns: &[Pattern]) -> Result<(), Error> {
+        if self.allow_duplicate_names {
+            return Ok(());
+        }
+        let mut seen = FxHashSet::default();
+        for (name, _, _) in skip_patterns {
+            if !seen.insert(name) {
+                return Err(Error::DuplicatePattern(name.clone()));
+            }
+            if self.literals.values().any(|&literal| literal == name)
+                || self.patterns.iter().any(|(pattern, _, _)| pattern == name)
+                || self.is_name_used_by_added_rules(name)
+            {
+                return Err(Error::DuplicateName(name.clone()));
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks the name of a [pattern function](Tokenizer#pattern-functions),
+    /// [balanced span](Tokenizer#balanced-spans) or [heredoc](Tokenizer#heredocs) about to be
+    /// added against all the rules, unless [`allow_duplicate_names`](Tokenizer#allow_duplicate_names)
+    /// is enabled.
+    fn validate_added_name(&self, name: &str) -> Result<(), Error> {
+        if self.allow_duplicate_names
+            || !(self.literals.values().any(|&literal| literal == name)
+                || self.patterns.iter().any(|(pattern, _, _)| pattern == name)
+                || self.skip_patterns.iter().any(|(skip, _, _)| skip == name)
+                || self.is_name_used_by_added_rules(name))
+        {
+            return Ok(());
+        }
+        Err(Error::DuplicateName(name.into()))
+    }
+
+    /// Returns the error for a [pattern](Tokenizer#patterns) sharing its name with one of the
+    /// `earlier` patterns or with a literal, if any. Repeating a pattern, or matching just
+    /// a literal of the same name, is redundant rather than ambiguous, so it's not an error.
+    fn pattern_name_error(&self, pattern: &Pattern, earlier: &[Pattern]) -> Option<Error> {
+        let name = pattern.0.as_str();
+        if earlier
+            .iter()
+            .any(|other| other.0 == name && !is_same_pattern(other, pattern))
+        {
+            return Some(Error::DuplicatePattern(name.into()));
+        }
+        self.literals
+            .iter()
+            .any(|(&literal, &literal_name)| {
+                literal_name == name && !matches_literal(pattern, literal)
+            })
+            .then(|| Error::DuplicateName(name.into()))
+    }
+
+    /// Returns whether the name is used by any pattern function, balanced span or heredoc.
+    fn is_name_used_by_added_rules(&self, name: &str) -> bool {
+        self.pattern_fns.iter().any(|pattern| pattern.name == name)
+            || self
+                .balanced
+                .iter()
+                .any(|(balanced, _, _)| balanced == name)
+            || self.heredocs.iter().any(|(heredoc, _, _)| heredoc == name)
+    }
+
+    /// Returns the names shared by several rules, in the order of their second use, unless
+    /// [`allow_duplicate_names`](Tokenizer#allow_duplicate_names) is enabled. Literals sharing
+    /// a name are aliases of each other, so their name only counts once, just like the name of
+    /// a redundant pattern.
+    fn duplicate_names(&self) -> Vec<&str> {
+        if self.allow_duplicate_names {
+            return Vec::new();
+        }
+        let mut duplicates = Vec::new();
+        for (i, pattern) in self.patterns.iter().enumerate() {
+            if self
+                .pattern_name_error(pattern, &self.patterns[..i])
+                .is_some()
+                && !duplicates.contains(&pattern.0.as_str())
+            {
+                duplicates.push(pattern.0.as_str());
+            }
+        }
+
+        let mut seen: FxHashSet<_> = self.literals.values().copied().collect();
+        seen.extend(self.patterns.iter().map(|(name, _, _)| name.as_str()));
+        let names = self
+            .skip_patterns
+            .iter()
+            .map(|(name, _, _)| name.as_str())
+            .chain(self.pattern_fns.iter().map(|pattern| pattern.name.as_str()))
+            .chain(self.balanced.iter().map(|(name, _, _)| name.as_str()))
+            .chain(self.heredocs.iter().map(|(name, _, _)| name.as_str()));
+        for name in names {
+            if !seen.insert(name) && !duplicates.contains(&name) {
+                duplicates.push(name);
+            }
+        }
+        duplicates
     }
 
     fn can_use_fast_mode(&self) -> bool {
-        self.patterns.is_empty() && self.literals.keys().all(|v| v.len() == 1)
+        // all tokens are a single byte long, so only a zero limit can be exceeded
+        self.patterns.is_empty()
+            && self.skip_patterns.is_empty()
+            && self.pattern_fns.is_empty()
+            && self.balanced.is_empty()
+            && self.heredocs.is_empty()
+            && self.literals.keys().all(|v| v.len() == 1)
+            && self.max_token_length != Some(0)
     }
 
     fn prepare_ignored(&self) -> FxHashSet<char> {
@@ -179,669 +878,3968 @@ impl<'a> Tokenizer<'a> {
     }
 
     /// Tokenizes the given source code and returns an [`Iterator`] of [`Token`]s.
+    ///
+    /// Tokenization never panics, regardless of the configuration and the source. Characters that
+    /// cannot be tokenized are reported as [`Error::BadToken`] and skipped, and pattern matches of
+    /// zero length are never accepted, so the tokenizer is guaranteed to make progress. If no token
+    /// matches at a position where a pattern matches an empty string, the character is reported as
+    /// [`Error::ZeroWidthMatch`] instead, as that usually points to a mistake in the pattern (see
+    /// also [`Tokenizer::zero_width_patterns`]).
+    ///
+    /// To collect all tokens, stopping at the first error, use [`Tokenizer::try_tokenize`].
+    ///
+    /// The returned [`Iterator`] is fused, i.e. it keeps returning [`None`] once it has returned
+    /// it. Its [`size_hint`](Iterator::size_hint) is bounded by the number of remaining bytes, as
+    /// every token and error consumes at least one byte (plus the
+    /// [end-of-input token](Tokenizer#emit_eof), if enabled).
     #[must_use]
     pub fn tokenize(
         &'a self,
         source: &'a str,
-    ) -> Box<dyn Iterator<Item = Result<Token<'a>, Error>> + 'a> {
-        let ignored = self.prepare_ignored();
+    ) -> Box<dyn FusedIterator<Item = Result<Token<'a>, Error>> + 'a> {
+        let tokens = self.tokenize_raw(source);
+        if self.emit_eof {
+            Box::new(stream::WithEof::new(tokens, source.len()))
+        } else {
+            tokens
+        }
+    }
+
+    /// Tokenizes the given source code, ignoring [`emit_eof`](Tokenizer#emit_eof).
+    fn tokenize_raw(
+        &'a self,
+        source: &'a str,
+    ) -> Box<dyn FusedIterator<Item = Result<Token<'a>, Error>> + 'a> {
+        let ignored = stream::Ignored::new(self.prepare_ignored(), self.ignore_whitespace);
         if self.can_use_fast_mode() {
             Box::new(stream::Fast::new(self, source, ignored))
         } else {
-            Box::new(stream::Core::new(self, source, ignored))
+            Box::new(stream::Core::new(self, source, Cow::Owned(ignored)))
         }
     }
 
-    /// Splits the given source code into lines and tokenizes each line separately.
-    /// Returns an [`Iterator`] of [`Vec`]s of [`Token`]s.
+    /// Tokenizes the given source code from its end towards its start, returning the [`Token`]s
+    /// in reverse order, e.g. for formats where the structure is given by the suffixes. The
+    /// positions are still byte offsets from the start of the source.
+    ///
+    /// Only [literals](Tokenizer#literals) can be matched in reverse, the longest literal ending
+    /// at the current position wins. Ignored characters are skipped and the
+    /// [end-of-input token](Tokenizer#emit_eof), if enabled, is emitted last at position 0.
+    ///
+    /// Note that the result can differ from the reversed output of [`Tokenizer::tokenize`], e.g.
+    /// with the literals `a`, `ab`, `bc`, and `c`, `abc` is tokenized as `a` and `bc` in reverse,
+    /// but as `ab` and `c` forwards.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use crossandra::Tokenizer;
+    /// let tok = Tokenizer::default()
+    ///     .with_literals(&[("k", "k"), ("m", "m"), ("km", "km"), ("num", "5")])
+    ///     .unwrap();
+    /// let tokens: Vec<_> = tok
+    ///     .tokenize_rev("5km")
+    ///     .unwrap()
+    ///     .map(|token| token.map(|token| (token.name, token.position)))
+    ///     .collect::<Result<_, _>>()
+    ///     .unwrap();
+    /// assert_eq!(tokens, [("km", 1), ("num", 0)]);
+    /// ```
     ///
     /// # Errors
     ///
-    /// This function will return an error if any line fails to tokenize.
-    #[must_use]
-    pub fn tokenize_lines(
+    /// Returns [`Error::UnsupportedPattern`] with the name of the first
+    /// [pattern](Tokenizer#patterns) (or [skip pattern](Tokenizer#skip_patterns),
+    /// [pattern function](Tokenizer#pattern-functions), [balanced span](Tokenizer#balanced-spans),
+    /// or [heredoc](Tokenizer#heredocs)) if there are any, as only literals can be matched from the
+    /// end.
+    ///
+    /// Errors during tokenization are reported in the same way as by [`Tokenizer::tokenize`].
+    pub fn tokenize_rev(
         &'a self,
         source: &'a str,
-    ) -> impl ParallelIterator<Item = Result<Vec<Token<'a>>, Error>> + 'a {
-        source
-            .par_split('\n')
-            .map(|line| self.tokenize(line).collect())
+    ) -> Result<Box<dyn Iterator<Item = Result<Token<'a>, Error>> + 'a>, Error> {
+        if let Some((name, _, _)) = self.patterns.iter().chain(&self.skip_patterns).next() {
+            return Err(Error::UnsupportedPattern(name.clone()));
+        }
+        if let Some(pattern) = self.pattern_fns.first() {
+            return Err(Error::UnsupportedPattern(pattern.name.clone()));
+        }
+        if let Some((name, _, _)) = self.balanced.first() {
+            return Err(Error::UnsupportedPattern(name.clone()));
+        }
+        if let Some((name, _, _)) = self.heredocs.first() {
+            return Err(Error::UnsupportedPattern(name.clone()));
+        }
+        let ignored = stream::Ignored::new(self.prepare_ignored(), self.ignore_whitespace);
+        let tokens = rev::Reverse::new(self, source, ignored);
+        Ok(if self.emit_eof {
+            Box::new(stream::WithEof::new(tokens, 0))
+        } else {
+            Box::new(tokens)
+        })
     }
 
-    /// Sets the [literals](Tokenizer#literals) of this [`Tokenizer`] and returns itself.
+    /// Counts the [`Token`]s in the given source code, without constructing them.
     ///
     /// # Errors
-    /// This function will return an error if any literal is empty.
-    pub fn with_literals(mut self, literals: &[(&'a str, &'a str)]) -> Result<Self, Error> {
-        self.set_literals(literals)?;
-        Ok(self)
+    ///
+    /// This function will return the first [`Error`] encountered during tokenization.
+    pub fn count(&'a self, source: &'a str) -> Result<usize, Error> {
+        let ignored = stream::Ignored::new(self.prepare_ignored(), self.ignore_whitespace);
+        let count = if self.can_use_fast_mode() {
+            stream::Fast::new(self, source, ignored).count_tokens()
+        } else {
+            stream::Core::new(self, source, Cow::Owned(ignored)).count_tokens()
+        }?;
+        Ok(count + usize::from(self.emit_eof))
     }
 
-    /// Sets the [patterns](Tokenizer#patterns) of this [`Tokenizer`] and returns itself.
+    /// Tokenizes the given source code and returns a [`TokenizeSummary`] instead of the tokens,
+    /// e.g. to tell apart an empty source from one consisting only of ignored characters.
     ///
     /// # Errors
     ///
-    /// This function will return an error if:
-    /// * there are duplicate patterns, or
-    /// * any pattern regex is invalid.
-    pub fn with_patterns(mut self, patterns: Vec<(String, String)>) -> Result<Self, Error> {
-        self.set_patterns(patterns)?;
-        Ok(self)
-    }
-
-    /// Sets the [ignored characters](Tokenizer#ignored_characters) of this [`Tokenizer`] and
-    /// returns itself.
-    #[must_use]
-    pub fn with_ignored_characters(mut self, ignored_characters: FxHashSet<char>) -> Self {
-        self.ignored_characters = ignored_characters;
-        self
+    /// This function will return the first [`Error`] encountered during tokenization.
+    pub fn summarize(&'a self, source: &'a str) -> Result<TokenizeSummary, Error> {
+        let mut summary = TokenizeSummary::default();
+        let mut token_chars = 0;
+        for token in self.tokenize_raw(source) {
+            let token = token?;
+            summary.tokens += 1;
+            summary.bytes_consumed += token.value.len();
+            token_chars += token.value.chars().count();
+        }
+        // all the characters outside of tokens had to be ignored
+        summary.skipped = source.chars().count() - token_chars;
+        Ok(summary)
     }
 
-    /// Sets the [`ignore_whitespace`](Tokenizer#ignore_whitespace) option of this [`Tokenizer`] and
-    /// returns itself.
-    #[must_use]
-    pub fn with_ignore_whitespace(mut self, ignore_whitespace: bool) -> Self {
-        self.ignore_whitespace = ignore_whitespace;
-        self
+    /// Tokenizes the given source code like [`Tokenizer::tokenize`], additionally reporting the
+    /// parts of the source that were skipped instead of producing tokens, i.e. runs of ignored
+    /// characters and [skip pattern](Tokenizer#skip_patterns) matches, e.g. to preserve the
+    /// formatting of the source. The skipped spans are yielded in the source order, interleaved
+    /// with the [`Token`]s.
+    ///
+    /// Characters skipped after an [`Error`] (e.g. [`Error::BadToken`]) aren't reported again.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use crossandra::{SkipReason, Tokenizer, TokenOrSkipped};
+    /// let tok = Tokenizer::default()
+    ///     .with_literals(&[("a", "a")])
+    ///     .unwrap()
+    ///     .with_skip_patterns(&[("comment", "#[^\n]*")])
+    ///     .unwrap()
+    ///     .with_ignore_whitespace(true);
+    /// let items: Vec<_> = tok.tokenize_with_skipped("a #x\n").map(Result::unwrap).collect();
+    /// assert!(matches!(items[0], TokenOrSkipped::Token(_)));
+    /// assert_eq!(
+    ///     items[1..],
+    ///     [
+    ///         TokenOrSkipped::Skipped { span: 1..2, reason: SkipReason::Ignored },
+    ///         TokenOrSkipped::Skipped { span: 2..4, reason: SkipReason::SkipPattern("comment") },
+    ///         TokenOrSkipped::Skipped { span: 4..5, reason: SkipReason::Ignored },
+    ///     ]
+    /// );
+    /// ```
+    pub fn tokenize_with_skipped(
+        &'a self,
+        source: &'a str,
+    ) -> impl Iterator<Item = Result<TokenOrSkipped<'a>, Error>> + 'a {
+        let ignored = stream::Ignored::new(self.prepare_ignored(), self.ignore_whitespace);
+        let core = stream::Core::new(self, source, Cow::Owned(ignored));
+        skipped::WithSkipped::new(core, self.emit_eof.then_some(source.len()))
     }
 
-    /// Sets the [literals](Tokenizer#literals) of this [`Tokenizer`].
+    /// Tokenizes the given source code, calling `f` for each [`Token`] instead of collecting them,
+    /// e.g. to build a symbol table or count token kinds in a single pass.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use crossandra::{FxHashMap, Tokenizer};
+    /// let tok = Tokenizer::default()
+    ///     .with_literals(&[("a", "a"), ("b", "b")])
+    ///     .unwrap();
+    /// let mut counts = FxHashMap::default();
+    /// tok.tokenize_with("abba", |token| *counts.entry(token.name).or_insert(0) += 1)
+    ///     .unwrap();
+    /// assert_eq!(counts["b"], 2);
+    /// ```
     ///
     /// # Errors
     ///
-    /// This function will return an error if any literal is empty.
-    pub fn set_literals(&mut self, literals: &[(&'a str, &'a str)]) -> Result<(), Error> {
-        validate_literals(literals)?;
-        self.literals = stream::build_hashmap(literals);
-        self.tree = generate_tree(&self.literals);
+    /// This function will return the first [`Error`] encountered during tokenization, after `f`
+    /// has been called for all the preceding tokens.
+    pub fn tokenize_with<F: FnMut(&Token<'a>)>(
+        &'a self,
+        source: &'a str,
+        mut f: F,
+    ) -> Result<(), Error> {
+        for result in self.tokenize(source) {
+            f(&result?);
+        }
         Ok(())
     }
 
-    /// Sets the [patterns](Tokenizer#patterns) of this [`Tokenizer`].
+    /// Matches the whole input as a single [`Token`], e.g. to validate that a string is a valid
+    /// number. Ignored characters around the token are allowed.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use crossandra::{common, Error, Tokenizer};
+    /// let tok = Tokenizer::default()
+    ///     .with_patterns(vec![common::FLOAT.clone()])
+    ///     .unwrap();
+    /// assert_eq!(tok.match_full("2.5").unwrap().value, "2.5");
+    /// assert!(matches!(
+    ///     tok.match_full("2.5.1"),
+    ///     Err(Error::TrailingInput { position: 3 })
+    /// ));
+    /// ```
     ///
     /// # Errors
     ///
-    /// This function will return an error if:
-    /// * there are duplicate patterns, or
-    /// * any pattern regex is invalid.
-    pub fn set_patterns(&mut self, patterns: Vec<(String, String)>) -> Result<(), Error> {
-        self.patterns = patterns::prepare(patterns)?;
-        Ok(())
+    /// Returns [`Error::EmptyInput`] if the input contains no token, [`Error::TrailingInput`] with
+    /// the position where the extra input begins if it doesn't end after the first token, or the
+    /// [`Error`] encountered while matching the first token.
+    pub fn match_full(&'a self, input: &'a str) -> Result<Token<'a>, Error> {
+        let mut tokens = self.tokenize_raw(input);
+        let token = tokens.next().ok_or(Error::EmptyInput)??;
+        let position = match tokens.next() {
+            None => return Ok(token),
+            Some(Ok(next)) => next.position,
+            Some(Err(err)) => err.position().unwrap_or(token.position + token.value.len()),
+        };
+        Err(Error::TrailingInput { position })
     }
 
-    /// Sets the [ignored characters](Tokenizer#ignored_characters) of this [`Tokenizer`].
-    pub fn set_ignored_characters(&mut self, ignored_characters: FxHashSet<char>) {
-        self.ignored_characters = ignored_characters;
+    /// Returns the name and value of the token that would be matched starting exactly at `offset`
+    /// bytes into the input, without tokenizing the input up to that point.
+    ///
+    /// Ignored characters and [skip patterns](Tokenizer#skip_patterns) aren't skipped before
+    /// matching, so e.g. an offset pointing at a space usually yields [`None`]. Matching only
+    /// looks at the input from `offset` onwards, same as when tokenization reaches that offset.
+    ///
+    /// Returns [`None`] if no token matches, if matching fails (e.g. with an [`Error::Timeout`]),
+    /// or if `offset` is out of bounds or not on a character boundary.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use crossandra::{common, Tokenizer};
+    /// let tok = Tokenizer::default()
+    ///     .with_literals(&[("assign", "="), ("eq", "==")])
+    ///     .unwrap()
+    ///     .with_patterns(vec![common::INT.clone()])
+    ///     .unwrap();
+    /// assert_eq!(tok.probe("x == 12", 2), Some(("eq", "==")));
+    /// assert_eq!(tok.probe("x == 12", 5), Some(("int", "12")));
+    /// assert_eq!(tok.probe("x == 12", 6), Some(("int", "2")));
+    /// assert_eq!(tok.probe("x == 12", 1), None);
+    /// ```
+    #[must_use]
+    pub fn probe(&'a self, input: &'a str, offset: usize) -> Option<(&'a str, &'a str)> {
+        let ignored = stream::Ignored::new(self.prepare_ignored(), self.ignore_whitespace);
+        stream::Core::new(self, input, Cow::Owned(ignored)).probe(offset)
     }
 
-    /// Sets the [`ignore_whitespace`](Tokenizer#ignore_whitespace) option of this [`Tokenizer`].
-    pub fn set_ignore_whitespace(&mut self, ignore_whitespace: bool) {
-        self.ignore_whitespace = ignore_whitespace;
+    /// Searches the input for the first [`Token`], returning its position along with the token.
+    /// Unlike [`Tokenizer::tokenize`], characters that can't be tokenized are silently skipped,
+    /// e.g. to scan for known tokens in arbitrary text.
+    ///
+    /// Each position is tried in turn as in [`Tokenizer::probe`], so ignored characters and
+    /// [skip patterns](Tokenizer#skip_patterns) aren't treated specially and a token can also be
+    /// found in the middle of what [`Tokenizer::tokenize`] would consider another token (e.g.
+    /// inside a comment). Positions where no token can start are skipped without trying them.
+    ///
+    /// Returns [`None`] if no token matches anywhere in the input.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use crossandra::{common, Tokenizer};
+    /// let tok = Tokenizer::default()
+    ///     .with_patterns(vec![common::SEMVER.clone()])
+    ///     .unwrap();
+    /// let (position, token) = tok.find_first("Released as v1.2.3 today").unwrap();
+    /// assert_eq!((position, token.value), (13, "1.2.3"));
+    /// assert!(tok.find_first("nothing here").is_none());
+    /// ```
+    #[must_use]
+    pub fn find_first(&'a self, input: &'a str) -> Option<(usize, Token<'a>)> {
+        // ignored characters aren't treated specially, so none are needed
+        let ignored = stream::Ignored::new(FxHashSet::default(), false);
+        let token = stream::Core::new(self, input, Cow::Owned(ignored)).find_first()?;
+        Some((token.position, token))
     }
-}
 
-impl Default for Tokenizer<'_> {
-    fn default() -> Self {
-        Self::new(&[], Vec::new(), FxHashSet::default(), false)
-            .expect("an empty tokenizer should be correct")
+    /// Creates a [`Typed`] view of this [`Tokenizer`], assigning a kind (usually a variant of a
+    /// user-defined enum) to each [token name](Tokenizer::token_names), so the tokens can be
+    /// matched on their kinds instead of their names. Several names can share the same kind.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use crossandra::{common, Tokenizer};
+    /// #[derive(Debug, Clone, Copy, PartialEq)]
+    /// enum Kind {
+    ///     Operator,
+    ///     Number,
+    /// }
+    ///
+    /// let tok = Tokenizer::default()
+    ///     .with_literals(&[("add", "+"), ("sub", "-")])
+    ///     .unwrap()
+    ///     .with_patterns(vec![common::INT.clone()])
+    ///     .unwrap();
+    /// let typed = tok
+    ///     .typed(&[
+    ///         ("add", Kind::Operator),
+    ///         ("sub", Kind::Operator),
+    ///         ("int", Kind::Number),
+    ///     ])
+    ///     .unwrap();
+    /// let kinds: Vec<_> = typed.tokenize("1+2").map(|t| t.unwrap().kind).collect();
+    /// assert_eq!(kinds, [Kind::Number, Kind::Operator, Kind::Number]);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::MissingKind`] if any name in [`Tokenizer::token_names`] has no kind
+    /// assigned. Extra names are allowed.
+    pub fn typed<K: Clone>(&'a self, kinds: &[(&str, K)]) -> Result<Typed<'a, K>, Error> {
+        Typed::new(self, kinds)
     }
-}
-
-fn validate_literals<'a>(literals: &[(&'a str, &'a str)]) -> Result<(), Error> {
-    literals
-        .iter()
-        .all(|(_name, token)| !token.is_empty())
-        .then_some(())
-        .ok_or(Error::EmptyLiteral)
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
 
-    #[test]
-    fn literal_validation_err() {
-        assert!(matches!(
-            validate_literals(&[("x", "x"), ("y", "")]),
-            Err(Error::EmptyLiteral)
-        ));
+    /// Tokenizes the given source code, stopping at the first [`Error`]. Unlike
+    /// [`Tokenizer::tokenize`], the returned [`Iterator`] also provides the part of the source that
+    /// hasn't been consumed, e.g. to hand it over to another [`Tokenizer`].
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use crossandra::Tokenizer;
+    /// let tok = Tokenizer::default()
+    ///     .with_literals(&[("a", "a")])
+    ///     .unwrap();
+    /// let mut tokens = tok.tokenize_with_remainder("aa!a");
+    /// assert_eq!(tokens.by_ref().filter(Result::is_ok).count(), 2);
+    /// assert_eq!(tokens.remaining(), "!a");
+    /// ```
+    #[must_use]
+    pub fn tokenize_with_remainder(&'a self, source: &'a str) -> TokensWithRemainder<'a> {
+        TokensWithRemainder::new(self, source)
+    }
+
+    /// Tokenizes at most the first `n` [`Token`]s of the given source code, e.g. to preview a large
+    /// file, without scanning the rest. Returns the tokens along with the position right after the
+    /// last one (or the end of the source, if there are fewer than `n` tokens), from which
+    /// tokenization can be resumed using [`Tokenizer::tokenize_from`].
+    ///
+    /// Tokenization stops early at the first [`Error`], in which case the position is that of the
+    /// error, so resuming from it reports the error.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use crossandra::{common, Tokenizer, TokenizerState};
+    /// let tok = Tokenizer::default()
+    ///     .with_patterns(vec![common::WORD.clone()])
+    ///     .unwrap()
+    ///     .with_ignore_whitespace(true);
+    /// let source = "one two three four";
+    ///
+    /// let (tokens, position) = tok.tokenize_prefix(source, 2);
+    /// assert_eq!(tokens.len(), 2);
+    /// assert_eq!(position, 7);
+    ///
+    /// let rest: Vec<_> = tok
+    ///     .tokenize_from(source, TokenizerState::at(position))
+    ///     .map(|token| token.unwrap().value)
+    ///     .collect();
+    /// assert_eq!(rest, ["three", "four"]);
+    /// ```
+    #[must_use]
+    pub fn tokenize_prefix(&'a self, source: &'a str, n: usize) -> (Vec<Token<'a>>, usize) {
+        let mut tokens = self.tokenize_with_remainder(source);
+        let prefix = tokens.by_ref().take(n).map_while(Result::ok).collect();
+        (prefix, tokens.position())
+    }
+
+    /// Tokenizes the given source code and returns a [`TokenStream`], allowing to peek at any
+    /// number of upcoming [`Token`]s.
+    ///
+    /// See [`Tokenizer::tokenize`] for more details.
+    #[must_use]
+    pub fn token_stream(
+        &'a self,
+        source: &'a str,
+    ) -> TokenStream<Box<dyn FusedIterator<Item = Result<Token<'a>, Error>> + 'a>> {
+        TokenStream::new(self.tokenize(source))
+    }
+
+    /// Tokenizes the given source code and returns an [`Iterator`] of [`OwnedToken`]s, which
+    /// (unlike [`Token`]s) don't borrow from the [`Tokenizer`] nor the source once yielded.
+    ///
+    /// See [`Tokenizer::tokenize`] for more details.
+    pub fn tokenize_owned(
+        &'a self,
+        source: &'a str,
+    ) -> impl Iterator<Item = Result<OwnedToken, Error>> + 'a {
+        self.tokenize(source)
+            .map(|token| token.map(Token::into_owned))
+    }
+
+    /// Tokenizes the given source code starting from the given [`TokenizerState`], yielding the
+    /// same [`Token`]s (with the same positions) as [`Tokenizer::tokenize`] would after that point.
+    ///
+    /// See [`TokenizerState`] for more details.
+    ///
+    /// If the position of the state is past the end of the source or not on a UTF-8 character
+    /// boundary, only [`Error::InvalidState`] is yielded.
+    pub fn tokenize_from(
+        &'a self,
+        source: &'a str,
+        state: TokenizerState,
+    ) -> impl Iterator<Item = Result<Token<'a>, Error>> + 'a {
+        let base = state.position();
+        let rest = source.get(base..);
+        let error = rest
+            .is_none()
+            .then_some(Err(Error::InvalidState { position: base }));
+        rest.into_iter()
+            .flat_map(move |rest| self.tokenize(rest))
+            .map(move |result| shift_result(result, base))
+            .chain(error)
+    }
+
+    /// Tokenizes the given bytes, which don't have to be valid UTF-8 as a whole. Each maximal
+    /// valid UTF-8 run is tokenized as in [`Tokenizer::tokenize`] and each invalid sequence between
+    /// them is reported as [`Error::InvalidUtf8`] and skipped. All positions are byte offsets into
+    /// the whole input, so there is no need for a lossy conversion that would shift them.
+    ///
+    /// Tokens never span an invalid sequence, so e.g. a string literal containing one is reported
+    /// as [`Error::Unterminated`]. As a result, the value of every [`Token`] is still a `&str`
+    /// rather than a `&[u8]`, and there is no separate byte mode: literals and patterns work the
+    /// same as with [`Tokenizer::tokenize`], including non-ASCII ones, and no pattern features are
+    /// unsupported.
+    ///
+    /// Just like the one returned by [`Tokenizer::tokenize`], the returned [`Iterator`] is fused.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use crossandra::{common, Error, Tokenizer};
+    /// let tok = Tokenizer::default()
+    ///     .with_patterns(vec![common::WORD.clone()])
+    ///     .unwrap()
+    ///     .with_ignore_whitespace(true);
+    /// let results: Vec<_> = tok.tokenize_bytes(b"ab \xff cd").collect();
+    /// assert_eq!(results[0].as_ref().unwrap().value, "ab");
+    /// assert!(matches!(results[1], Err(Error::InvalidUtf8 { position: 3 })));
+    /// assert_eq!(results[2].as_ref().unwrap().position, 5);
+    /// ```
+    #[must_use]
+    pub fn tokenize_bytes(
+        &'a self,
+        input: &'a [u8],
+    ) -> Box<dyn FusedIterator<Item = Result<Token<'a>, Error>> + 'a> {
+        let mut offset = 0;
+        let tokens = input.utf8_chunks().flat_map(move |chunk| {
+            let base = offset;
+            let valid = chunk.valid();
+            offset += valid.len() + chunk.invalid().len();
+
+            let invalid = (!chunk.invalid().is_empty()).then(|| {
+                Err(Error::InvalidUtf8 {
+                    position: base + valid.len(),
+                })
+            });
+            self.tokenize_raw(valid)
+                .map(move |result| shift_result(result, base))
+                .chain(invalid)
+        });
+        if self.emit_eof {
+            Box::new(stream::WithEof::new(tokens, input.len()))
+        } else {
+            Box::new(tokens)
+        }
+    }
+
+    /// Tokenizes the source code read from the given [`BufRead`](std::io::BufRead) and returns an
+    /// [`Iterator`] of [`OwnedToken`]s, so the whole source doesn't have to be held in memory.
+    ///
+    /// The source is read in chunks, and whenever a token could continue past the end of what has
+    /// been read so far, more is read before deciding. The size of a single token is therefore
+    /// limited, see [`ReaderTokens::with_max_buffer_size`]. Positions are byte offsets from the
+    /// start of the whole source.
+    ///
+    /// Reading errors and invalid UTF-8 are reported as [`Error::Io`] and stop the iteration.
+    ///
+    /// ```rust
+    /// # use crossandra::Tokenizer;
+    /// let tok = Tokenizer::default().with_literals(&[("a", "a")]).unwrap();
+    /// let reader = std::io::Cursor::new("aaa");
+    /// assert_eq!(tok.tokenize_reader(reader).count(), 3);
+    /// ```
+    pub fn tokenize_reader<R: std::io::BufRead>(&'a self, reader: R) -> ReaderTokens<'a, R> {
+        ReaderTokens::new(
+            self,
+            reader,
+            stream::Ignored::new(self.prepare_ignored(), self.ignore_whitespace),
+        )
+    }
+
+    /// Tokenizes the given source code and collects the [`Token`]s into a [`Vec`], stopping at the
+    /// first error. Just like [`Tokenizer::tokenize`], this never panics.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use crossandra::{Error, Tokenizer};
+    /// let tok = Tokenizer::default()
+    ///     .with_literals(&[("a", "a")])
+    ///     .unwrap();
+    /// assert_eq!(tok.try_tokenize("aa").unwrap().len(), 2);
+    /// assert!(matches!(tok.try_tokenize("a?a?"), Err(Error::BadToken('?', 1))));
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// This function will return the first error encountered during tokenization.
+    #[doc(alias = "tokenize_vec")]
+    pub fn try_tokenize(&'a self, source: &'a str) -> Result<Vec<Token<'a>>, Error> {
+        self.tokenize(source).collect()
+    }
+
+    /// Tokenizes the given source code, collecting all valid [`Token`]s and all [`Error`]s
+    /// separately instead of stopping at the first error. Characters that cannot be tokenized are
+    /// recorded as [`Error::BadToken`] and skipped, so tokenization continues after them. The same
+    /// goes for the opening sequence of an [`Error::Unterminated`] token.
+    ///
+    /// Error positions are byte offsets into the source, consistent with [`Token::position`].
+    #[must_use]
+    pub fn tokenize_recover(&'a self, source: &'a str) -> (Vec<Token<'a>>, Vec<Error>) {
+        let mut tokens = Vec::new();
+        let mut errors = Vec::new();
+        for result in self.tokenize(source) {
+            match result {
+                Ok(token) => tokens.push(token),
+                Err(err) => errors.push(err),
+            }
+        }
+        (tokens, errors)
+    }
+
+    /// Tokenizes the given source code like [`Tokenizer::tokenize`], but reports characters that
+    /// cannot be tokenized right after a [`Token`] as [`Error::BadTokenAfter`], carrying the name
+    /// and span of that token, e.g. for messages like "unexpected `@` after the identifier `foo`".
+    ///
+    /// Only ignored characters may separate the two. Characters following another error, or at
+    /// the start of the source, are still reported as [`Error::BadToken`].
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use crossandra::{common, Error, Tokenizer};
+    /// let tok = Tokenizer::default()
+    ///     .with_patterns(vec![common::C_NAME.clone()])
+    ///     .unwrap()
+    ///     .with_ignore_whitespace(true);
+    /// let errors: Vec<_> = tok
+    ///     .tokenize_with_context("@ foo @#")
+    ///     .filter_map(Result::err)
+    ///     .collect();
+    /// assert!(matches!(
+    ///     &errors[..],
+    ///     [
+    ///         Error::BadToken('@', 0),
+    ///         Error::BadTokenAfter { character: '@', position: 6, previous, previous_span },
+    ///         Error::BadToken('#', 7),
+    ///     ] if previous == "c_name" && previous_span == &(2..5)
+    /// ));
+    /// ```
+    pub fn tokenize_with_context(
+        &'a self,
+        source: &'a str,
+    ) -> impl Iterator<Item = Result<Token<'a>, Error>> + 'a {
+        let mut previous = None;
+        self.tokenize(source).map(move |result| match result {
+            Ok(token) => {
+                previous = Some((
+                    token.name,
+                    token.position..token.position + token.value.len(),
+                ));
+                Ok(token)
+            }
+            Err(Error::BadToken(character, position)) => Err(match previous.take() {
+                Some((name, span)) => Error::BadTokenAfter {
+                    character,
+                    position,
+                    previous: name.into(),
+                    previous_span: span,
+                },
+                None => Error::BadToken(character, position),
+            }),
+            Err(err) => {
+                previous = None;
+                Err(err)
+            }
+        })
+    }
+
+    /// Splits the given source code on `\n` and tokenizes the lines in parallel. Returns
+    /// a [`ParallelIterator`] of the [`Vec`]s of [`Token`]s of each line, which keeps the order of
+    /// the lines when collected.
+    ///
+    /// This is only equivalent to [`Tokenizer::tokenize`] if no token can span multiple lines,
+    /// i.e. no literal contains `\n` and no pattern can match across a newline. Token and error
+    /// positions are relative to the start of their line; use
+    /// [`LineIndex::to_absolute_positions`] to convert them to offsets into the whole source.
+    ///
+    /// # Errors
+    ///
+    /// Each line that fails to tokenize yields the first [`Error`] encountered in it.
+    #[cfg(feature = "rayon")]
+    #[must_use]
+    pub fn tokenize_lines(
+        &'a self,
+        source: &'a str,
+    ) -> impl ParallelIterator<Item = Result<Vec<Token<'a>>, Error>> + 'a {
+        source
+            .par_split('\n')
+            .map(|line| self.tokenize_raw(line).collect())
+    }
+
+    /// Returns the prefix [`Tree`] of the [literals](Tokenizer#literals) of this [`Tokenizer`],
+    /// e.g. to find all literals a given input starts with.
+    ///
+    /// ```rust
+    /// # use crossandra::Tokenizer;
+    /// let tok = Tokenizer::default()
+    ///     .with_literals(&[("lt", "<"), ("le", "<="), ("shl", "<<")])
+    ///     .unwrap();
+    /// assert_eq!(tok.tree().match_all_prefixes("<=>"), vec![("<", &"lt"), ("<=", &"le")]);
+    /// ```
+    #[must_use]
+    pub fn tree(&self) -> &LiteralTree<'a> {
+        &self.tree
+    }
+
+    /// Returns the length (in bytes) of the longest [literal](Tokenizer#literals) of this
+    /// [`Tokenizer`], or 0 if there are no literals, e.g. to size a buffer so that it never splits
+    /// a literal.
+    ///
+    /// ```rust
+    /// # use crossandra::Tokenizer;
+    /// let tok = Tokenizer::default()
+    ///     .with_literals(&[("lt", "<"), ("spaceship", "<=>"), ("arrow", "→")])
+    ///     .unwrap();
+    /// assert_eq!(tok.max_literal_len(), 3);
+    /// assert_eq!(Tokenizer::default().max_literal_len(), 0);
+    /// ```
+    #[must_use]
+    pub fn max_literal_len(&self) -> usize {
+        self.tree.max_literal_len()
+    }
+
+    /// Returns the number of [literals](Tokenizer#literals) of this [`Tokenizer`]. Each
+    /// [alias](Tokenizer::set_literal_aliases) counts as a separate literal.
+    ///
+    /// ```rust
+    /// # use crossandra::Tokenizer;
+    /// let tok = Tokenizer::default()
+    ///     .with_literals(&[("lt", "<"), ("le", "<=")])
+    ///     .unwrap()
+    ///     .with_literal_aliases("arrow", &["->", "→"])
+    ///     .unwrap();
+    /// assert_eq!(tok.literal_count(), 4);
+    /// ```
+    #[must_use]
+    pub fn literal_count(&self) -> usize {
+        self.tree.len()
+    }
+
+    /// Returns the number of [patterns](Tokenizer#patterns) of this [`Tokenizer`], not including
+    /// the [skip patterns](Tokenizer#skip_patterns).
+    ///
+    /// ```rust
+    /// # use crossandra::{common, Tokenizer};
+    /// let tok = Tokenizer::default()
+    ///     .with_patterns(vec![common::INT.clone(), common::C_NAME.clone()])
+    ///     .unwrap();
+    /// assert_eq!(tok.pattern_count(), 2);
+    /// ```
+    #[must_use]
+    pub fn pattern_count(&self) -> usize {
+        self.patterns.len()
+    }
+
+    /// Returns `true` if this [`Tokenizer`] has no [literals](Tokenizer#literals),
+    /// [patterns](Tokenizer#patterns), [pattern functions](Tokenizer#pattern-functions),
+    /// [balanced spans](Tokenizer#balanced-spans) or [heredocs](Tokenizer#heredocs), i.e. it can't
+    /// produce any tokens. Any input that doesn't consist only of ignored characters (or
+    /// [skip patterns](Tokenizer#skip_patterns)) then results in an error.
+    ///
+    /// ```rust
+    /// # use crossandra::Tokenizer;
+    /// assert!(Tokenizer::default().is_empty());
+    /// assert!(!Tokenizer::default().with_literals(&[("a", "a")]).unwrap().is_empty());
+    /// ```
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+            && self.patterns.is_empty()
+            && self.pattern_fns.is_empty()
+            && self.balanced.is_empty()
+            && self.heredocs.is_empty()
+    }
+
+    /// Returns the (name, literal) pairs of this [`Tokenizer`], sorted by the literal length in
+    /// descending order (and alphabetically for literals of the same length).
+    ///
+    /// This is the order in which literals take precedence during tokenization, i.e. a longer
+    /// literal always beats a shorter one that is its prefix.
+    #[must_use]
+    pub fn literals_sorted(&self) -> Vec<(&'a str, String)> {
+        let mut literals: Vec<_> = self
+            .tree
+            .entries()
+            .map(|(literal, &name)| (name, literal))
+            .collect();
+        literals.sort_by(|(_, a), (_, b)| b.len().cmp(&a.len()).then_with(|| a.cmp(b)));
+        literals
+    }
+
+    /// Returns the names of the [patterns](Tokenizer#patterns) matching an empty string, in their
+    /// declaration order. Such patterns (e.g. `a*` instead of `a+`) are likely a mistake, so this
+    /// can be used to validate patterns when building a [`Tokenizer`]. Note that patterns only
+    /// matching an empty string in some context (e.g. `\b`) are not detected.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use crossandra::Tokenizer;
+    /// let tok = Tokenizer::default()
+    ///     .with_patterns(vec![
+    ///         ("digits".into(), r"\d*".into()),
+    ///         ("letters".into(), r"[a-z]+".into()),
+    ///     ])
+    ///     .unwrap();
+    /// assert_eq!(tok.zero_width_patterns(), ["digits"]);
+    /// ```
+    #[must_use]
+    pub fn zero_width_patterns(&self) -> Vec<&str> {
+        self.patterns
+            .iter()
+            .filter(|(_, pattern, _)| patterns::matches_empty(pattern))
+            .map(|(name, _, _)| name.as_str())
+            .collect()
+    }
+
+    /// Checks this [`Tokenizer`] for likely mistakes, reporting all of them at once.
+    ///
+    /// Invalid regexes, empty literals and duplicate names are already rejected when building
+    /// a [`Tokenizer`], so this reports each name shared by several rules as an
+    /// [`Error::DuplicateName`] only if it slipped through, i.e. if
+    /// [`allow_duplicate_names`](Tokenizer#allow_duplicate_names) was enabled while setting the
+    /// rules and disabled afterwards. The
+    /// [patterns](Tokenizer#patterns) matching an empty string follow as
+    /// [`Error::ZeroWidthPattern`] (see [`Tokenizer::zero_width_patterns`]). To check
+    /// a configuration before building a [`Tokenizer`] from it, use [`TokenizerConfig::validate`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return all the problems found.
+    pub fn validate(&self) -> Result<(), Vec<Error>> {
+        let duplicates = self
+            .duplicate_names()
+            .into_iter()
+            .map(|name| Error::DuplicateName(name.into()));
+        let zero_width = self
+            .zero_width_patterns()
+            .into_iter()
+            .map(|name| Error::ZeroWidthPattern(name.into()));
+        let errors: Vec<_> = duplicates.chain(zero_width).collect();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Returns the names of all tokens this [`Tokenizer`] can produce, e.g. to build a legend for
+    /// syntax highlighting.
+    ///
+    /// The [literal](Tokenizer#literals) names come first, in the order of
+    /// [`Tokenizer::literals_sorted`], followed by the [pattern](Tokenizer#patterns) names in their
+    /// declaration order and the [end-of-input token](Tokenizer#emit_eof) name, if enabled. Each
+    /// name is only listed once.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use crossandra::{common, Tokenizer};
+    /// let tok = Tokenizer::default()
+    ///     .with_literals(&[("add", "+"), ("sub", "-"), ("assign", ":=")])
+    ///     .unwrap()
+    ///     .with_patterns(vec![common::INT.clone(), common::C_NAME.clone()])
+    ///     .unwrap();
+    /// assert_eq!(tok.token_names(), ["assign", "add", "sub", "int", "c_name"]);
+    /// ```
+    #[must_use]
+    pub fn token_names(&self) -> Vec<&str> {
+        let literals = self.literals_sorted().into_iter().map(|(name, _)| name);
+        let patterns = self.patterns.iter().map(|(name, _, _)| name.as_str());
+        let pattern_fns = self.pattern_fns.iter().map(|pattern| pattern.name.as_str());
+        let balanced = self.balanced.iter().map(|(name, _, _)| name.as_str());
+        let heredocs = self.heredocs.iter().map(|(name, _, _)| name.as_str());
+        let eof = self.emit_eof.then_some(stream::EOF);
+
+        let mut seen = FxHashSet::default();
+        literals
+            .chain(patterns)
+            .chain(pattern_fns)
+            .chain(balanced)
+            .chain(heredocs)
+            .chain(eof)
+            .filter(|name| seen.insert(*name))
+            .collect()
+    }
+
+    /// Sets the [literals](Tokenizer#literals) of this [`Tokenizer`] and returns itself.
+    ///
+    /// # Errors
+    /// This function will return an error if:
+    /// * any literal is empty, or
+    /// * a literal name is already used by a pattern or another rule.
+    pub fn with_literals(mut self, literals: &[(&'a str, &'a str)]) -> Result<Self, Error> {
+        self.set_literals(literals)?;
+        Ok(self)
+    }
+
+    /// Adds [literals](Tokenizer#literals) all producing tokens of the given name to this
+    /// [`Tokenizer`] and returns itself.
+    ///
+    /// See [`Tokenizer::set_literal_aliases`] for more details.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if:
+    /// * any literal is empty, or
+    /// * the name is already used by a pattern or another rule.
+    pub fn with_literal_aliases(
+        mut self,
+        name: &'a str,
+        literals: &[&'a str],
+    ) -> Result<Self, Error> {
+        self.set_literal_aliases(name, literals)?;
+        Ok(self)
+    }
+
+    /// Sets the [patterns](Tokenizer#patterns) of this [`Tokenizer`] and returns itself.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if:
+    /// * there are duplicate patterns,
+    /// * a pattern name is already used by another rule, or
+    /// * any pattern regex is invalid.
+    pub fn with_patterns(mut self, patterns: Vec<(String, String)>) -> Result<Self, Error> {
+        self.set_patterns(patterns)?;
+        Ok(self)
+    }
+
+    /// Sets the [patterns](Tokenizer#patterns) of this [`Tokenizer`], each compiled with its own
+    /// [`RegexOptions`], and returns itself.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if:
+    /// * there are duplicate patterns,
+    /// * a pattern name is already used by another rule, or
+    /// * any pattern regex is invalid.
+    pub fn with_patterns_ext(
+        mut self,
+        patterns: Vec<(String, String, RegexOptions)>,
+    ) -> Result<Self, Error> {
+        self.set_patterns_ext(patterns)?;
+        Ok(self)
+    }
+
+    /// Sets the [patterns](Tokenizer#patterns) of this [`Tokenizer`] from already compiled regexes
+    /// and returns itself.
+    ///
+    /// See [`Tokenizer::set_compiled_patterns`] for more details.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if:
+    /// * there are duplicate patterns,
+    /// * a pattern name is already used by another rule, or
+    /// * `anchor` is set and any pattern fails to compile again.
+    pub fn with_compiled_patterns(
+        mut self,
+        patterns: &[(&str, Regex)],
+        anchor: bool,
+    ) -> Result<Self, Error> {
+        self.set_compiled_patterns(patterns, anchor)?;
+        Ok(self)
+    }
+
+    /// Adds a [pattern function](Tokenizer#pattern-functions) to this [`Tokenizer`] and returns
+    /// itself.
+    ///
+    /// See [`Tokenizer::add_pattern_fn`] for more details.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the name is already used by another rule.
+    pub fn with_pattern_fn(
+        mut self,
+        name: &str,
+        matcher: impl Fn(&str) -> Option<usize> + Send + Sync + 'static,
+    ) -> Result<Self, Error> {
+        self.add_pattern_fn(name, matcher)?;
+        Ok(self)
+    }
+
+    /// Adds a [balanced span](Tokenizer#balanced-spans) rule to this [`Tokenizer`] and returns
+    /// itself.
+    ///
+    /// See [`Tokenizer::add_balanced`] for more details.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the name is already used by another rule.
+    pub fn with_balanced(mut self, name: &str, open: char, close: char) -> Result<Self, Error> {
+        self.add_balanced(name, open, close)?;
+        Ok(self)
+    }
+
+    /// Adds a [heredoc](Tokenizer#heredocs) rule to this [`Tokenizer`] and returns itself.
+    ///
+    /// See [`Tokenizer::add_heredoc`] for more details.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the name is already used by another rule or the
+    /// pattern regex is invalid.
+    pub fn with_heredoc(mut self, name: &str, opener: &str) -> Result<Self, Error> {
+        self.add_heredoc(name, opener)?;
+        Ok(self)
+    }
+
+    /// Sets the [skip patterns](Tokenizer#skip_patterns) of this [`Tokenizer`] and returns itself.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if:
+    /// * there are duplicate skip patterns,
+    /// * a skip pattern name is already used by another rule, or
+    /// * any skip pattern regex is invalid.
+    pub fn with_skip_patterns(mut self, skip_patterns: &[(&str, &str)]) -> Result<Self, Error> {
+        self.set_skip_patterns(skip_patterns)?;
+        Ok(self)
+    }
+
+    /// Sets the [ignored characters](Tokenizer#ignored_characters) of this [`Tokenizer`] and
+    /// returns itself.
+    #[must_use]
+    pub fn with_ignored_characters(mut self, ignored_characters: FxHashSet<char>) -> Self {
+        self.ignored_characters = ignored_characters;
+        self
+    }
+
+    /// Sets the [`ignore_whitespace`](Tokenizer#ignore_whitespace) option of this [`Tokenizer`] and
+    /// returns itself.
+    #[must_use]
+    pub fn with_ignore_whitespace(mut self, ignore_whitespace: bool) -> Self {
+        self.ignore_whitespace = ignore_whitespace;
+        self
+    }
+
+    /// Sets the [`longest_match`](Tokenizer#longest_match) option of this [`Tokenizer`] and
+    /// returns itself.
+    #[must_use]
+    pub fn with_longest_match(mut self, longest_match: bool) -> Self {
+        self.longest_match = longest_match;
+        self
+    }
+
+    /// Sets the [`max_token_length`](Tokenizer#max_token_length) option of this [`Tokenizer`] and
+    /// returns itself.
+    #[must_use]
+    pub fn with_max_token_length(mut self, max_token_length: Option<usize>) -> Self {
+        self.max_token_length = max_token_length;
+        self
+    }
+
+    /// Sets the [`backtrack_limit`](Tokenizer#backtrack_limit) option of this [`Tokenizer`] and
+    /// returns itself.
+    ///
+    /// # Errors
+    ///
+    /// See [`Tokenizer::set_backtrack_limit`].
+    pub fn with_backtrack_limit(mut self, backtrack_limit: Option<usize>) -> Result<Self, Error> {
+        self.set_backtrack_limit(backtrack_limit)?;
+        Ok(self)
+    }
+
+    /// Sets the [`emit_eof`](Tokenizer#emit_eof) option of this [`Tokenizer`] and returns itself.
+    #[must_use]
+    pub fn with_emit_eof(mut self, emit_eof: bool) -> Self {
+        self.emit_eof = emit_eof;
+        self
+    }
+
+    /// Sets the allowed [escape sequences](Tokenizer#escapes) for the patterns of the given name
+    /// and returns itself. [`None`] removes the restriction.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the regex is invalid.
+    pub fn with_escapes(mut self, name: &str, escapes: Option<&str>) -> Result<Self, Error> {
+        self.set_escapes(name, escapes)?;
+        Ok(self)
+    }
+
+    /// Sets the [`allow_duplicate_names`](Tokenizer#allow_duplicate_names) option of this
+    /// [`Tokenizer`] and returns itself.
+    #[must_use]
+    pub fn with_allow_duplicate_names(mut self, allow_duplicate_names: bool) -> Self {
+        self.allow_duplicate_names = allow_duplicate_names;
+        self
+    }
+
+    /// Sets the [literals](Tokenizer#literals) of this [`Tokenizer`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if:
+    /// * any literal is empty, or
+    /// * a literal name is already used by a pattern or another rule.
+    pub fn set_literals(&mut self, literals: &[(&'a str, &'a str)]) -> Result<(), Error> {
+        validate_literals(literals)?;
+        self.validate_literal_names(literals.iter().copied())?;
+        self.literals = stream::build_hashmap(literals);
+        self.tree = generate_tree(&self.literals);
+        Ok(())
+    }
+
+    /// Adds [literals](Tokenizer#literals) all producing tokens of the given name to this
+    /// [`Tokenizer`], keeping the existing ones.
+    ///
+    /// The literals are aliases of each other by design, just like literals of the same name
+    /// passed to [`Tokenizer::set_literals`], so the name may be used by existing literals too.
+    /// It still must not be used by a pattern though (unless
+    /// [`allow_duplicate_names`](Tokenizer#allow_duplicate_names) is enabled). Note that setting
+    /// the literals afterwards (e.g. using [`Tokenizer::set_literals`]) replaces the aliases as
+    /// well.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use crossandra::Tokenizer;
+    /// let tok = Tokenizer::default()
+    ///     .with_literals(&[("colon", ":")])
+    ///     .unwrap()
+    ///     .with_literal_aliases("arrow", &["->", "→"])
+    ///     .unwrap();
+    /// let names: Vec<_> = tok.tokenize("->:→").map(|token| token.unwrap().name).collect();
+    /// assert_eq!(names, ["arrow", "colon", "arrow"]);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if:
+    /// * any literal is empty, or
+    /// * the name is already used by a pattern or another rule.
+    pub fn set_literal_aliases(
+        &mut self,
+        name: &'a str,
+        literals: &[&'a str],
+    ) -> Result<(), Error> {
+        if literals.iter().any(|literal| literal.is_empty()) {
+            return Err(Error::EmptyLiteral);
+        }
+        self.validate_literal_names(literals.iter().map(|&literal| (name, literal)))?;
+        self.literals
+            .extend(literals.iter().map(|&literal| (literal, name)));
+        self.tree = generate_tree(&self.literals);
+        Ok(())
+    }
+
+    /// Sets the [patterns](Tokenizer#patterns) of this [`Tokenizer`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if:
+    /// * there are duplicate patterns,
+    /// * a pattern name is already used by another rule, or
+    /// * any pattern regex is invalid.
+    pub fn set_patterns(&mut self, patterns: Vec<(String, String)>) -> Result<(), Error> {
+        let patterns = patterns::prepare(patterns, self.backtrack_limit)?;
+        self.validate_pattern_names(&patterns)?;
+        self.patterns = patterns;
+        self.combined = Combined::new(&self.patterns, self.backtrack_limit);
+        Ok(())
+    }
+
+    /// Sets the [patterns](Tokenizer#patterns) of this [`Tokenizer`], each compiled with its own
+    /// [`RegexOptions`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if:
+    /// * there are duplicate patterns,
+    /// * a pattern name is already used by another rule, or
+    /// * any pattern regex is invalid.
+    pub fn set_patterns_ext(
+        &mut self,
+        patterns: Vec<(String, String, RegexOptions)>,
+    ) -> Result<(), Error> {
+        let patterns = patterns::prepare_with_options(patterns, self.backtrack_limit)?;
+        self.validate_pattern_names(&patterns)?;
+        self.patterns = patterns;
+        self.combined = Combined::new(&self.patterns, self.backtrack_limit);
+        Ok(())
+    }
+
+    /// Sets the [patterns](Tokenizer#patterns) of this [`Tokenizer`] from already compiled regexes,
+    /// e.g. to share them between multiple tokenizers.
+    ///
+    /// Unlike with [`Tokenizer::set_patterns`], the regexes are used as they are, without being
+    /// anchored to the start of the remaining source, so they should start with `^`. Unanchored
+    /// regexes still produce correct tokens, but every failed match scans the whole rest of the
+    /// source. If `anchor` is set, the regexes are compiled again with the anchor added instead.
+    ///
+    /// The source text of the regexes is used when they are combined or compiled again (e.g. with
+    /// a different [`backtrack_limit`](Tokenizer#backtrack_limit)), so flags have to be set inline
+    /// (e.g. `(?i)`) rather than using [`RegexBuilder`](fancy_regex::RegexBuilder).
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use crossandra::Tokenizer;
+    /// # use fancy_regex::Regex;
+    /// let int = Regex::new(r"^\d+").unwrap();
+    /// let tok = Tokenizer::default()
+    ///     .with_compiled_patterns(&[("int", int.clone())], false)
+    ///     .unwrap();
+    /// assert_eq!(tok.try_tokenize("12").unwrap()[0].value, "12");
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if:
+    /// * there are duplicate patterns,
+    /// * a pattern name is already used by another rule, or
+    /// * `anchor` is set and any pattern fails to compile again.
+    pub fn set_compiled_patterns(
+        &mut self,
+        patterns: &[(&str, Regex)],
+        anchor: bool,
+    ) -> Result<(), Error> {
+        let patterns = patterns::prepare_compiled(patterns, anchor, self.backtrack_limit)?;
+        self.validate_pattern_names(&patterns)?;
+        self.patterns = patterns;
+        self.combined = Combined::new(&self.patterns, self.backtrack_limit);
+        Ok(())
+    }
+
+    /// Removes all [literals](Tokenizer#literals) with the given name from this [`Tokenizer`].
+    /// Returns whether any literal was removed.
+    pub fn remove_literal(&mut self, name: &str) -> bool {
+        let literals: Vec<_> = self
+            .literals
+            .iter()
+            .filter(|(_, &literal_name)| literal_name == name)
+            .map(|(&literal, _)| literal)
+            .collect();
+
+        for literal in &literals {
+            self.literals.remove(literal);
+            self.tree.remove(literal);
+        }
+
+        !literals.is_empty()
+    }
+
+    /// Removes all [patterns](Tokenizer#patterns) with the given name from this [`Tokenizer`].
+    /// Returns whether any pattern was removed.
+    pub fn remove_pattern(&mut self, name: &str) -> bool {
+        let count = self.patterns.len();
+        self.patterns
+            .retain(|(pattern_name, _, _)| pattern_name != name);
+        if self.patterns.len() == count {
+            return false;
+        }
+        self.combined = Combined::new(&self.patterns, self.backtrack_limit);
+        true
+    }
+
+    /// Adds a [pattern function](Tokenizer#pattern-functions) to this [`Tokenizer`], producing
+    /// a token of the given name whenever `matcher` returns the length of a match at the start of
+    /// the remaining source.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the name is already used by another rule (unless
+    /// [`allow_duplicate_names`](Tokenizer#allow_duplicate_names) is enabled).
+    pub fn add_pattern_fn(
+        &mut self,
+        name: &str,
+        matcher: impl Fn(&str) -> Option<usize> + Send + Sync + 'static,
+    ) -> Result<(), Error> {
+        self.validate_added_name(name)?;
+        self.pattern_fns.push(PatternFn {
+            name: name.into(),
+            matcher: Arc::new(matcher),
+        });
+        Ok(())
+    }
+
+    /// Adds a [balanced span](Tokenizer#balanced-spans) rule to this [`Tokenizer`], matching
+    /// a span from `open` to the matching `close` as a single token of the given name. If `open`
+    /// and `close` are the same character, the span can't be nested and ends at the next `close`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the name is already used by another rule (unless
+    /// [`allow_duplicate_names`](Tokenizer#allow_duplicate_names) is enabled).
+    pub fn add_balanced(&mut self, name: &str, open: char, close: char) -> Result<(), Error> {
+        self.validate_added_name(name)?;
+        self.balanced.push((name.into(), open, close));
+        Ok(())
+    }
+
+    /// Adds a [heredoc](Tokenizer#heredocs) rule to this [`Tokenizer`], matching the opening
+    /// sequence with the given pattern and producing the whole heredoc as a single token of the
+    /// given name. The marker is the first capture group of the pattern (or the whole match).
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if:
+    /// * the name is already used by another rule (unless
+    ///   [`allow_duplicate_names`](Tokenizer#allow_duplicate_names) is enabled), or
+    /// * the pattern regex is invalid.
+    pub fn add_heredoc(&mut self, name: &str, opener: &str) -> Result<(), Error> {
+        self.validate_added_name(name)?;
+        let heredoc = patterns::prepare(vec![(name.into(), opener.into())], self.backtrack_limit)?;
+        self.heredocs.extend(heredoc);
+        Ok(())
+    }
+
+    /// Sets the [skip patterns](Tokenizer#skip_patterns) of this [`Tokenizer`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if:
+    /// * there are duplicate skip patterns,
+    /// * a skip pattern name is already used by another rule, or
+    /// * any skip pattern regex is invalid.
+    pub fn set_skip_patterns(&mut self, skip_patterns: &[(&str, &str)]) -> Result<(), Error> {
+        let skip_patterns = patterns::prepare(
+            skip_patterns
+                .iter()
+                .map(|&(name, pattern)| (name.into(), pattern.into()))
+                .collect(),
+            self.backtrack_limit,
+        )?;
+        self.validate_skip_pattern_names(&skip_patterns)?;
+        self.skip_patterns = skip_patterns;
+        Ok(())
+    }
+
+    /// Sets the [ignored characters](Tokenizer#ignored_characters) of this [`Tokenizer`].
+    pub fn set_ignored_characters(&mut self, ignored_characters: FxHashSet<char>) {
+        self.ignored_characters = ignored_characters;
+    }
+
+    /// Sets the [`ignore_whitespace`](Tokenizer#ignore_whitespace) option of this [`Tokenizer`].
+    pub fn set_ignore_whitespace(&mut self, ignore_whitespace: bool) {
+        self.ignore_whitespace = ignore_whitespace;
+    }
+
+    /// Sets the [`longest_match`](Tokenizer#longest_match) option of this [`Tokenizer`].
+    pub fn set_longest_match(&mut self, longest_match: bool) {
+        self.longest_match = longest_match;
+    }
+
+    /// Sets the [`max_token_length`](Tokenizer#max_token_length) option of this [`Tokenizer`].
+    pub fn set_max_token_length(&mut self, max_token_length: Option<usize>) {
+        self.max_token_length = max_token_length;
+    }
+
+    /// Sets the [`backtrack_limit`](Tokenizer#backtrack_limit) option of this [`Tokenizer`],
+    /// recompiling the already set patterns from their source text. Flags of
+    /// [compiled patterns](Tokenizer::set_compiled_patterns) set using
+    /// [`RegexBuilder`](fancy_regex::RegexBuilder) rather than inline are therefore lost.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if any pattern fails to compile again, in which case
+    /// this [`Tokenizer`] is left unchanged.
+    pub fn set_backtrack_limit(&mut self, backtrack_limit: Option<usize>) -> Result<(), Error> {
+        if backtrack_limit == self.backtrack_limit {
+            return Ok(());
+        }
+        let patterns = patterns::recompile(&self.patterns, backtrack_limit)?;
+        let skip_patterns = patterns::recompile(&self.skip_patterns, backtrack_limit)?;
+        let heredocs = patterns::recompile(&self.heredocs, backtrack_limit)?;
+        self.backtrack_limit = backtrack_limit;
+        self.patterns = patterns;
+        self.skip_patterns = skip_patterns;
+        self.heredocs = heredocs;
+        self.combined = Combined::new(&self.patterns, backtrack_limit);
+        Ok(())
+    }
+
+    /// Sets the [`emit_eof`](Tokenizer#emit_eof) option of this [`Tokenizer`].
+    pub fn set_emit_eof(&mut self, emit_eof: bool) {
+        self.emit_eof = emit_eof;
+    }
+
+    /// Sets the allowed [escape sequences](Tokenizer#escapes) for the patterns of the given name.
+    /// [`None`] removes the restriction.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the regex is invalid.
+    pub fn set_escapes(&mut self, name: &str, escapes: Option<&str>) -> Result<(), Error> {
+        match escapes {
+            Some(escapes) => {
+                let regex = patterns::prepare_escapes(name, escapes)?;
+                self.escapes.insert(name.into(), regex);
+            }
+            None => {
+                self.escapes.remove(name);
+            }
+        }
+        Ok(())
+    }
+
+    /// Sets the [`allow_duplicate_names`](Tokenizer#allow_duplicate_names) option of this
+    /// [`Tokenizer`].
+    pub fn set_allow_duplicate_names(&mut self, allow_duplicate_names: bool) {
+        self.allow_duplicate_names = allow_duplicate_names;
+    }
+}
+
+#[cfg(feature = "std")]
+impl Default for Tokenizer<'_> {
+    fn default() -> Self {
+        Self::new(&[], Vec::new(), FxHashSet::default(), false)
+            .expect("an empty tokenizer should be correct")
+    }
+}
+
+#[cfg(feature = "std")]
+fn validate_literals<'a>(literals: &[(&'a str, &'a str)]) -> Result<(), Error> {
+    literals
+        .iter()
+        .all(|(_name, token)| !token.is_empty())
+        .then_some(())
+        .ok_or(Error::EmptyLiteral)
+}
+
+/// Returns whether two [patterns](Tokenizer#patterns) are the same, i.e. one of them is redundant.
+#[cfg(feature = "std")]
+fn is_same_pattern(a: &Pattern, b: &Pattern) -> bool {
+    a.1.as_str() == b.1.as_str() && a.2 == b.2
+}
+
+/// Returns whether the [pattern](Tokenizer#patterns) matches just the given literal, i.e. it's
+/// redundant with a literal of the same name.
+#[cfg(feature = "std")]
+fn matches_literal(pattern: &Pattern, literal: &str) -> bool {
+    pattern.1.as_str() == anchor_pattern(&fancy_regex::escape(literal), &pattern.2)
+}
+
+#[cfg(feature = "std")]
+/// Shifts the position of the token or the error by `offset` bytes.
+fn shift_result(mut result: Result<Token<'_>, Error>, offset: usize) -> Result<Token<'_>, Error> {
+    match &mut result {
+        Ok(token) => token.position += offset,
+        Err(err) => err.shift(offset),
+    }
+    result
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn literal_validation_err() {
+        assert!(matches!(
+            validate_literals(&[("x", "x"), ("y", "")]),
+            Err(Error::EmptyLiteral)
+        ));
+        assert!(matches!(
+            Tokenizer::default().with_literals(&[("x", "x"), ("y", "")]),
+            Err(Error::EmptyLiteral)
+        ));
+    }
+
+    #[test]
+    fn literal_validation_ok() {
+        assert!(validate_literals(&[("x", "x"), ("", "y")]).is_ok());
+    }
+
+    #[test]
+    fn fast_mode() {
+        let tests: [(&[_], Vec<_>, bool); 5] = [
+            (&[], Vec::new(), true),
+            (&[], vec![(String::new(), String::new())], false),
+            (&[("", "a")], Vec::new(), true),
+            (&[], Vec::new(), true),
+            (&[("", "a"), ("", "b"), ("", "c!")], Vec::new(), false),
+        ];
+        for (literals, patterns, expected) in tests {
+            assert_eq!(
+                Tokenizer::default()
+                    .with_literals(literals)
+                    .unwrap()
+                    .with_patterns(patterns)
+                    .unwrap()
+                    .can_use_fast_mode(),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn ignored_preparation() {
+        let tests = [
+            (FxHashSet::default(), false, FxHashSet::default()),
+            (
+                FxHashSet::from_iter(['a', 'b', 'c']),
+                false,
+                "abc".chars().collect(),
+            ),
+            (
+                FxHashSet::from_iter(['a', 'b', 'c']),
+                true,
+                "abc \x0c\t\x0b\r\n".chars().collect(),
+            ),
+        ];
+        for (ignored_characters, ignore_whitespace, expected) in tests {
+            assert_eq!(
+                Tokenizer::default()
+                    .with_ignore_whitespace(ignore_whitespace)
+                    .with_ignored_characters(ignored_characters)
+                    .prepare_ignored(),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn unicode_whitespace() {
+        let tokenizer = Tokenizer::default()
+            .with_literals(&[("plus", "+")])
+            .unwrap()
+            .with_patterns(vec![("words".into(), r"a\sb".into())])
+            .unwrap()
+            .with_ignore_whitespace(true);
+        let source = "\u{a0}+\u{3000}a\u{a0}b \u{2003}+";
+        let tokens = tokenizer
+            .tokenize(source)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::from(("plus", "+", 2)),
+                Token::from(("words", "a\u{a0}b", 6)),
+                Token::from(("plus", "+", 14)),
+            ]
+        );
+        assert_eq!(tokenizer.count(source).unwrap(), 3);
+
+        // without the option, non-ASCII whitespace is only ignored when listed explicitly
+        let tokenizer = tokenizer
+            .with_ignore_whitespace(false)
+            .with_ignored_characters(FxHashSet::from_iter([' ', '\u{a0}']));
+        assert!(matches!(
+            tokenizer.tokenize("\u{a0}+ \u{3000}").nth(1),
+            Some(Err(Error::BadToken('\u{3000}', 4)))
+        ));
+    }
+
+    #[test]
+    fn summary() {
+        let tokenizer = Tokenizer::default()
+            .with_literals(&[("plus", "+")])
+            .unwrap()
+            .with_patterns(vec![("word".into(), r"\w+".into())])
+            .unwrap()
+            .with_ignore_whitespace(true)
+            .with_emit_eof(true);
+
+        let summarize = |source| tokenizer.summarize(source).unwrap();
+        assert_eq!(summarize(""), TokenizeSummary::default());
+        let blank = summarize(" \t\u{3000}\n");
+        assert!(!blank.has_tokens());
+        assert_eq!(blank.bytes_consumed(), 0);
+        assert_eq!(blank.skipped(), 4);
+
+        let summary = summarize(" žluť + kůň ");
+        assert!(summary.has_tokens());
+        assert_eq!(summary.tokens(), 3);
+        assert_eq!(summary.bytes_consumed(), 12);
+        assert_eq!(summary.skipped(), 4);
+
+        assert!(matches!(
+            tokenizer.summarize("a ? b"),
+            Err(Error::BadToken('?', 2))
+        ));
+    }
+
+    #[test]
+    fn from_common() {
+        let tokenizer = Tokenizer::from_common(&[&common::WORD, &common::DIGIT]).unwrap();
+        assert_eq!(
+            tokenizer,
+            Tokenizer::default()
+                .with_patterns(vec![common::WORD.clone(), common::DIGIT.clone()])
+                .unwrap()
+        );
+        assert!(Tokenizer::from_common(&[]).unwrap().patterns.is_empty());
+
+        let custom = ("word".into(), "x".into());
+        assert!(matches!(
+            Tokenizer::from_common(&[&common::WORD, &custom]),
+            Err(Error::DuplicatePattern(_))
+        ));
+        assert!(matches!(
+            Tokenizer::from_common(&[&("bad".into(), "(".into())]),
+            Err(Error::InvalidRegex { name, .. }) if name == "bad"
+        ));
+    }
+
+    #[test]
+    fn token_names() {
+        assert!(Tokenizer::default().token_names().is_empty());
+
+        let tokenizer = Tokenizer::default()
+            .with_allow_duplicate_names(true)
+            .with_literals(&[("bracket", "("), ("bracket", ")"), ("arrow", "->")])
+            .unwrap()
+            .with_patterns(vec![
+                ("number".into(), r"\d+".into()),
+                ("name".into(), r"\w+".into()),
+                ("number".into(), r"\d+\.\d+".into()),
+                ("arrow".into(), "=>".into()),
+            ])
+            .unwrap();
+        assert_eq!(
+            tokenizer.token_names(),
+            ["arrow", "bracket", "number", "name"]
+        );
+        assert_eq!(
+            tokenizer.with_emit_eof(true).token_names(),
+            ["arrow", "bracket", "number", "name", "eof"]
+        );
+    }
+
+    #[test]
+    fn counts() {
+        let tokenizer = Tokenizer::default();
+        assert!(tokenizer.is_empty());
+        assert_eq!(tokenizer.literal_count(), 0);
+        assert_eq!(tokenizer.pattern_count(), 0);
+
+        let tokenizer = tokenizer
+            .with_allow_duplicate_names(true)
+            .with_literals(&[("bracket", "("), ("bracket", ")"), ("arrow", "->")])
+            .unwrap()
+            .with_skip_patterns(&[("comment", "#.*")])
+            .unwrap();
+        assert!(!tokenizer.is_empty());
+        assert_eq!(tokenizer.literal_count(), 3);
+        assert_eq!(tokenizer.pattern_count(), 0);
+
+        let tokenizer = tokenizer
+            .with_literals(&[])
+            .unwrap()
+            .with_patterns(vec![
+                ("number".into(), r"\d+".into()),
+                ("number".into(), r"\d+\.\d+".into()),
+            ])
+            .unwrap();
+        assert!(!tokenizer.is_empty());
+        assert_eq!(tokenizer.literal_count(), 0);
+        assert_eq!(tokenizer.pattern_count(), 2);
+
+        let balanced = Tokenizer::default()
+            .with_balanced("parens", '(', ')')
+            .unwrap();
+        assert!(!balanced.is_empty());
+        let heredoc = Tokenizer::default()
+            .with_heredoc("heredoc", r"<<(\w+)")
+            .unwrap();
+        assert!(!heredoc.is_empty());
+        let pattern_fn = Tokenizer::default().with_pattern_fn("x", |_| None).unwrap();
+        assert!(!pattern_fn.is_empty());
+    }
+
+    #[test]
+    fn bad_token_context() {
+        let tok = Tokenizer::default()
+            .with_literals(&[("dot", ".")])
+            .unwrap()
+            .with_patterns(vec![common::C_NAME.clone()])
+            .unwrap()
+            .with_ignore_whitespace(true);
+
+        let results: Vec<_> = tok.tokenize_with_context("a.b ?? c\n?").collect();
+        assert_eq!(results.len(), 7);
+        assert!(matches!(
+            &results[3],
+            Err(Error::BadTokenAfter { character: '?', position: 4, previous, previous_span })
+                if previous == "c_name" && previous_span == &(2..3)
+        ));
+        assert!(matches!(&results[4], Err(Error::BadToken('?', 5))));
+        assert!(matches!(
+            &results[6],
+            Err(Error::BadTokenAfter { position: 9, previous_span, .. }) if previous_span == &(7..8)
+        ));
+
+        // the plain iterator is unaffected
+        assert!(matches!(
+            tok.tokenize("a?").nth(1),
+            Some(Err(Error::BadToken('?', 1)))
+        ));
+    }
+
+    #[test]
+    fn prefix() {
+        let tok = Tokenizer::default()
+            .with_literals(&[("a", "a"), ("b", "bb")])
+            .unwrap()
+            .with_ignore_whitespace(true);
+
+        assert_eq!(tok.tokenize_prefix("a bb", 0), (vec![], 0));
+        assert_eq!(tok.tokenize_prefix("", 3), (vec![], 0));
+
+        let (tokens, position) = tok.tokenize_prefix("a bb a ", 2);
+        assert_eq!(
+            tokens,
+            vec![Token::from(("a", "a", 0)), Token::from(("b", "bb", 2))]
+        );
+        assert_eq!(position, 4);
+        let (tokens, position) = tok.tokenize_prefix("a bb a ", 5);
+        assert_eq!(tokens.len(), 3);
+        assert_eq!(position, 7);
+
+        let (tokens, position) = tok.tokenize_prefix("a ? a", 3);
+        assert_eq!(tokens, vec![Token::from(("a", "a", 0))]);
+        assert_eq!(position, 2);
+        assert!(matches!(
+            tok.tokenize_from("a ? a", TokenizerState::at(position))
+                .next(),
+            Some(Err(Error::BadToken('?', 2)))
+        ));
+    }
+
+    #[test]
+    fn literal_aliases() {
+        let tok = Tokenizer::default()
+            .with_literals(&[("minus", "-"), ("arrow", "=>")])
+            .unwrap()
+            .with_literal_aliases("arrow", &["->", "→"])
+            .unwrap()
+            .with_ignore_whitespace(true);
+        let tokens = tok.try_tokenize("-> - → =>").unwrap();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::from(("arrow", "->", 0)),
+                Token::from(("minus", "-", 3)),
+                Token::from(("arrow", "→", 5)),
+                Token::from(("arrow", "=>", 9)),
+            ]
+        );
+        assert_eq!(tok.tree().match_longest_prefix("→"), Some(("→", &"arrow")));
+        assert_eq!(tok.token_names(), ["arrow", "minus"]);
+
+        assert!(matches!(
+            tok.clone().with_literal_aliases("empty", &["x", ""]),
+            Err(Error::EmptyLiteral)
+        ));
+        assert_eq!(
+            tok.with_literals(&[("minus", "-")]).unwrap().literals.len(),
+            1
+        );
+    }
+
+    #[test]
+    fn validation() {
+        assert!(Tokenizer::default().validate().is_ok());
+
+        let tok = Tokenizer::default()
+            .with_patterns(vec![
+                ("digits".into(), r"\d*".into()),
+                ("word".into(), r"\w+".into()),
+                ("space".into(), r" ?".into()),
+            ])
+            .unwrap();
+        let errors = tok.validate().unwrap_err();
+        assert!(matches!(
+            &errors[..],
+            [Error::ZeroWidthPattern(a), Error::ZeroWidthPattern(b)]
+                if a == "digits" && b == "space"
+        ));
+    }
+
+    #[test]
+    fn end_anchored_patterns() {
+        let end = RegexOptions {
+            anchor: AnchorMode::StartAndEnd,
+            ..Default::default()
+        };
+        let tok = Tokenizer::default()
+            .with_patterns_ext(vec![
+                ("rest".into(), r"//.*".into(), end),
+                ("word".into(), r"\w+".into(), RegexOptions::default()),
+            ])
+            .unwrap()
+            .with_ignore_whitespace(true);
+        let names = |source| {
+            tok.tokenize(source)
+                .map(|token| token.map(|token| token.name))
+                .collect::<Result<Vec<_>, _>>()
+        };
+        assert_eq!(names("a b // c d").unwrap(), ["word", "word", "rest"]);
+        assert!(matches!(names("a // b\nc"), Err(Error::BadToken('/', 2))));
+    }
+
+    #[test]
+    fn json() {
+        let tok = Tokenizer::json();
+        let source = "{\"a\": [-1.5e3, 0, \"x\\\"y\"],\r\n\t\"b\": {\"c\": null, \"d\": false}}";
+        let tokens: Vec<_> = tok
+            .tokenize(source)
+            .map(|token| token.map(|token| (token.name, token.value)))
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            tokens,
+            [
+                ("lbrace", "{"),
+                ("string", "\"a\""),
+                ("colon", ":"),
+                ("lbracket", "["),
+                ("number", "-1.5e3"),
+                ("comma", ","),
+                ("number", "0"),
+                ("comma", ","),
+                ("string", "\"x\\\"y\""),
+                ("rbracket", "]"),
+                ("comma", ","),
+                ("string", "\"b\""),
+                ("colon", ":"),
+                ("lbrace", "{"),
+                ("string", "\"c\""),
+                ("colon", ":"),
+                ("null", "null"),
+                ("comma", ","),
+                ("string", "\"d\""),
+                ("colon", ":"),
+                ("bool", "false"),
+                ("rbrace", "}"),
+                ("rbrace", "}"),
+            ]
+        );
+
+        let first_error = |source| tok.tokenize(source).find_map(Result::err);
+        assert!(matches!(first_error("[01]"), Some(Error::BadToken('0', 1))));
+        assert!(matches!(first_error("[+1]"), Some(Error::BadToken('+', 1))));
+        assert!(matches!(first_error("[1.]"), Some(Error::BadToken('.', 2))));
+        assert!(matches!(first_error("'a'"), Some(Error::BadToken('\'', 0))));
+        assert!(matches!(first_error("True"), Some(Error::BadToken('T', 0))));
+        assert!(matches!(
+            first_error("\u{a0}1"),
+            Some(Error::BadToken('\u{a0}', 0))
+        ));
+    }
+
+    #[test]
+    fn csv() {
+        let tok = Tokenizer::csv(',').unwrap();
+        let source = "a,\"a,b\nc\",\r\n\"x\"\"y\", z ,\n,";
+        let tokens: Vec<_> = tok
+            .tokenize(source)
+            .map(|token| token.map(|token| (token.name, token.value)))
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            tokens,
+            [
+                ("field", "a"),
+                ("separator", ","),
+                ("field", "\"a,b\nc\""),
+                ("separator", ","),
+                ("newline", "\r\n"),
+                ("field", "\"x\"\"y\""),
+                ("separator", ","),
+                ("field", " z "),
+                ("separator", ","),
+                ("newline", "\n"),
+                ("separator", ","),
+            ]
+        );
+
+        let first_error = |source| tok.tokenize(source).find_map(Result::err);
+        assert!(matches!(
+            first_error("a,\"b"),
+            Some(Error::Unterminated { name, start: 2, end: 3 }) if name == "field"
+        ));
+
+        let tok = Tokenizer::csv('|').unwrap();
+        let values: Vec<_> = tok
+            .tokenize("a|b,c|\"|\"")
+            .map(|token| token.unwrap().value)
+            .collect();
+        assert_eq!(values, ["a", "|", "b,c", "|", "\"|\""]);
+    }
+
+    #[test]
+    fn csv_invalid_delimiter() {
+        for delimiter in ['"', '\r', '\n'] {
+            assert!(matches!(
+                Tokenizer::csv(delimiter),
+                Err(Error::InvalidDelimiter(c)) if c == delimiter
+            ));
+        }
+    }
+
+    #[test]
+    fn skip_patterns() {
+        let tok = Tokenizer::default()
+            .with_literals(&[("div", "/"), ("mul", "*")])
+            .unwrap()
+            .with_patterns(vec![common::C_NAME.clone()])
+            .unwrap()
+            .with_skip_patterns(&[("comment", r"//[^\n]*"), ("block", r"/\*(?s:.)*?\*/")])
+            .unwrap()
+            .with_ignore_whitespace(true);
+        assert!(!tok.can_use_fast_mode());
+
+        let source = "a/b // c d\n/* e\n* / */ *f//";
+        let tokens: Vec<_> = tok
+            .tokenize(source)
+            .map(|token| token.map(|token| (token.name, token.position)))
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            tokens,
+            [
+                ("c_name", 0),
+                ("div", 1),
+                ("c_name", 2),
+                ("mul", 23),
+                ("c_name", 24),
+            ]
+        );
+        assert_eq!(tok.count(source).unwrap(), 5);
+        assert_eq!(tok.summarize(source).unwrap().skipped(), 22);
+
+        // without the literals, an unclosed block comment can't be tokenized
+        let comments = Tokenizer::default()
+            .with_patterns(vec![common::C_NAME.clone()])
+            .unwrap()
+            .with_skip_patterns(&[("block", r"/\*(?s:.)*?\*/")])
+            .unwrap();
+        assert!(matches!(
+            comments.tokenize("a/* b").find_map(Result::err),
+            Some(Error::Unterminated { name, start: 1, end: 3 }) if name == "block"
+        ));
+
+        let mut limited = tok.clone().with_backtrack_limit(Some(100)).unwrap();
+        assert_ne!(limited, tok);
+        limited.set_backtrack_limit(None).unwrap();
+        assert_eq!(limited, tok);
+        limited.set_skip_patterns(&[]).unwrap();
+        assert_ne!(limited, tok);
+
+        assert!(matches!(
+            Tokenizer::default().with_skip_patterns(&[("bad", "(")]),
+            Err(Error::InvalidRegex { name, .. }) if name == "bad"
+        ));
+    }
+
+    #[test]
+    fn runtime_patterns() {
+        fn build(names: &[String]) -> Tokenizer<'static> {
+            let patterns = names
+                .iter()
+                .map(|name| (name.clone(), format!("{name}[0-9]+")))
+                .collect();
+            Tokenizer::default()
+                .with_literals(&[("comma", ",")])
+                .unwrap()
+                .with_patterns(patterns)
+                .unwrap()
+        }
+
+        let names: Vec<String> = ["x", "y"].iter().map(ToString::to_string).collect();
+        let tok = build(&names);
+        drop(names);
+
+        let handle = std::thread::spawn(move || {
+            tok.tokenize("x1,y23")
+                .map(|token| token.unwrap().into_owned().name)
+                .collect::<Vec<_>>()
+        });
+        assert_eq!(handle.join().unwrap(), ["x", "comma", "y"]);
+    }
+
+    #[test]
+    fn cloning() {
+        let base = Tokenizer::default()
+            .with_literals(&[("plus", "+"), ("arrow", "->")])
+            .unwrap()
+            .with_patterns(vec![common::INT.clone(), common::C_NAME.clone()])
+            .unwrap()
+            .with_escapes("c_name", Some("x"))
+            .unwrap()
+            .with_ignore_whitespace(true);
+        let clone = base.clone();
+        assert_eq!(clone, base);
+
+        // the compiled regexes are shared
+        for ((_, a, _), (_, b, _)) in base.patterns.iter().zip(&clone.patterns) {
+            assert!(Arc::ptr_eq(a, b));
+        }
+        assert!(Arc::ptr_eq(
+            &base.escapes["c_name"],
+            &clone.escapes["c_name"]
+        ));
+
+        let source = "x -> 1 + y2";
+        assert_eq!(
+            clone.try_tokenize(source).unwrap(),
+            base.try_tokenize(source).unwrap()
+        );
+
+        let mut custom = base.clone();
+        assert!(custom.remove_pattern("int"));
+        assert!(custom.try_tokenize(source).is_err());
+        assert_eq!(base.try_tokenize(source).unwrap().len(), 5);
+    }
+
+    #[test]
+    fn literal_pattern_name_collision() {
+        let tok = Tokenizer::default()
+            .with_literals(&[("eq", "=="), ("lt", "<")])
+            .unwrap();
+        assert!(matches!(
+            tok.with_patterns(vec![("eq".into(), "=+".into())]),
+            Err(Error::DuplicateName(name)) if name == "eq"
+        ));
+
+        let tok = Tokenizer::default()
+            .with_patterns(vec![common::INT.clone()])
+            .unwrap();
+        assert!(matches!(
+            tok.clone().with_literals(&[("plus", "+"), ("int", "0")]),
+            Err(Error::DuplicateName(name)) if name == "int"
+        ));
+        assert!(matches!(
+            tok.clone().with_literal_aliases("int", &["zero"]),
+            Err(Error::DuplicateName(name)) if name == "int"
+        ));
+        assert!(matches!(
+            Tokenizer::new(
+                &[("int", "0")],
+                vec![common::INT.clone()],
+                FxHashSet::default(),
+                false
+            ),
+            Err(Error::DuplicateName(_))
+        ));
+
+        let tok = tok
+            .with_allow_duplicate_names(true)
+            .with_literals(&[("int", "zero")])
+            .unwrap();
+        assert_eq!(tok.try_tokenize("zero1").unwrap().len(), 2);
+    }
+
+    #[test]
+    fn comparison() {
+        let def = Tokenizer::default();
+        assert_eq!(def, Tokenizer::default());
+        assert_eq!(
+            def,
+            Tokenizer::default().with_ignored_characters(FxHashSet::default())
+        );
+        assert_ne!(
+            def,
+            Tokenizer::default().with_ignored_characters(FxHashSet::from_iter(['x']))
+        );
+        assert_ne!(def, Tokenizer::default().with_ignore_whitespace(true));
+        assert_ne!(def, Tokenizer::default().with_longest_match(true));
+        assert_ne!(def, Tokenizer::default().with_max_token_length(Some(5)));
+        assert_ne!(
+            def,
+            Tokenizer::default().with_backtrack_limit(Some(5)).unwrap()
+        );
+        assert_ne!(def, Tokenizer::default().with_allow_duplicate_names(true));
+        assert_ne!(
+            def,
+            Tokenizer::default().with_literals(&[("1", "2")]).unwrap()
+        );
+        assert_eq!(
+            def.clone().with_literals(&[("1", "2")]).unwrap(),
+            Tokenizer::default().with_literals(&[("1", "2")]).unwrap()
+        );
+        assert_ne!(
+            def,
+            Tokenizer::default()
+                .with_patterns([("1".into(), "2".into())].into())
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn builder_equivalence() {
+        let literals = [("a", "b")];
+        let patterns = vec![(String::from("a"), String::from("b"))];
+        let ignored_chars: FxHashSet<_> = FxHashSet::from_iter(['x']);
+
+        let mut tok1 = Tokenizer::default();
+        tok1.set_longest_match(true);
+        tok1.set_ignore_whitespace(true);
+        tok1.set_ignored_characters(ignored_chars.clone());
+        tok1.set_literals(&literals).unwrap();
+        tok1.set_patterns(patterns.clone()).unwrap();
+
+        let tok2 = Tokenizer::default()
+            .with_longest_match(true)
+            .with_ignore_whitespace(true)
+            .with_ignored_characters(ignored_chars.clone())
+            .with_literals(&literals)
+            .unwrap()
+            .with_patterns(patterns.clone())
+            .unwrap();
+
+        let tok3 = Tokenizer::new(&literals, patterns, ignored_chars, true)
+            .unwrap()
+            .with_longest_match(true);
+
+        assert_eq!(tok1, tok2);
+        assert_eq!(tok1, tok3);
+        assert_eq!(tok2, tok3);
+    }
+
+    #[test]
+    fn builder_processing_literals() {
+        let mut tok = Tokenizer::default();
+        assert_eq!(tok.tree, Tree::default());
+
+        assert!(tok.set_literals(&[("a", "b")]).is_ok());
+
+        let flipped_literals = FxHashMap::from_iter([("b", "a")]);
+        let expected_tree = generate_tree(&flipped_literals);
+        assert_eq!(tok.literals, flipped_literals);
+        assert_eq!(tok.tree, expected_tree);
+
+        assert!(tok.set_literals(&[("a", "")]).is_err());
+    }
+
+    #[test]
+    fn builder_processing_patterns() {
+        let mut tok = Tokenizer::default();
+        assert!(tok.patterns.is_empty());
+
+        let pattern = ("a".into(), r"\d+".into());
+        assert!(tok.set_patterns(vec![pattern.clone()]).is_ok());
+        assert_eq!(tok.patterns.first().unwrap().1.as_str(), r"^(?:\d+)");
+
+        assert!(tok.set_patterns(vec![pattern.clone(), pattern]).is_ok());
+        assert!(tok.set_patterns(vec![("a".into(), "+".into())]).is_err());
+    }
+
+    #[test]
+    fn sorted_literals() {
+        let tok = Tokenizer::default()
+            .with_literals(&[
+                ("c", "+++"),
+                ("a", "+"),
+                ("f", "++++++"),
+                ("d", "++++"),
+                ("b", "++"),
+                ("e", "+++++"),
+            ])
+            .unwrap();
+        assert_eq!(
+            tok.literals_sorted(),
+            vec![
+                ("f", "++++++".into()),
+                ("e", "+++++".into()),
+                ("d", "++++".into()),
+                ("c", "+++".into()),
+                ("b", "++".into()),
+                ("a", "+".into()),
+            ]
+        );
+
+        let tok = Tokenizer::default()
+            .with_literals(&[("x", "ab"), ("y", "ba"), ("z", "a")])
+            .unwrap();
+        assert_eq!(
+            tok.literals_sorted(),
+            vec![("x", "ab".into()), ("y", "ba".into()), ("z", "a".into())]
+        );
+        assert!(Tokenizer::default().literals_sorted().is_empty());
+    }
+
+    #[test]
+    fn removal() {
+        let mut tok = Tokenizer::default()
+            .with_allow_duplicate_names(true)
+            .with_literals(&[("x", "ab"), ("y", "a"), ("x", "c")])
+            .unwrap()
+            .with_patterns(vec![common::INT.clone(), common::WORD.clone()])
+            .unwrap();
+
+        assert!(tok.remove_literal("x"));
+        assert!(!tok.remove_literal("x"));
+        assert_eq!(
+            tok,
+            Tokenizer::default()
+                .with_allow_duplicate_names(true)
+                .with_literals(&[("y", "a")])
+                .unwrap()
+                .with_patterns(vec![common::INT.clone(), common::WORD.clone()])
+                .unwrap()
+        );
+        assert_eq!(tok.tree, generate_tree(&FxHashMap::from_iter([("a", "y")])));
+
+        assert!(tok.remove_pattern("int"));
+        assert!(!tok.remove_pattern("int"));
+        assert!(!tok.remove_pattern("y"));
+
+        let tokens: Vec<_> = tok.tokenize("ab1").collect();
+        assert!(matches!(
+            &tokens[..],
+            [Ok(a), Ok(b), Err(Error::BadToken('1', 2))]
+                if a == &Token::from(("y", "a", 0)) && b == &Token::from(("word", "b", 1))
+        ));
+    }
+
+    #[test]
+    fn pattern_options() {
+        let case_insensitive = RegexOptions {
+            case_insensitive: true,
+            ..Default::default()
+        };
+        let tok = Tokenizer::default()
+            .with_patterns_ext(vec![
+                ("kw".into(), "select".into(), case_insensitive),
+                ("name".into(), "[a-z]+".into(), RegexOptions::default()),
+            ])
+            .unwrap()
+            .with_ignore_whitespace(true);
+        let tokens: Vec<_> = tok.tokenize("SeLeCt select Abc").collect();
+        assert!(matches!(
+            &tokens[..],
+            [Ok(a), Ok(b), Err(Error::BadToken('A', 14)), Ok(c)]
+                if a == &Token::from(("kw", "SeLeCt", 0))
+                    && b == &Token::from(("kw", "select", 7))
+                    && c == &Token::from(("name", "bc", 15))
+        ));
+
+        assert_ne!(
+            tok,
+            Tokenizer::default()
+                .with_patterns(vec![
+                    ("kw".into(), "select".into()),
+                    ("name".into(), "[a-z]+".into()),
+                ])
+                .unwrap()
+                .with_ignore_whitespace(true)
+        );
+        assert_eq!(
+            Tokenizer::default()
+                .with_patterns_ext(vec![("a".into(), "b".into(), RegexOptions::default())])
+                .unwrap(),
+            Tokenizer::default()
+                .with_patterns(vec![("a".into(), "b".into())])
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn empty_tokenizer() {
+        let tok = Tokenizer::default();
+
+        assert!(tok.tokenize("").next().is_none());
+
+        match tok.tokenize("source").next() {
+            Some(Err(Error::BadToken(c, p))) => assert_eq!((c, p), ('s', 0)),
+            _ => panic!("tokenization didn't fail with BadToken"),
+        };
+    }
+
+    #[test]
+    fn brainfuck_fast_tokenizer() {
+        let tok = Tokenizer::default()
+            .with_literals(&[
+                ("add", "+"),
+                ("sub", "-"),
+                ("left", "<"),
+                ("right", ">"),
+                ("read", ","),
+                ("write", "."),
+                ("begin_loop", "["),
+                ("end_loop", "]"),
+            ])
+            .unwrap();
+
+        if let Ok(tokens) = tok.tokenize(",[.,]").collect::<Result<Vec<_>, _>>() {
+            assert!(tokens
+                .iter()
+                .map(|t| t.name)
+                .eq("read begin_loop write read end_loop".split_whitespace()));
+        } else {
+            panic!();
+        };
+    }
+
+    fn make_output<'a>(tokens: Vec<((&'a str, &'a str), usize)>) -> Vec<Token<'a>> {
+        tokens
+            .into_iter()
+            .map(|((n, v), p)| Token::from((n, v, p)))
+            .collect()
+    }
+
+    /// Returns the names reported as duplicates by [`Tokenizer::validate`].
+    fn duplicates(tok: &Tokenizer) -> Vec<String> {
+        tok.validate()
+            .err()
+            .into_iter()
+            .flatten()
+            .filter_map(|err| match err {
+                Error::DuplicateName(name) => Some(name),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn arithmetic_tokenizer() {
+        let tests = [
+            (
+                "2 * 2 + 3 - 7",
+                make_output(vec![
+                    (("int", "2"), 0),
+                    (("mul", "*"), 2),
+                    (("int", "2"), 4),
+                    (("add", "+"), 6),
+                    (("int", "3"), 8),
+                    (("sub", "-"), 10),
+                    (("int", "7"), 12),
+                ]),
+            ),
+            (
+                "2**3",
+                make_output(vec![
+                    (("int", "2"), 0),
+                    (("pow", "**"), 1),
+                    (("int", "3"), 3),
+                ]),
+            ),
+            (
+                "-5",
+                make_output(vec![(("sub", "-"), 0), (("int", "5"), 1)]),
+            ),
+            (
+                "100 + -5",
+                make_output(vec![
+                    (("int", "100"), 0),
+                    (("add", "+"), 4),
+                    (("sub", "-"), 6),
+                    (("int", "5"), 7),
+                ]),
+            ),
+            (
+                "4 - 2 ** 5 / 2",
+                make_output(vec![
+                    (("int", "4"), 0),
+                    (("sub", "-"), 2),
+                    (("int", "2"), 4),
+                    (("pow", "**"), 6),
+                    (("int", "5"), 9),
+                    (("div", "/"), 11),
+                    (("int", "2"), 13),
+                ]),
+            ),
+            (
+                "10 % 3",
+                make_output(vec![
+                    (("int", "10"), 0),
+                    (("mod", "%"), 3),
+                    (("int", "3"), 5),
+                ]),
+            ),
+        ];
+
+        let tok = Tokenizer::default()
+            .with_literals(&[
+                ("add", "+"),
+                ("sub", "-"),
+                ("mul", "*"),
+                ("div", "/"),
+                ("pow", "**"),
+                ("mod", "%"),
+            ])
+            .unwrap()
+            .with_patterns(vec![common::INT.clone()])
+            .unwrap();
+
+        for (input, output) in tests {
+            assert_eq!(
+                tok.tokenize(input)
+                    .filter_map(Result::ok)
+                    .collect::<Vec<_>>(),
+                output
+            );
+        }
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn line_tokenization() {
+        let tok = Tokenizer::default()
+            .with_ignore_whitespace(true)
+            .with_patterns(vec![common::WORD.clone()])
+            .unwrap();
+        let Ok(lines) = tok
+            .tokenize_lines("a b\nc\rde")
+            .collect::<Result<Vec<Vec<Token>>, _>>()
+        else {
+            panic!("tokenization failed");
+        };
+        assert_eq!(
+            lines,
+            vec![
+                make_output(vec![(("word", "a"), 0), (("word", "b"), 2)]),
+                make_output(vec![(("word", "c"), 0), (("word", "de"), 2)]),
+            ]
+        );
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn line_tokenization_fast() {
+        let (a, b) = (("a", "a"), ("b", "b"));
+        let tok = Tokenizer::default()
+            .with_ignore_whitespace(true)
+            .with_literals(&[a, b])
+            .unwrap();
+        let Ok(lines) = tok
+            .tokenize_lines("a b\nb\ra")
+            .collect::<Result<Vec<Vec<Token>>, _>>()
+        else {
+            panic!("tokenization failed");
+        };
+        assert_eq!(
+            lines,
+            vec![
+                make_output(vec![(a, 0), (b, 2)]),
+                make_output(vec![(b, 0), (a, 2)])
+            ]
+        );
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn parallel_tokenization() {
+        let tok = Tokenizer::default()
+            .with_ignore_whitespace(true)
+            .with_patterns(vec![common::WORD.clone()])
+            .unwrap();
+        let source = "ab c\n\nd ?\n e";
+
+        let mut lines: Vec<_> = tok.tokenize_lines(source).collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[0].as_ref().unwrap()[1].position, 3);
+        assert!(matches!(lines[2], Err(Error::BadToken('?', 2))));
+        assert_eq!(lines[3].as_ref().unwrap()[0].position, 1);
+
+        LineIndex::new(source).to_absolute_positions(&mut lines);
+        assert!(matches!(lines[2], Err(Error::BadToken('?', 8))));
+        let tokens: Vec<_> = lines.into_iter().filter_map(Result::ok).flatten().collect();
+        assert_eq!(
+            tokens,
+            make_output(vec![
+                (("word", "ab"), 0),
+                (("word", "c"), 3),
+                (("word", "e"), 11)
+            ])
+        );
+    }
+
+    #[test]
+    fn breakpoint_tokenization() {
+        let (x, y, z) = (("x", "abc"), ("y", "a"), ("z", "b"));
+        let tok = Tokenizer::default().with_literals(&[x, y, z]).unwrap();
+        let Ok(tokens) = tok.tokenize("ababaababc").collect::<Result<Vec<_>, _>>() else {
+            panic!("tokenization failed");
+        };
+        assert_eq!(
+            tokens,
+            make_output(vec![
+                (y, 0),
+                (z, 1),
+                (y, 2),
+                (z, 3),
+                (y, 4),
+                (y, 5),
+                (z, 6),
+                (x, 7)
+            ])
+        );
+    }
+
+    #[test]
+    fn multichar_breakpoint_tokenization() {
+        let (x, y, z) = (("x", "ab"), ("y", "bc"), ("z", "abcd"));
+        let tok = Tokenizer::default().with_literals(&[x, y, z]).unwrap();
+        let source = "ccddbabcaabcccdcbaaabdaabcbaabbbabaaaccabcdabaabadbcacddacbddbcb";
+        let tokens: Vec<_> = tok.tokenize(source).flatten().collect();
+        assert_eq!(
+            tokens,
+            make_output(vec![
+                (x, 5),
+                (x, 9),
+                (x, 19),
+                (x, 23),
+                (x, 28),
+                (x, 32),
+                (z, 39),
+                (x, 43),
+                (x, 46),
+                (y, 50),
+                (y, 61)
+            ])
+        );
+    }
+
+    #[test]
+    fn fast_tokenization_with_ignoreset() {
+        let (foo, bar) = (("foo", "x"), ("bar", "y"));
+        let tok = Tokenizer::default()
+            .with_literals(&[foo, bar])
+            .unwrap()
+            .with_ignored_characters(FxHashSet::from_iter(['z']));
+        let Ok(tokens) = tok.tokenize("xzy").collect::<Result<Vec<_>, _>>() else {
+            panic!("tokenization failed");
+        };
+        assert_eq!(tokens, make_output(vec![(foo, 0), (bar, 2)]));
+    }
+
+    #[test]
+    fn core_tokenization_with_ignoreset() {
+        let (foo, bar) = (("foo", "xz"), ("bar", "yz"));
+        let tok = Tokenizer::default()
+            .with_literals(&[foo, bar])
+            .unwrap()
+            .with_ignored_characters(FxHashSet::from_iter(['z']));
+        let Ok(tokens) = tok
+            .tokenize("zxzyzxzyzzzyzzxzyzzzxzz")
+            .collect::<Result<Vec<_>, _>>()
+        else {
+            panic!("tokenization failed");
+        };
+        assert_eq!(
+            tokens,
+            make_output(vec![
+                (foo, 1),
+                (bar, 3),
+                (foo, 5),
+                (bar, 7),
+                (bar, 11),
+                (foo, 14),
+                (bar, 16),
+                (foo, 20)
+            ])
+        );
+    }
+
+    #[test]
+    fn whitespace_tokenization() {
+        let (cr, ln, space) = (("cr", "\r"), ("ln", "\n"), ("space", " "));
+        let tok = Tokenizer::default()
+            .with_literals(&[cr, ln, space])
+            .unwrap();
+        let source = " \r\n \r \n ";
+
+        let Ok(tokens) = tok.tokenize(source).collect::<Result<Vec<_>, _>>() else {
+            panic!("tokenization failed");
+        };
+        assert_eq!(
+            tokens,
+            make_output(vec![
+                (space, 0),
+                (cr, 1),
+                (ln, 2),
+                (space, 3),
+                (cr, 4),
+                (space, 5),
+                (ln, 6),
+                (space, 7)
+            ])
+        );
+
+        #[cfg(feature = "rayon")]
+        {
+            let Ok(lines) = tok
+                .tokenize_lines(source)
+                .collect::<Result<Vec<Vec<Token>>, _>>()
+            else {
+                panic!("tokenization failed");
+            };
+            assert_eq!(
+                lines,
+                vec![
+                    make_output(vec![(space, 0), (cr, 1)]),
+                    make_output(vec![(space, 0), (cr, 1), (space, 2)]),
+                    make_output(vec![(space, 0)]),
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn bad_tokenization_fast() {
+        let tok = Tokenizer::default();
+        let Some(Err(Error::BadToken(err_value, err_position))) = tok.tokenize("x").last() else {
+            panic!("tokenization didn't fail with BadToken");
+        };
+        assert_eq!(err_value, 'x');
+        assert_eq!(err_position, 0);
+    }
+
+    #[test]
+    fn bad_tokenization_core() {
+        let tok = Tokenizer::default().with_literals(&[("xy", "xy")]).unwrap();
+        let Some(Err(Error::BadToken(err_value, err_position))) =
+            tok.tokenize("xyz").find(Result::is_err)
+        else {
+            panic!("tokenization didn't fail with BadToken");
+        };
+        assert_eq!(err_value, 'z');
+        assert_eq!(err_position, 2);
+    }
+
+    #[test]
+    fn word_tokenization() {
+        let tok = Tokenizer::default()
+            .with_patterns(vec![common::WORD.clone()])
+            .unwrap();
+        let tokens: Vec<_> = tok.tokenize("Hello, world!").flatten().collect();
+        assert_eq!(
+            tokens,
+            make_output(vec![(("word", "Hello"), 0), (("word", "world"), 7)])
+        );
+    }
+
+    #[test]
+    fn fast_tokenization_continues_after_bad_token() {
+        let (a, b) = (("a", "a"), ("b", "b"));
+        let tok = Tokenizer::default().with_literals(&[a, b]).unwrap();
+        let mut expected_tokens =
+            make_output(vec![(a, 0), (b, 3), (b, 4), (a, 5), (a, 7)]).into_iter();
+        let mut expected_error_indexes = [1, 2, 6].into_iter();
+
+        let tokens: Vec<_> = tok.tokenize("axxbbaxa").collect();
+        assert_eq!(tokens.len(), 8);
+
+        for result in &tokens {
+            match result {
+                Ok(tok) => assert_eq!(tok, &expected_tokens.next().unwrap()),
+                Err(Error::BadToken(c, p)) => {
+                    assert_eq!((*c, *p), ('x', expected_error_indexes.next().unwrap()));
+                }
+                _ => panic!("unexpected error"),
+            }
+        }
+    }
+
+    #[test]
+    fn core_tokenization_continues_after_bad_token() {
+        let tok = Tokenizer::default()
+            .with_literals(&[("a", "axe")])
+            .unwrap()
+            .with_patterns([("b".into(), "box".into())].into())
+            .unwrap();
+        let expected_tokens = [
+            ("a", "axe", 0),
+            ("b", "box", 4),
+            ("a", "axe", 8),
+            ("a", "axe", 12),
+            ("b", "box", 16),
+            ("b", "box", 20),
+        ];
+        let expected_errors = ['&', ',', ',', '.', '.'];
+
+        let tokens: Vec<_> = tok.tokenize("axe&box,axe,axe.box.box").collect();
+        assert_eq!(tokens.len(), 11);
+
+        for i in (0..tokens.len()).step_by(2) {
+            assert_eq!(
+                tokens[i].as_ref().unwrap(),
+                &Token::from(expected_tokens[i / 2])
+            );
+        }
+        for i in (1..tokens.len()).step_by(2) {
+            let expected_err = (expected_errors[i / 2], i * 2 + 1);
+            assert!(matches!(tokens[i], Err(Error::BadToken(c, p)) if (c, p) == expected_err));
+        }
+    }
+
+    #[test]
+    fn backreference_pattern() {
+        let tok = Tokenizer::default()
+            .with_patterns(vec![("a".into(), r"(a)b\1".into())])
+            .unwrap();
+        let out: Vec<_> = tok.tokenize("abaaba").flatten().collect();
+        assert_eq!(out, make_output(vec![(("a", "aba"), 0), (("a", "aba"), 3)]));
+    }
+
+    #[test]
+    fn combined_patterns() {
+        let plain = |(name, pattern): (String, String)| (name, pattern, RegexOptions::default());
+        let mut tok = Tokenizer::default()
+            .with_patterns_ext(vec![
+                (
+                    "keyword".into(),
+                    "if|else".into(),
+                    RegexOptions {
+                        case_insensitive: true,
+                        ..Default::default()
+                    },
+                ),
+                // matches empty strings, which have to be skipped
+                plain(("xs".into(), "x*".into())),
+                plain(common::C_NAME.clone()),
+                plain(common::FLOAT.clone()),
+                plain(common::UNSIGNED_INT.clone()),
+                plain(common::STRING.clone()),
+            ])
+            .unwrap()
+            .with_literals(&[("plus", "+"), ("eq", "==")])
+            .unwrap()
+            .with_ignore_whitespace(true);
+        assert!(tok.combined.is_some());
+
+        let source = "IF x == 'a b' else 1.5+2 ?y 'open";
+        let combined: Vec<_> = tok.tokenize(source).map(|r| format!("{r:?}")).collect();
+        tok.combined = None;
+        let sequential: Vec<_> = tok.tokenize(source).map(|r| format!("{r:?}")).collect();
+        assert_eq!(combined, sequential);
+        assert!(combined[0].contains("\"keyword\""));
+        assert!(combined[1].contains("\"xs\""));
+
+        assert!(tok.remove_pattern("keyword"));
+        assert!(tok.combined.is_some());
+        tok.set_patterns(vec![common::RAW_STRING.clone(), common::WORD.clone()])
+            .unwrap();
+        assert!(tok.combined.is_none());
+    }
+
+    #[test]
+    fn captures() {
+        let mut tok = Tokenizer::default()
+            .with_patterns(vec![
+                (
+                    "pair".into(),
+                    r"(?P<key>\w+)=(?:(?P<number>\d+)|(?P<word>\w+))".into(),
+                ),
+                common::WORD.clone(),
+            ])
+            .unwrap()
+            .with_literals(&[("comma", ",")])
+            .unwrap();
+        assert!(tok.combined.is_some());
+
+        let source = "x=1,y=ab,z";
+        for longest_match in [false, true] {
+            tok.set_longest_match(longest_match);
+            let tokens = tok.try_tokenize(source).unwrap();
+            assert_eq!(tokens[0].captures, [("key", "x"), ("number", "1")]);
+            assert_eq!(tokens[1].captures, []);
+            assert_eq!(tokens[2].captures, [("key", "y"), ("word", "ab")]);
+            assert_eq!(tokens[4].name, "word");
+            assert_eq!(tokens[4].captures, []);
+        }
+
+        let token = tok.tokenize_owned(source).next().unwrap().unwrap();
+        assert_eq!(
+            token.captures,
+            [
+                ("key".to_owned(), "x".to_owned()),
+                ("number".to_owned(), "1".to_owned())
+            ]
+        );
+    }
+
+    #[test]
+    fn longest_match() {
+        let patterns = vec![
+            ("lt".into(), "<".into()),
+            ("le".into(), "<=".into()),
+            ("also_le".into(), "<=".into()),
+        ];
+        let tok = Tokenizer::default().with_patterns(patterns).unwrap();
+        let tokens: Vec<_> = tok.tokenize("<=").flatten().collect();
+        assert_eq!(tokens, make_output(vec![(("lt", "<"), 0)]));
+
+        let tok = tok.with_longest_match(true);
+        let tokens: Vec<_> = tok.tokenize("<=<").flatten().collect();
+        assert_eq!(
+            tokens,
+            make_output(vec![(("le", "<="), 0), (("lt", "<"), 2)])
+        );
     }
 
     #[test]
-    fn literal_validation_ok() {
-        assert!(validate_literals(&[("x", "x"), ("", "y")]).is_ok());
+    fn longest_match_with_literals() {
+        let tok = Tokenizer::default()
+            .with_literals(&[("kw_if", "if")])
+            .unwrap()
+            .with_patterns(vec![common::C_NAME.clone()])
+            .unwrap();
+        let tokens: Vec<_> = tok.tokenize("iffy").flatten().collect();
+        assert_eq!(
+            tokens,
+            make_output(vec![(("kw_if", "if"), 0), (("c_name", "fy"), 2)])
+        );
+
+        let tok = tok.with_longest_match(true);
+        let tokens: Vec<_> = tok.tokenize("iffy").flatten().collect();
+        assert_eq!(tokens, make_output(vec![(("c_name", "iffy"), 0)]));
+        let tokens: Vec<_> = tok.tokenize("if").flatten().collect();
+        assert_eq!(tokens, make_output(vec![(("kw_if", "if"), 0)]));
     }
 
     #[test]
-    fn fast_mode() {
-        let tests: [(&[_], Vec<_>, bool); 5] = [
-            (&[], Vec::new(), true),
-            (&[], vec![(String::new(), String::new())], false),
-            (&[("", "a")], Vec::new(), true),
-            (&[], Vec::new(), true),
-            (&[("", "a"), ("", "b"), ("", "c!")], Vec::new(), false),
-        ];
-        for (literals, patterns, expected) in tests {
+    fn overlapping_quoted_patterns() {
+        let char_first = Tokenizer::default()
+            .with_patterns(vec![
+                common::CHAR.clone(),
+                common::SINGLE_QUOTED_STRING.clone(),
+            ])
+            .unwrap();
+        let string_first = Tokenizer::default()
+            .with_patterns(vec![
+                common::SINGLE_QUOTED_STRING.clone(),
+                common::CHAR.clone(),
+            ])
+            .unwrap();
+
+        for longest_match in [false, true] {
+            let tok = char_first.clone().with_longest_match(longest_match);
+            let tokens: Vec<_> = tok.tokenize("'ab''a''\\''").flatten().collect();
             assert_eq!(
-                Tokenizer::default()
-                    .with_literals(literals)
-                    .unwrap()
-                    .with_patterns(patterns)
-                    .unwrap()
-                    .can_use_fast_mode(),
-                expected
+                tokens,
+                make_output(vec![
+                    (("single_quoted_string", "'ab'"), 0),
+                    (("char", "'a'"), 4),
+                    (("char", "'\\''"), 7),
+                ])
             );
-        }
-    }
 
-    #[test]
-    fn ignored_preparation() {
-        let tests = [
-            (FxHashSet::default(), false, FxHashSet::default()),
-            (
-                FxHashSet::from_iter(['a', 'b', 'c']),
-                false,
-                "abc".chars().collect(),
-            ),
-            (
-                FxHashSet::from_iter(['a', 'b', 'c']),
-                true,
-                "abc \x0c\t\x0b\r\n".chars().collect(),
-            ),
-        ];
-        for (ignored_characters, ignore_whitespace, expected) in tests {
+            let tok = string_first.clone().with_longest_match(longest_match);
+            let tokens: Vec<_> = tok.tokenize("'ab''a'").flatten().collect();
             assert_eq!(
-                Tokenizer::default()
-                    .with_ignore_whitespace(ignore_whitespace)
-                    .with_ignored_characters(ignored_characters)
-                    .prepare_ignored(),
-                expected
+                tokens,
+                make_output(vec![
+                    (("single_quoted_string", "'ab'"), 0),
+                    (("single_quoted_string", "'a'"), 4),
+                ])
             );
         }
     }
 
     #[test]
-    fn comparison() {
-        let def = Tokenizer::default();
-        assert_eq!(def, Tokenizer::default());
+    fn pattern_fn() {
+        // a run of the same operator character, which a regex can't express without backreferences
+        let run = |source: &str| {
+            let first = source.chars().next().filter(|c| "+-*".contains(*c))?;
+            Some(source.len() - source.trim_start_matches(first).len())
+        };
+        let tok = Tokenizer::default()
+            .with_literals(&[("plus", "+")])
+            .unwrap()
+            .with_patterns(vec![common::UNSIGNED_INT.clone()])
+            .unwrap()
+            .with_pattern_fn("run", run)
+            .unwrap()
+            .with_pattern_fn("empty", |source| source.starts_with('!').then_some(0))
+            .unwrap()
+            .with_pattern_fn("too_long", |source| source.starts_with('?').then_some(9))
+            .unwrap()
+            .with_pattern_fn("split_char", |source| source.starts_with('ž').then_some(1))
+            .unwrap();
+        let tokens: Vec<_> = tok.tokenize("1+2**3---4").flatten().collect();
         assert_eq!(
-            def,
-            Tokenizer::default().with_ignored_characters(FxHashSet::default())
-        );
-        assert_ne!(
-            def,
-            Tokenizer::default().with_ignored_characters(FxHashSet::from_iter(['x']))
+            tokens,
+            make_output(vec![
+                (("unsigned_int", "1"), 0),
+                (("plus", "+"), 1),
+                (("unsigned_int", "2"), 2),
+                (("run", "**"), 3),
+                (("unsigned_int", "3"), 5),
+                (("run", "---"), 6),
+                (("unsigned_int", "4"), 9),
+            ])
         );
-        assert_ne!(def, Tokenizer::default().with_ignore_whitespace(true));
-        assert_ne!(
-            def,
-            Tokenizer::default().with_literals(&[("1", "2")]).unwrap()
+        assert_eq!(
+            tok.token_names(),
+            [
+                "plus",
+                "unsigned_int",
+                "run",
+                "empty",
+                "too_long",
+                "split_char"
+            ]
         );
+
+        // literals take precedence, unless the match is longer with longest_match
+        assert_eq!(tok.probe("++", 0), Some(("plus", "+")));
         assert_eq!(
-            def.clone().with_literals(&[("1", "2")]).unwrap(),
-            Tokenizer::default().with_literals(&[("1", "2")]).unwrap()
+            tok.clone().with_longest_match(true).probe("++", 0),
+            Some(("run", "++"))
         );
+
+        let tokens: Vec<_> = tok.tokenize("!?ž").collect();
+        assert!(matches!(
+            tokens.as_slice(),
+            [
+                Err(Error::ZeroWidthMatch { name, position: 0 }),
+                Err(Error::BadToken('?', 1)),
+                Err(Error::BadToken('ž', 2)),
+            ] if name == "empty"
+        ));
+
+        assert_eq!(tok.clone(), tok);
         assert_ne!(
-            def,
-            Tokenizer::default()
-                .with_patterns([("1".into(), "2".into())].into())
-                .unwrap()
+            tok.clone(),
+            tok.clone().with_pattern_fn("run2", run).unwrap()
+        );
+        assert!(matches!(
+            tok.tokenize_rev(""),
+            Err(Error::UnsupportedPattern(name)) if name == "unsigned_int"
+        ));
+        assert!(matches!(
+            tok.clone().with_pattern_fn("plus", run),
+            Err(Error::DuplicateName(name)) if name == "plus"
+        ));
+        assert!(matches!(
+            tok.with_pattern_fn("run", run),
+            Err(Error::DuplicateName(name)) if name == "run"
+        ));
+
+        // single character literals alone would use the fast mode
+        let tok = Tokenizer::default()
+            .with_literals(&[("plus", "+")])
+            .unwrap()
+            .with_pattern_fn("run", run)
+            .unwrap();
+        let tokens: Vec<_> = tok.tokenize("+--").flatten().collect();
+        assert_eq!(
+            tokens,
+            make_output(vec![(("plus", "+"), 0), (("run", "--"), 1)])
         );
+        assert!(matches!(
+            tok.tokenize_rev(""),
+            Err(Error::UnsupportedPattern(name)) if name == "run"
+        ));
     }
 
     #[test]
-    fn builder_equivalence() {
-        let literals = [("a", "b")];
-        let patterns = vec![(String::from("a"), String::from("b"))];
-        let ignored_chars: FxHashSet<_> = FxHashSet::from_iter(['x']);
+    fn balanced() {
+        let tok = Tokenizer::default()
+            .with_literals(&[("comma", ",")])
+            .unwrap()
+            .with_patterns(vec![common::C_NAME.clone()])
+            .unwrap()
+            .with_balanced("parens", '(', ')')
+            .unwrap()
+            .with_balanced("pipes", '|', '|')
+            .unwrap();
+        let tokens: Vec<_> = tok.tokenize("f((a)(b)), |x|").flatten().collect();
+        assert_eq!(
+            tokens,
+            make_output(vec![
+                (("c_name", "f"), 0),
+                (("parens", "((a)(b))"), 1),
+                (("comma", ","), 9),
+                (("pipes", "|x|"), 11),
+            ])
+        );
+        assert_eq!(tok.token_names(), ["comma", "c_name", "parens", "pipes"]);
 
-        let mut tok1 = Tokenizer::default();
-        tok1.set_ignore_whitespace(true);
-        tok1.set_ignored_characters(ignored_chars.clone());
-        tok1.set_literals(&literals).unwrap();
-        tok1.set_patterns(patterns.clone()).unwrap();
+        let tokens: Vec<_> = tok.tokenize("((a)").collect();
+        assert!(matches!(
+            tokens.as_slice(),
+            [Err(Error::Unterminated { name, start: 0, end: 1 }), Ok(parens)]
+                if name == "parens" && parens == &Token::from(("parens", "(a)", 1))
+        ));
+        let tokens: Vec<_> = tok.tokenize("a)").collect();
+        assert!(matches!(
+            tokens.as_slice(),
+            [Ok(_), Err(Error::BadToken(')', 1))]
+        ));
 
-        let tok2 = Tokenizer::default()
+        // literals take precedence, unless the span is longer with longest_match
+        let tok = tok.with_literals(&[("lparen", "(")]).unwrap();
+        assert_eq!(tok.probe("(a)", 0), Some(("lparen", "(")));
+        let tok = tok.with_longest_match(true);
+        assert_eq!(tok.probe("(a)", 0), Some(("parens", "(a)")));
+        assert_eq!(tok.probe("(a", 0), Some(("lparen", "(")));
+
+        assert!(matches!(
+            tok.clone().with_balanced("c_name", '[', ']'),
+            Err(Error::DuplicateName(name)) if name == "c_name"
+        ));
+        assert!(matches!(
+            tok.clone().with_balanced("parens", '[', ']'),
+            Err(Error::DuplicateName(name)) if name == "parens"
+        ));
+        let tok = tok
+            .with_allow_duplicate_names(true)
+            .with_balanced("parens", '[', ']')
+            .unwrap();
+        assert!(duplicates(&tok).is_empty());
+        assert_eq!(
+            duplicates(&tok.with_allow_duplicate_names(false)),
+            ["parens"]
+        );
+    }
+
+    #[test]
+    fn heredoc() {
+        let tok = Tokenizer::default()
+            .with_literals(&[("shl", "<<")])
+            .unwrap()
+            .with_patterns(vec![common::C_NAME.clone()])
+            .unwrap()
+            .with_heredoc("heredoc", r"<<(\w+)")
+            .unwrap()
+            .with_heredoc("front_matter", "---")
+            .unwrap()
             .with_ignore_whitespace(true)
-            .with_ignored_characters(ignored_chars.clone())
-            .with_literals(&literals)
+            .with_longest_match(true);
+        let tokens: Vec<_> = tok
+            .tokenize("cat <<END x\r\nhi\n END\nEND\r\na << b\n---\nk\n---")
+            .flatten()
+            .collect();
+        assert_eq!(
+            tokens,
+            make_output(vec![
+                (("c_name", "cat"), 0),
+                (("heredoc", "<<END x\r\nhi\n END\nEND"), 4),
+                (("c_name", "a"), 26),
+                (("shl", "<<"), 28),
+                (("c_name", "b"), 31),
+                (("front_matter", "---\nk\n---"), 33),
+            ])
+        );
+        assert_eq!(
+            tok.token_names(),
+            ["shl", "c_name", "heredoc", "front_matter"]
+        );
+
+        // an unterminated heredoc falls back to the literal
+        assert_eq!(tok.probe("<<END", 0), Some(("shl", "<<")));
+        let no_literals = tok.clone().with_literals(&[]).unwrap();
+        let tokens: Vec<_> = no_literals.tokenize("x <<END\nhi\nENDING").collect();
+        assert!(matches!(
+            tokens.as_slice(),
+            [Ok(_), Err(Error::Unterminated { name, start: 2, end: 7 }), Ok(hi), Ok(ending)]
+                if name == "heredoc" && hi.value == "hi" && ending.value == "ENDING"
+        ));
+        let tokens: Vec<_> = no_literals.tokenize("<<END").collect();
+        assert!(matches!(
+            tokens.as_slice(),
+            [Err(Error::Unterminated { name, start: 0, end: 5 })] if name == "heredoc"
+        ));
+
+        // the literal takes precedence without longest_match
+        let tok = tok.with_longest_match(false);
+        assert_eq!(tok.probe("<<END\nEND", 0), Some(("shl", "<<")));
+
+        assert!(matches!(
+            tok.clone().with_heredoc("shl", "<<-(\\w+)"),
+            Err(Error::DuplicateName(name)) if name == "shl"
+        ));
+        assert!(matches!(
+            tok.with_heredoc("heredoc2", "<<("),
+            Err(Error::InvalidRegex { name, .. }) if name == "heredoc2"
+        ));
+    }
+
+    #[test]
+    fn tokenize_rev() {
+        let tok = Tokenizer::default()
+            .with_literals(&[
+                ("ab", "ab"),
+                ("bc", "bc"),
+                ("a", "a"),
+                ("c", "c"),
+                ("é", "é"),
+            ])
             .unwrap()
-            .with_patterns(patterns.clone())
+            .with_ignored_characters(FxHashSet::from_iter(['.']))
+            .with_max_token_length(Some(2));
+        let tokens: Vec<_> = tok.tokenize_rev("abc é.").unwrap().collect();
+        assert_eq!(
+            tokens.into_iter().flatten().collect::<Vec<_>>(),
+            make_output(vec![(("é", "é"), 4), (("bc", "bc"), 1), (("a", "a"), 0)])
+        );
+
+        let tokens: Vec<_> = tok.tokenize_rev("ax.é").unwrap().collect();
+        assert!(matches!(
+            tokens.as_slice(),
+            [Ok(_), Err(Error::BadToken('x', 1)), Ok(_)]
+        ));
+
+        let tok = tok
+            .with_emit_eof(true)
+            .with_literals(&[("long", "long")])
+            .unwrap();
+        let tokens: Vec<_> = tok.tokenize_rev("long").unwrap().collect();
+        assert!(matches!(
+            tokens.as_slice(),
+            [Err(Error::TokenTooLong {
+                length: 4,
+                position: 0,
+                ..
+            })]
+        ));
+        let tokens: Vec<_> = tok.tokenize_rev("").unwrap().flatten().collect();
+        assert_eq!(tokens, make_output(vec![(("eof", ""), 0)]));
+
+        let tok = tok.with_patterns(vec![common::INT.clone()]).unwrap();
+        assert!(matches!(
+            tok.tokenize_rev("1"),
+            Err(Error::UnsupportedPattern(name)) if name == "int"
+        ));
+        let tok = Tokenizer::default()
+            .with_skip_patterns(&[("comment", "#.*")])
+            .unwrap();
+        assert!(matches!(
+            tok.tokenize_rev(""),
+            Err(Error::UnsupportedPattern(name)) if name == "comment"
+        ));
+    }
+
+    #[test]
+    fn find_first() {
+        let tok = Tokenizer::default()
+            .with_literals(&[("arrow", "→")])
+            .unwrap()
+            .with_patterns(vec![("pair".into(), r"(?P<key>\w+)=(?P<value>\d+)".into())])
             .unwrap();
+        let (position, token) = tok.find_first("¿¡ a= b=12 → c=3").unwrap();
+        assert_eq!(position, 8);
+        assert_eq!(token.value, "b=12");
+        assert_eq!(token.captures, [("key", "b"), ("value", "12")]);
 
-        let tok3 = Tokenizer::new(&literals, patterns, ignored_chars, true).unwrap();
+        let (position, token) = tok.find_first("x → b=1").unwrap();
+        assert_eq!((position, token.name), (2, "arrow"));
+        assert!(tok.find_first("").is_none());
+        assert!(tok.find_first("a= =1").is_none());
+    }
 
-        assert_eq!(tok1, tok2);
-        assert_eq!(tok1, tok3);
-        assert_eq!(tok2, tok3);
+    #[test]
+    fn find_first_candidates() {
+        let tok = Tokenizer::default()
+            .with_literals(&[("eq", "==")])
+            .unwrap()
+            .with_patterns(vec![("int".into(), r"\d+(?=;)".into())])
+            .unwrap()
+            .with_balanced("parens", '(', ')')
+            .unwrap()
+            .with_heredoc("heredoc", r"<<(\w+)")
+            .unwrap();
+        // the first candidate found by each search doesn't match
+        for input in [
+            "= 1 (<<x =",
+            "= 1 (<<x = 2;",
+            "= 1 (<<x ==",
+            "= 1 (<<x = (a)",
+            "= 1 ( <<x\nx",
+            "",
+            "é",
+        ] {
+            let expected = input
+                .char_indices()
+                .find_map(|(offset, _)| Some((offset, tok.probe(input, offset)?)));
+            let found = tok
+                .find_first(input)
+                .map(|(position, token)| (position, (token.name, token.value)));
+            assert_eq!(found, expected, "{input:?}");
+        }
+
+        let tok = tok
+            .with_pattern_fn("x", |s| s.starts_with('x').then_some(1))
+            .unwrap();
+        let (position, token) = tok.find_first("= 1 (<<x =").unwrap();
+        assert_eq!((position, token.name), (7, "x"));
+
+        // matched on their own like in `probe`, without the preceding text
+        for (pattern, input, expected) in [(r"(?<!a)b", "ab b", 1), (r"\bif", "xif", 1)] {
+            let tok = Tokenizer::default()
+                .with_patterns(vec![("x".into(), pattern.into())])
+                .unwrap();
+            assert!(tok.probe(input, expected).is_some());
+            assert_eq!(tok.find_first(input).unwrap().0, expected, "{pattern:?}");
+        }
+    }
+
+    #[test]
+    fn probe() {
+        let tok = Tokenizer::default()
+            .with_literals(&[("kw_if", "if")])
+            .unwrap()
+            .with_patterns(vec![common::C_NAME.clone()])
+            .unwrap();
+        assert_eq!(tok.probe("x iffy", 2), Some(("kw_if", "if")));
+        assert_eq!(tok.probe("x iffy", 3), Some(("c_name", "ffy")));
+        assert_eq!(tok.probe("x iffy", 1), None);
+        assert_eq!(tok.probe("x iffy", 6), None);
+        assert_eq!(tok.probe("x iffy", 7), None);
+        assert_eq!(tok.probe("é", 1), None);
+
+        let tok = tok.with_longest_match(true);
+        assert_eq!(tok.probe("x iffy", 2), Some(("c_name", "iffy")));
+    }
+
+    #[test]
+    fn fused_with_size_hint() {
+        let fast = Tokenizer::default()
+            .with_literals(&[("a", "a")])
+            .unwrap()
+            .with_ignore_whitespace(true);
+        let core = fast
+            .clone()
+            .with_patterns(vec![common::DOUBLE_QUOTED_STRING.clone()])
+            .unwrap();
+        for tok in [
+            fast.clone(),
+            fast.with_emit_eof(true),
+            core.clone(),
+            core.with_emit_eof(true),
+        ] {
+            let mut tokens = tok.tokenize("a x a  ");
+            assert_eq!(tokens.size_hint(), (0, Some(7 + usize::from(tok.emit_eof))));
+            assert!(matches!(tokens.next(), Some(Ok(_))));
+            // errors don't end the iteration
+            assert!(matches!(tokens.next(), Some(Err(Error::BadToken('x', 2)))));
+            assert!(matches!(tokens.next(), Some(Ok(_))));
+            assert!(tokens.size_hint().1.is_some_and(|upper| upper <= 3));
+            assert!(tokens.next().is_none());
+            assert!(tokens.next().is_none());
+            assert!(tokens.size_hint().1.is_some_and(|upper| upper <= 2));
+        }
+    }
+
+    #[test]
+    fn try_tokenization() {
+        let tok = Tokenizer::default()
+            .with_literals(&[("a", "a"), ("ab", "ab")])
+            .unwrap();
+        assert_eq!(
+            tok.try_tokenize("aab").unwrap(),
+            make_output(vec![(("a", "a"), 0), (("ab", "ab"), 1)])
+        );
+        assert!(matches!(
+            tok.try_tokenize("abxa"),
+            Err(Error::BadToken('x', 2))
+        ));
+    }
+
+    #[test]
+    fn counting() {
+        let fast = Tokenizer::default()
+            .with_literals(&[("a", "a"), ("b", "b")])
+            .unwrap()
+            .with_ignore_whitespace(true);
+        let core = fast
+            .clone()
+            .with_patterns(vec![common::UNSIGNED_INT.clone()])
+            .unwrap();
+
+        for tok in [&fast, &core] {
+            assert_eq!(tok.count("").unwrap(), 0);
+            assert_eq!(tok.count("a b\nba").unwrap(), 4);
+            assert!(matches!(tok.count("ab x a"), Err(Error::BadToken('x', 3))));
+        }
+        assert_eq!(core.count("a 12 b345").unwrap(), 4);
+        assert_eq!(
+            core.count("a 12 b345").unwrap(),
+            core.tokenize("a 12 b345").count()
+        );
+    }
+
+    #[test]
+    fn escapes() {
+        let mut tok = Tokenizer::default()
+            .with_allow_duplicate_names(true)
+            .with_patterns(vec![common::STRING.clone(), ("raw".into(), r"r\S+".into())])
+            .unwrap()
+            // escapes only apply to patterns, not to a literal of the same name
+            .with_literals(&[("string", "\\")])
+            .unwrap()
+            .with_escapes("string", Some(r#"[nt\\'"]|u\{[0-9A-Fa-f]{1,6}\}"#))
+            .unwrap()
+            .with_ignore_whitespace(true);
+
+        for longest_match in [false, true] {
+            tok.set_longest_match(longest_match);
+            assert!(tok.try_tokenize(r#"'a\n' "\\q\u{1F980}" r\q \"#).is_ok());
+
+            let results: Vec<_> = tok.tokenize(r#"'ok' 'a\q\n' "\u{}" 'b'"#).collect();
+            assert!(matches!(
+                &results[..],
+                [
+                    Ok(_),
+                    Err(Error::InvalidEscape { name, position: 7 }),
+                    Err(Error::InvalidEscape { position: 14, .. }),
+                    Ok(Token { value: "'b'", .. }),
+                ] if name == "string"
+            ));
+        }
+        assert!(matches!(
+            tok.count(r#"'\x'"#),
+            Err(Error::InvalidEscape { position: 1, .. })
+        ));
+
+        tok.set_escapes("string", None).unwrap();
+        assert!(tok.try_tokenize(r#"'a\q'"#).is_ok());
+        assert!(matches!(
+            tok.set_escapes("string", Some("(")),
+            Err(Error::InvalidRegex { name, .. }) if name == "string"
+        ));
     }
 
     #[test]
-    fn builder_processing_literals() {
-        let mut tok = Tokenizer::default();
-        assert_eq!(tok.tree, Tree::Node(FxHashMap::default()));
+    fn bytes() {
+        let fast = Tokenizer::default()
+            .with_literals(&[("a", "a"), ("é", "é")])
+            .unwrap()
+            .with_ignore_whitespace(true);
+        let core = fast
+            .clone()
+            .with_patterns(vec![common::STRING.clone()])
+            .unwrap();
 
-        assert!(tok.set_literals(&[("a", "b")]).is_ok());
+        for tok in [&fast, &core] {
+            let source = "a é\na";
+            let expected: Vec<_> = tok.tokenize(source).map(|r| format!("{r:?}")).collect();
+            let results: Vec<_> = tok
+                .tokenize_bytes(source.as_bytes())
+                .map(|r| format!("{r:?}"))
+                .collect();
+            assert_eq!(results, expected);
 
-        let flipped_literals = FxHashMap::from_iter([("b", "a")]);
-        let expected_tree = generate_tree(&flipped_literals);
-        assert_eq!(tok.literals, flipped_literals);
-        assert_eq!(tok.tree, expected_tree);
+            // a truncated `é` followed by a lone continuation byte
+            let results: Vec<_> = tok.tokenize_bytes(b"a\xc3 \xa9\xa9a").collect();
+            assert!(matches!(
+                &results[..],
+                [
+                    Ok(Token { position: 0, .. }),
+                    Err(Error::InvalidUtf8 { position: 1 }),
+                    Err(Error::InvalidUtf8 { position: 3 }),
+                    Err(Error::InvalidUtf8 { position: 4 }),
+                    Ok(Token { position: 5, .. }),
+                ]
+            ));
+        }
 
-        assert!(tok.set_literals(&[("a", "")]).is_err());
+        let results: Vec<_> = core.tokenize_bytes(b"a 'x\xffy' a").collect();
+        assert!(matches!(
+            &results[..],
+            [
+                Ok(_),
+                Err(Error::Unterminated { start: 2, .. }),
+                Err(Error::BadToken('x', 3)),
+                Err(Error::InvalidUtf8 { position: 4 }),
+                Err(Error::BadToken('y', 5)),
+                Err(Error::Unterminated { start: 6, .. }),
+                Ok(Token { position: 8, .. }),
+            ]
+        ));
     }
 
     #[test]
-    fn builder_processing_patterns() {
-        let mut tok = Tokenizer::default();
-        assert!(tok.patterns.is_empty());
-
-        let pattern = ("a".into(), r"\d+".into());
-        assert!(tok.set_patterns(vec![pattern.clone()]).is_ok());
-        assert_eq!(tok.patterns.first().unwrap().1.as_str(), r"^(?:\d+)");
+    fn resumption() {
+        let fast = Tokenizer::default()
+            .with_literals(&[("a", "a"), ("b", "b"), ("é", "é")])
+            .unwrap()
+            .with_ignore_whitespace(true);
+        let core = fast
+            .clone()
+            .with_patterns(vec![common::STRING.clone(), common::UNSIGNED_INT.clone()])
+            .unwrap();
 
-        assert!(tok.set_patterns(vec![pattern.clone(), pattern]).is_ok());
-        assert!(tok.set_patterns(vec![("a".into(), "+".into())]).is_err());
-    }
+        let stringify = |results: &mut dyn Iterator<Item = Result<Token, Error>>| {
+            results.map(|r| format!("{r:?}")).collect::<Vec<_>>()
+        };
+        for tok in [&fast, &core] {
+            for source in ["a bé\n ?a", "ab 12 'x y' b ?? 3 'open"] {
+                let full = stringify(&mut tok.tokenize(source));
+                assert_eq!(
+                    stringify(&mut tok.tokenize_from(source, TokenizerState::default())),
+                    full
+                );
 
-    #[test]
-    fn empty_tokenizer() {
-        let tok = Tokenizer::default();
+                for (i, result) in tok.tokenize(source).enumerate() {
+                    let Ok(token) = result else { continue };
+                    let state = TokenizerState::after(&token);
+                    assert_eq!(
+                        stringify(&mut tok.tokenize_from(source, state)),
+                        full[i + 1..]
+                    );
+                }
+            }
+        }
 
-        assert!(tok.tokenize("").next().is_none());
+        // resuming before an edit gives the same tokens as a full scan of the edited source
+        let edited = "ab 12 'x y' 34";
+        let state = TokenizerState::after(&core.tokenize("ab 12 'x y' b").nth(2).unwrap().unwrap());
+        assert_eq!(
+            stringify(&mut core.tokenize_from(edited, state)),
+            stringify(&mut core.tokenize(edited).skip(3))
+        );
 
-        match tok.tokenize("source").next() {
-            Some(Err(Error::BadToken(c, p))) => assert_eq!((c, p), ('s', 0)),
-            _ => panic!("tokenization didn't fail with BadToken"),
-        };
+        // positions past the end or inside a character are reported instead of panicking
+        for position in [4, 10] {
+            let results: Vec<_> = core
+                .tokenize_from("ab é", TokenizerState::at(position))
+                .collect();
+            assert!(matches!(
+                &results[..],
+                [Err(Error::InvalidState { position: p })] if *p == position
+            ));
+        }
     }
 
     #[test]
-    fn brainfuck_fast_tokenizer() {
+    fn callback() {
         let tok = Tokenizer::default()
-            .with_literals(&[
-                ("add", "+"),
-                ("sub", "-"),
-                ("left", "<"),
-                ("right", ">"),
-                ("read", ","),
-                ("write", "."),
-                ("begin_loop", "["),
-                ("end_loop", "]"),
-            ])
-            .unwrap();
+            .with_literals(&[("a", "a")])
+            .unwrap()
+            .with_patterns(vec![common::UNSIGNED_INT.clone()])
+            .unwrap()
+            .with_ignore_whitespace(true);
 
-        if let Ok(tokens) = tok.tokenize(",[.,]").collect::<Result<Vec<_>, _>>() {
-            assert!(tokens
-                .iter()
-                .map(|t| t.name)
-                .eq("read begin_loop write read end_loop".split_whitespace()));
-        } else {
-            panic!();
-        };
-    }
+        let mut seen = Vec::new();
+        tok.tokenize_with("a 12 a", |token| seen.push(token.value))
+            .unwrap();
+        assert_eq!(seen, ["a", "12", "a"]);
 
-    fn make_output<'a>(tokens: Vec<((&'a str, &'a str), usize)>) -> Vec<Token<'a>> {
-        tokens
-            .into_iter()
-            .map(|((n, v), p)| Token::from((n, v, p)))
-            .collect()
+        seen.clear();
+        let result = tok.tokenize_with("a 1 ? a", |token| seen.push(token.value));
+        assert!(matches!(result, Err(Error::BadToken('?', 4))));
+        assert_eq!(seen, ["a", "1"]);
     }
 
     #[test]
-    fn arithmetic_tokenizer() {
-        let tests = [
-            (
-                "2 * 2 + 3 - 7",
-                make_output(vec![
-                    (("int", "2"), 0),
-                    (("mul", "*"), 2),
-                    (("int", "2"), 4),
-                    (("add", "+"), 6),
-                    (("int", "3"), 8),
-                    (("sub", "-"), 10),
-                    (("int", "7"), 12),
-                ]),
-            ),
-            (
-                "2**3",
-                make_output(vec![
-                    (("int", "2"), 0),
-                    (("pow", "**"), 1),
-                    (("int", "3"), 3),
-                ]),
-            ),
-            (
-                "-5",
-                make_output(vec![(("sub", "-"), 0), (("int", "5"), 1)]),
-            ),
-            (
-                "100 + -5",
-                make_output(vec![
-                    (("int", "100"), 0),
-                    (("add", "+"), 4),
-                    (("sub", "-"), 6),
-                    (("int", "5"), 7),
-                ]),
-            ),
-            (
-                "4 - 2 ** 5 / 2",
-                make_output(vec![
-                    (("int", "4"), 0),
-                    (("sub", "-"), 2),
-                    (("int", "2"), 4),
-                    (("pow", "**"), 6),
-                    (("int", "5"), 9),
-                    (("div", "/"), 11),
-                    (("int", "2"), 13),
-                ]),
-            ),
-            (
-                "10 % 3",
-                make_output(vec![
-                    (("int", "10"), 0),
-                    (("mod", "%"), 3),
-                    (("int", "3"), 5),
-                ]),
-            ),
+    fn compiled_patterns() {
+        let compiled = [
+            ("int", Regex::new(r"^\d+").unwrap()),
+            ("word", Regex::new(r"[a-z]+").unwrap()),
         ];
+        for anchor in [false, true] {
+            let mut tok = Tokenizer::default()
+                .with_compiled_patterns(&compiled, anchor)
+                .unwrap()
+                .with_ignore_whitespace(true);
+            assert!(tok.combined.is_some());
+            assert_eq!(tok.patterns[1].1.as_str().starts_with('^'), anchor);
 
-        let tok = Tokenizer::default()
-            .with_literals(&[
-                ("add", "+"),
-                ("sub", "-"),
-                ("mul", "*"),
-                ("div", "/"),
-                ("pow", "**"),
-                ("mod", "%"),
-            ])
+            for longest_match in [false, true] {
+                tok.set_longest_match(longest_match);
+                let results: Vec<_> = tok.tokenize("12ab ?cd").collect();
+                assert!(matches!(
+                    &results[..],
+                    [
+                        Ok(Token {
+                            name: "int",
+                            value: "12",
+                            ..
+                        }),
+                        Ok(Token {
+                            name: "word",
+                            value: "ab",
+                            ..
+                        }),
+                        Err(Error::BadToken('?', 5)),
+                        Ok(Token {
+                            name: "word",
+                            value: "cd",
+                            position: 6,
+                            ..
+                        }),
+                    ]
+                ));
+            }
+        }
+
+        assert!(matches!(
+            Tokenizer::default().with_compiled_patterns(
+                &[compiled[0].clone(), ("int", compiled[1].1.clone())],
+                false
+            ),
+            Err(Error::DuplicatePattern(_))
+        ));
+        // repeating the same pattern is redundant
+        assert!(Tokenizer::default()
+            .with_compiled_patterns(&[compiled[0].clone(), compiled[0].clone()], false)
+            .is_ok());
+    }
+
+    #[test]
+    fn eof() {
+        let fast = Tokenizer::default()
+            .with_literals(&[("a", "a")])
             .unwrap()
-            .with_patterns(vec![common::INT.clone()])
+            .with_ignore_whitespace(true)
+            .with_emit_eof(true);
+        let core = fast
+            .clone()
+            .with_patterns(vec![common::UNSIGNED_INT.clone()])
             .unwrap();
+        let eof = |position| Token::from(("eof", "", position));
 
-        for (input, output) in tests {
+        for tok in [&fast, &core] {
+            assert_eq!(tok.try_tokenize("").unwrap(), [eof(0)]);
+            let tokens = tok.try_tokenize("a a ").unwrap();
+            assert_eq!(tokens.len(), 3);
+            assert_eq!(tokens[2], eof(4));
+            assert_eq!(tok.count("a a ").unwrap(), 3);
+
+            let results: Vec<_> = tok.tokenize("a ? a").collect();
+            assert!(matches!(&results[..], [Ok(_), Err(_), Ok(_)]));
+            assert!(tok.count("a ? a").is_err());
+
+            let state = TokenizerState::after(&tokens[0]);
             assert_eq!(
-                tok.tokenize(input)
-                    .filter_map(Result::ok)
-                    .collect::<Vec<_>>(),
-                output
+                tok.tokenize_from("a a ", state).last().unwrap().unwrap(),
+                eof(4)
             );
+            assert_eq!(tok.tokenize_bytes(b"a \xff").last().unwrap().ok(), None);
+            assert_eq!(tok.tokenize_bytes(b"a a").last().unwrap().unwrap(), eof(3));
+            assert_eq!(tok.match_full("a").unwrap(), Token::from(("a", "a", 0)));
         }
-    }
+        #[cfg(feature = "rayon")]
+        assert!(core
+            .tokenize_lines("a\n1")
+            .all(|line| line.unwrap().len() == 1));
 
-    #[test]
-    fn line_tokenization() {
-        let tok = Tokenizer::default()
-            .with_ignore_whitespace(true)
-            .with_patterns(vec![common::WORD.clone()])
-            .unwrap();
-        let Ok(lines) = tok
-            .tokenize_lines("a b\nc\rde")
-            .collect::<Result<Vec<Vec<Token>>, _>>()
-        else {
-            panic!("tokenization failed");
-        };
-        assert_eq!(
-            lines,
-            vec![
-                make_output(vec![(("word", "a"), 0), (("word", "b"), 2)]),
-                make_output(vec![(("word", "c"), 0), (("word", "de"), 2)]),
-            ]
-        );
+        let mut tok = fast.clone();
+        tok.set_emit_eof(false);
+        assert_eq!(tok.try_tokenize("a").unwrap().len(), 1);
     }
 
     #[test]
-    fn line_tokenization_fast() {
-        let (a, b) = (("a", "a"), ("b", "b"));
+    fn full_match() {
         let tok = Tokenizer::default()
-            .with_ignore_whitespace(true)
-            .with_literals(&[a, b])
-            .unwrap();
-        let Ok(lines) = tok
-            .tokenize_lines("a b\nb\ra")
-            .collect::<Result<Vec<Vec<Token>>, _>>()
-        else {
-            panic!("tokenization failed");
-        };
-        assert_eq!(
-            lines,
-            vec![
-                make_output(vec![(a, 0), (b, 2)]),
-                make_output(vec![(b, 0), (a, 2)])
-            ]
-        );
-    }
+            .with_literals(&[("a", "a")])
+            .unwrap()
+            .with_patterns(vec![common::UNSIGNED_INT.clone(), common::STRING.clone()])
+            .unwrap()
+            .with_ignore_whitespace(true);
 
-    #[test]
-    fn breakpoint_tokenization() {
-        let (x, y, z) = (("x", "abc"), ("y", "a"), ("z", "b"));
-        let tok = Tokenizer::default().with_literals(&[x, y, z]).unwrap();
-        let Ok(tokens) = tok.tokenize("ababaababc").collect::<Result<Vec<_>, _>>() else {
-            panic!("tokenization failed");
-        };
         assert_eq!(
-            tokens,
-            make_output(vec![
-                (y, 0),
-                (z, 1),
-                (y, 2),
-                (z, 3),
-                (y, 4),
-                (y, 5),
-                (z, 6),
-                (x, 7)
-            ])
+            tok.match_full("1_000").unwrap(),
+            Token::from(("unsigned_int", "1_000", 0))
         );
-    }
-
-    #[test]
-    fn multichar_breakpoint_tokenization() {
-        let (x, y, z) = (("x", "ab"), ("y", "bc"), ("z", "abcd"));
-        let tok = Tokenizer::default().with_literals(&[x, y, z]).unwrap();
-        let source = "ccddbabcaabcccdcbaaabdaabcbaabbbabaaaccabcdabaabadbcacddacbddbcb";
-        let tokens: Vec<_> = tok.tokenize(source).flatten().collect();
         assert_eq!(
-            tokens,
-            make_output(vec![
-                (x, 5),
-                (x, 9),
-                (x, 19),
-                (x, 23),
-                (x, 28),
-                (x, 32),
-                (z, 39),
-                (x, 43),
-                (x, 46),
-                (y, 50),
-                (y, 61)
-            ])
+            tok.match_full("  12\n").unwrap(),
+            Token::from(("unsigned_int", "12", 2))
         );
-    }
+        assert!(matches!(tok.match_full(""), Err(Error::EmptyInput)));
+        assert!(matches!(tok.match_full(" \t"), Err(Error::EmptyInput)));
+        assert!(matches!(
+            tok.match_full("12a"),
+            Err(Error::TrailingInput { position: 2 })
+        ));
+        assert!(matches!(
+            tok.match_full("12 ?"),
+            Err(Error::TrailingInput { position: 3 })
+        ));
+        assert!(matches!(
+            tok.match_full("1 'ab"),
+            Err(Error::TrailingInput { position: 2 })
+        ));
+        assert!(matches!(
+            tok.match_full("?12"),
+            Err(Error::BadToken('?', 0))
+        ));
 
-    #[test]
-    fn fast_tokenization_with_ignoreset() {
-        let (foo, bar) = (("foo", "x"), ("bar", "y"));
-        let tok = Tokenizer::default()
-            .with_literals(&[foo, bar])
-            .unwrap()
-            .with_ignored_characters(FxHashSet::from_iter(['z']));
-        let Ok(tokens) = tok.tokenize("xzy").collect::<Result<Vec<_>, _>>() else {
-            panic!("tokenization failed");
-        };
-        assert_eq!(tokens, make_output(vec![(foo, 0), (bar, 2)]));
+        let fast = Tokenizer::default().with_literals(&[("a", "a")]).unwrap();
+        assert_eq!(fast.match_full("a").unwrap(), Token::from(("a", "a", 0)));
+        assert!(matches!(
+            fast.match_full("aa"),
+            Err(Error::TrailingInput { position: 1 })
+        ));
     }
 
     #[test]
-    fn core_tokenization_with_ignoreset() {
-        let (foo, bar) = (("foo", "xz"), ("bar", "yz"));
+    fn max_token_length() {
         let tok = Tokenizer::default()
-            .with_literals(&[foo, bar])
+            .with_literals(&[("arrow", "-->"), ("dash", "-")])
             .unwrap()
-            .with_ignored_characters(FxHashSet::from_iter(['z']));
-        let Ok(tokens) = tok
-            .tokenize("zxzyzxzyzzzyzzxzyzzzxzz")
-            .collect::<Result<Vec<_>, _>>()
-        else {
-            panic!("tokenization failed");
-        };
-        assert_eq!(
-            tokens,
-            make_output(vec![
-                (foo, 1),
-                (bar, 3),
-                (foo, 5),
-                (bar, 7),
-                (bar, 11),
-                (foo, 14),
-                (bar, 16),
-                (foo, 20)
+            .with_patterns(vec![common::STRING.clone()])
+            .unwrap()
+            .with_max_token_length(Some(4));
+        let results: Vec<_> = tok.tokenize("-->'ab''abcd'-'éé'").collect();
+        assert!(matches!(
+            &results[..],
+            [
+                Ok(Token { name: "arrow", .. }),
+                Ok(Token { name: "string", .. }),
+                Err(Error::TokenTooLong { name, length: 6, position: 7 }),
+                Ok(Token { name: "dash", .. }),
+                // the length is in bytes, not characters
+                Err(Error::TokenTooLong { length: 6, position: 14, .. }),
+            ] if name == "string"
+        ));
+        assert!(matches!(
+            tok.clone()
+                .with_max_token_length(Some(2))
+                .try_tokenize("-->"),
+            Err(Error::TokenTooLong { length: 3, .. })
+        ));
+
+        let fast = Tokenizer::default()
+            .with_literals(&[("a", "a")])
+            .unwrap()
+            .with_max_token_length(Some(0));
+        assert!(matches!(
+            fast.try_tokenize("a"),
+            Err(Error::TokenTooLong { length: 1, .. })
+        ));
+        assert_eq!(fast.with_max_token_length(Some(1)).count("aa").unwrap(), 2);
+
+        // greedy matches are cut off right past the limit instead of spanning the whole source
+        let words = Tokenizer::default()
+            .with_patterns(vec![
+                common::C_NAME.clone(),
+                ("line".into(), r"-[^\n]*".into()),
             ])
-        );
+            .unwrap()
+            .with_max_token_length(Some(4));
+        let source = format!("ab-{}", "x".repeat(1 << 20));
+        let results: Vec<_> = words.tokenize(&source).take(2).collect();
+        assert!(matches!(
+            &results[..],
+            [
+                Ok(Token { value: "ab", .. }),
+                Err(Error::TokenTooLong { name, length: 5, position: 2 }),
+            ] if name == "line"
+        ));
+        // the cut is on a character boundary
+        assert!(matches!(
+            words.tokenize("-ééé").next(),
+            Some(Err(Error::TokenTooLong { length: 5, .. }))
+        ));
     }
 
     #[test]
-    fn whitespace_tokenization() {
-        let (cr, ln, space) = (("cr", "\r"), ("ln", "\n"), ("space", " "));
+    fn backtrack_limit() {
+        // exponentially many ways to split the source, none of them followed by a `c`
+        let patterns = vec![("ab".into(), "(a|b|ab)*(?=c)".into()), common::WORD.clone()];
         let tok = Tokenizer::default()
-            .with_literals(&[cr, ln, space])
+            .with_patterns(patterns.clone())
+            .unwrap()
+            .with_backtrack_limit(Some(1000))
             .unwrap();
-        let source = " \r\n \r \n ";
-
-        let Ok(tokens) = tok.tokenize(source).collect::<Result<Vec<_>, _>>() else {
-            panic!("tokenization failed");
-        };
         assert_eq!(
-            tokens,
-            make_output(vec![
-                (space, 0),
-                (cr, 1),
-                (ln, 2),
-                (space, 3),
-                (cr, 4),
-                (space, 5),
-                (ln, 6),
-                (space, 7)
-            ])
+            tok,
+            Tokenizer::default()
+                .with_backtrack_limit(Some(1000))
+                .unwrap()
+                .with_patterns(patterns)
+                .unwrap()
         );
 
-        let Ok(lines) = tok
-            .tokenize_lines(source)
-            .collect::<Result<Vec<Vec<Token>>, _>>()
-        else {
-            panic!("tokenization failed");
-        };
-        assert_eq!(
-            lines,
-            vec![
-                make_output(vec![(space, 0), (cr, 1)]),
-                make_output(vec![(space, 0), (cr, 1), (space, 2)]),
-                make_output(vec![(space, 0)]),
-            ]
-        );
+        let source = "ab".repeat(30);
+        assert!(matches!(
+            tok.tokenize(&source).next(),
+            Some(Err(Error::Timeout { name, position: 0 })) if name == "ab"
+        ));
+        assert_eq!(tok.try_tokenize("abc").unwrap()[0].value, "ab");
+        assert_eq!(tok.try_tokenize("xy").unwrap()[0].name, "word");
+
+        // the comment is only valid with the flag set by the builder, which recompiling loses
+        let commented = fancy_regex::RegexBuilder::new(r"^a # (")
+            .ignore_whitespace(true)
+            .build()
+            .unwrap();
+        let mut tok = Tokenizer::default()
+            .with_compiled_patterns(&[("a", commented)], false)
+            .unwrap();
+        let unchanged = tok.clone();
+        assert!(matches!(
+            tok.set_backtrack_limit(Some(1000)),
+            Err(Error::InvalidRegex { name, .. }) if name == "a"
+        ));
+        assert_eq!(tok, unchanged);
     }
 
     #[test]
-    fn bad_tokenization_fast() {
-        let tok = Tokenizer::default();
-        let Some(Err(Error::BadToken(err_value, err_position))) = tok.tokenize("x").last() else {
-            panic!("tokenization didn't fail with BadToken");
-        };
-        assert_eq!(err_value, 'x');
-        assert_eq!(err_position, 0);
+    fn byte_offsets() {
+        let source = "ó a\t∑ ab";
+        for tok in [
+            Tokenizer::default().with_literals(&[("a", "a")]).unwrap(),
+            Tokenizer::default()
+                .with_patterns(vec![("a".into(), "a".into())])
+                .unwrap(),
+        ] {
+            let tok = tok.with_ignore_whitespace(true);
+            let results: Vec<_> = tok.tokenize(source).collect();
+            assert!(matches!(
+                &results[..],
+                [
+                    Err(Error::BadToken('ó', 0)),
+                    Ok(Token { position: 3, .. }),
+                    Err(Error::BadToken('∑', 5)),
+                    Ok(Token { position: 9, .. }),
+                    Err(Error::BadToken('b', 10)),
+                ]
+            ));
+            assert!(source[5..].starts_with('∑'));
+        }
     }
 
     #[test]
-    fn bad_tokenization_core() {
-        let tok = Tokenizer::default().with_literals(&[("xy", "xy")]).unwrap();
-        let Some(Err(Error::BadToken(err_value, err_position))) =
-            tok.tokenize("xyz").find(Result::is_err)
-        else {
-            panic!("tokenization didn't fail with BadToken");
-        };
-        assert_eq!(err_value, 'z');
-        assert_eq!(err_position, 2);
+    fn owned_tokenization() {
+        fn tokenize(source: &str) -> Vec<Result<OwnedToken, Error>> {
+            let tok = Tokenizer::default()
+                .with_literals(&[("a", "a")])
+                .unwrap()
+                .with_patterns(vec![("num".into(), "[0-9]+".into())])
+                .unwrap();
+            tok.tokenize_owned(source).collect()
+        }
+
+        let tokens = std::thread::spawn(|| tokenize(&String::from("a12x")))
+            .join()
+            .unwrap();
+        assert!(matches!(
+            &tokens[..],
+            [Ok(a), Ok(num), Err(Error::BadToken('x', 3))]
+                if *a == Token::from(("a", "a", 0)).into_owned()
+                    && *num == Token::from(("num", "12", 1)).into_owned()
+        ));
     }
 
     #[test]
-    fn word_tokenization() {
+    fn recovery() {
         let tok = Tokenizer::default()
-            .with_patterns(vec![common::WORD.clone()])
+            .with_literals(&[("a", "a"), ("ab", "ab")])
             .unwrap();
-        let tokens: Vec<_> = tok.tokenize("Hello, world!").flatten().collect();
+        let (tokens, errors) = tok.tokenize_recover("aéxab!");
         assert_eq!(
             tokens,
-            make_output(vec![(("word", "Hello"), 0), (("word", "world"), 7)])
+            make_output(vec![(("a", "a"), 0), (("ab", "ab"), 4)])
         );
+        assert!(matches!(
+            &errors[..],
+            [
+                Error::BadToken('é', 1),
+                Error::BadToken('x', 3),
+                Error::BadToken('!', 6)
+            ]
+        ));
+
+        let fast = Tokenizer::default().with_literals(&[("a", "a")]).unwrap();
+        let (tokens, errors) = fast.tokenize_recover("éa");
+        assert_eq!(tokens, make_output(vec![(("a", "a"), 2)]));
+        assert!(matches!(&errors[..], [Error::BadToken('é', 0)]));
+
+        let (tokens, errors) = tok.tokenize_recover("aab");
+        assert_eq!(tokens.len(), 2);
+        assert!(errors.is_empty());
+
+        let strings = Tokenizer::default()
+            .with_literals(&[("a", "a")])
+            .unwrap()
+            .with_patterns(vec![("string".into(), "'[^']*'".into())])
+            .unwrap();
+        let (tokens, errors) = strings.tokenize_recover("'abc a a");
+        assert_eq!(tokens.len(), 3);
+        assert!(matches!(
+            &errors[..],
+            [
+                Error::Unterminated { name, start: 0, end: 1 },
+                Error::BadToken('b', 2),
+                Error::BadToken('c', 3),
+                Error::BadToken(' ', 4),
+                Error::BadToken(' ', 6),
+            ] if name == "string"
+        ));
     }
 
     #[test]
-    fn fast_tokenization_continues_after_bad_token() {
-        let (a, b) = (("a", "a"), ("b", "b"));
-        let tok = Tokenizer::default().with_literals(&[a, b]).unwrap();
-        let mut expected_tokens =
-            make_output(vec![(a, 0), (b, 3), (b, 4), (a, 5), (a, 7)]).into_iter();
-        let mut expected_error_indexes = [1, 2, 6].into_iter();
+    fn unterminated() {
+        let tok = Tokenizer::default()
+            .with_literals(&[("eq", "=")])
+            .unwrap()
+            .with_patterns(vec![
+                ("name".into(), r"[a-z]+".into()),
+                ("string".into(), r#""[^"]*""#.into()),
+                ("comment".into(), r"(?s)/\*.*?\*/".into()),
+                ("group".into(), r"\([a-z]*\)".into()),
+            ])
+            .unwrap();
 
-        let tokens: Vec<_> = tok.tokenize("axxbbaxa").collect();
-        assert_eq!(tokens.len(), 8);
+        let tokens: Vec<_> = tok.tokenize("x=\"abc de").collect();
+        assert!(matches!(
+            &tokens[..],
+            [
+                Ok(_),
+                Ok(_),
+                Err(Error::Unterminated { name, start: 2, end: 3 }),
+                Ok(abc),
+                Err(Error::BadToken(' ', 6)),
+                Ok(de),
+            ] if name == "string" && abc.value == "abc" && de.value == "de"
+        ));
 
-        for result in &tokens {
-            match result {
-                Ok(tok) => assert_eq!(tok, &expected_tokens.next().unwrap()),
-                Err(Error::BadToken(c, p)) => {
-                    assert_eq!((*c, *p), ('x', expected_error_indexes.next().unwrap()));
-                }
-                _ => panic!("unexpected error"),
-            }
-        }
+        assert!(matches!(
+            tok.try_tokenize("x/* abc\n"),
+            Err(Error::Unterminated { name, start: 1, end: 3 }) if name == "comment"
+        ));
+        assert!(matches!(
+            tok.try_tokenize("(abc"),
+            Err(Error::Unterminated { name, start: 0, end: 1 }) if name == "group"
+        ));
+
+        // not a prefix of any pattern
+        assert!(matches!(
+            tok.try_tokenize("x@y"),
+            Err(Error::BadToken('@', 1))
+        ));
+        assert!(matches!(
+            tok.try_tokenize("(ab1)"),
+            Err(Error::BadToken('(', 0))
+        ));
+
+        // a quote in each line, as in a long run of unterminated strings
+        let source = "\"\n".repeat(10_000);
+        let tok = Tokenizer::default()
+            .with_patterns(vec![("string".into(), r#""[^"\n]*""#.into())])
+            .unwrap();
+        let errors = tok.tokenize(&source).filter(Result::is_err).count();
+        assert_eq!(errors, 20_000);
     }
 
     #[test]
-    fn core_tokenization_continues_after_bad_token() {
+    fn non_ascii_literal_fallback() {
         let tok = Tokenizer::default()
-            .with_literals(&[("a", "axe")])
+            .with_literals(&[("x", "é"), ("y", "éab")])
+            .unwrap();
+        let tokens: Vec<_> = tok.tokenize("éazéab").collect();
+        assert!(matches!(
+            &tokens[..],
+            [Ok(x), Err(Error::BadToken('a', 2)), Err(Error::BadToken('z', 3)), Ok(y)]
+                if x == &Token::from(("x", "é", 0)) && y == &Token::from(("y", "éab", 4))
+        ));
+    }
+
+    #[test]
+    fn empty_patterns() {
+        let tok = Tokenizer::default()
+            .with_patterns(vec![
+                ("empty".into(), String::new()),
+                ("star".into(), "a*".into()),
+                ("lookahead".into(), "(?=b)".into()),
+            ])
+            .unwrap();
+        let tokens: Vec<_> = tok.tokenize("aab").collect();
+        assert!(matches!(
+            &tokens[..],
+            [Ok(a), Err(Error::ZeroWidthMatch { name, position: 2 })]
+                if a == &Token::from(("star", "aa", 0)) && name == "empty"
+        ));
+        assert_eq!(tok.zero_width_patterns(), ["empty", "star"]);
+    }
+
+    #[test]
+    fn zero_width_match() {
+        let tok = Tokenizer::default()
+            .with_literals(&[("plus", "+")])
             .unwrap()
-            .with_patterns([("b".into(), "box".into())].into())
+            .with_patterns(vec![("star".into(), "a*".into())])
             .unwrap();
-        let expected_tokens = [
-            ("a", "axe", 0),
-            ("b", "box", 4),
-            ("a", "axe", 8),
-            ("a", "axe", 12),
-            ("b", "box", 16),
-            ("b", "box", 20),
-        ];
-        let expected_errors = ['&', ',', ',', '.', '.'];
+        let tokens: Vec<_> = tok.tokenize("a+b+").collect();
+        assert!(matches!(
+            &tokens[..],
+            [Ok(a), Ok(_), Err(Error::ZeroWidthMatch { name, position: 2 }), Ok(_)]
+                if a == &Token::from(("star", "a", 0)) && name == "star"
+        ));
+        assert!(matches!(
+            tok.count("b"),
+            Err(Error::ZeroWidthMatch { position: 0, .. })
+        ));
 
-        let tokens: Vec<_> = tok.tokenize("axe&box,axe,axe.box.box").collect();
-        assert_eq!(tokens.len(), 11);
+        let tok = tok.with_patterns(vec![("as".into(), "a+".into())]).unwrap();
+        assert!(tok.zero_width_patterns().is_empty());
+        assert!(matches!(
+            tok.tokenize("b").next(),
+            Some(Err(Error::BadToken('b', 0)))
+        ));
+    }
 
-        for i in (0..tokens.len()).step_by(2) {
-            assert_eq!(
-                tokens[i].as_ref().unwrap(),
-                &Token::from(expected_tokens[i / 2])
-            );
-        }
-        for i in (1..tokens.len()).step_by(2) {
-            let expected_err = (expected_errors[i / 2], i * 2 + 1);
-            assert!(matches!(tokens[i], Err(Error::BadToken(c, p)) if (c, p) == expected_err));
+    #[test]
+    fn adversarial_inputs() {
+        let deep = "(".repeat(500);
+        let tok = Tokenizer::default()
+            .with_literals(&[("open", "("), ("close", ")"), ("deep", &deep)])
+            .unwrap()
+            .with_patterns(vec![common::STRING.clone(), common::C_NAME.clone()])
+            .unwrap()
+            .with_ignore_whitespace(true);
+
+        let nested = format!("{}x{}", "(".repeat(10_000), ")".repeat(10_000));
+        assert_eq!(tok.tokenize(&nested).count(), 20 + 1 + 10_000);
+        assert!(tok.tokenize(&nested).all(|t| t.is_ok()));
+
+        for source in [
+            r#""\ud800""#,
+            r#""\"#,
+            r"\u{10ffff}\u{0}",
+            "\u{10ffff}\u{0}\u{feff}",
+            "'\u{301}'",
+            "\u{1f600}\u{200d}\u{1f600}",
+            "\r\n\r\n",
+        ] {
+            let tokens: Vec<_> = tok.tokenize(source).collect();
+            let consumed: usize = tokens
+                .iter()
+                .map(|t| match t {
+                    Ok(token) => token.value.len(),
+                    Err(Error::BadToken(c, _)) => c.len_utf8(),
+                    Err(Error::Unterminated { start, end, .. }) => end - start,
+                    Err(_) => 0,
+                })
+                .sum();
+            let ignored = source.chars().filter(char::is_ascii_whitespace).count();
+            assert_eq!(consumed + ignored, source.len());
         }
     }
 
     #[test]
-    fn backreference_pattern() {
+    fn duplicate_pattern_names() {
+        let patterns = vec![
+            ("digit".into(), "[0-9]".into()),
+            ("digit".into(), "[0-9]+".into()),
+            ("space".into(), " ".into()),
+            ("digit".into(), r"\d".into()),
+        ];
+        assert!(matches!(
+            Tokenizer::default().with_patterns(patterns.clone()),
+            Err(Error::DuplicatePattern(name)) if name == "digit"
+        ));
+        assert!(matches!(
+            Tokenizer::new(&[], patterns.clone(), FxHashSet::default(), false),
+            Err(Error::DuplicatePattern(name)) if name == "digit"
+        ));
+        assert!(matches!(
+            Tokenizer::default().with_patterns_ext(
+                patterns
+                    .iter()
+                    .map(|(n, p)| (n.clone(), p.clone(), RegexOptions::default()))
+                    .collect()
+            ),
+            Err(Error::DuplicatePattern(_))
+        ));
+
         let tok = Tokenizer::default()
-            .with_patterns(vec![("a".into(), r"(a)b\1".into())])
+            .with_heredoc("digit", "<<(\\w+)")
             .unwrap();
-        let out: Vec<_> = tok.tokenize("abaaba").flatten().collect();
-        assert_eq!(out, make_output(vec![(("a", "aba"), 0), (("a", "aba"), 3)]));
+        assert!(matches!(
+            tok.with_patterns(vec![("digit".into(), "[0-9]".into())]),
+            Err(Error::DuplicateName(name)) if name == "digit"
+        ));
+
+        let tok = Tokenizer::default()
+            .with_allow_duplicate_names(true)
+            .with_patterns(patterns)
+            .unwrap();
+        assert!(tok.validate().is_ok());
+        let tokens: Vec<_> = tok.tokenize("12").flatten().collect();
+        assert_eq!(
+            tokens,
+            make_output(vec![(("digit", "1"), 0), (("digit", "2"), 1)])
+        );
     }
 
     #[test]
     fn duplicate_literal_names() {
+        // literals of the same name are aliases, not duplicates
         let (a, b) = (("a", "a"), ("a", "b"));
         let tok = Tokenizer::default().with_literals(&[a, b]).unwrap();
+        assert!(tok.validate().is_ok());
         let tokens: Vec<_> = tok.tokenize("ab").flatten().collect();
         assert_eq!(tokens, make_output(vec![(a, 0), (b, 1)]));
     }
+
+    #[test]
+    fn duplicate_skip_pattern_names() {
+        assert!(matches!(
+            Tokenizer::default().with_skip_patterns(&[("space", " "), ("space", "\t")]),
+            Err(Error::DuplicatePattern(name)) if name == "space"
+        ));
+
+        let tok = Tokenizer::default()
+            .with_literals(&[("plus", "+")])
+            .unwrap()
+            .with_skip_patterns(&[("comment", "#.*")])
+            .unwrap();
+        assert!(matches!(
+            tok.clone().with_skip_patterns(&[("plus", r"\+\+")]),
+            Err(Error::DuplicateName(name)) if name == "plus"
+        ));
+        assert!(matches!(
+            tok.clone().with_patterns(vec![("comment".into(), "//.*".into())]),
+            Err(Error::DuplicateName(name)) if name == "comment"
+        ));
+        assert!(matches!(
+            tok.clone().with_balanced("comment", '(', ')'),
+            Err(Error::DuplicateName(name)) if name == "comment"
+        ));
+        assert!(matches!(
+            tok.with_literals(&[("comment", "//")]),
+            Err(Error::DuplicateName(name)) if name == "comment"
+        ));
+    }
+
+    #[test]
+    fn redundant_pattern_names() {
+        // a pattern matching just the literal of the same name can't be told apart from it
+        let tok = Tokenizer::default()
+            .with_literals(&[("plus", "+")])
+            .unwrap()
+            .with_patterns(vec![("plus".into(), r"\+".into())])
+            .unwrap();
+        assert!(tok.validate().is_ok());
+        assert!(matches!(
+            tok.with_patterns(vec![("plus".into(), r"\++".into())]),
+            Err(Error::DuplicateName(name)) if name == "plus"
+        ));
+
+        let digit = ("digit".to_string(), "[0-9]".to_string());
+        let tok = Tokenizer::default()
+            .with_patterns(vec![digit.clone(), digit])
+            .unwrap();
+        assert!(tok.validate().is_ok());
+        assert!(matches!(
+            tok.with_literals(&[("digit", "0")]),
+            Err(Error::DuplicateName(name)) if name == "digit"
+        ));
+    }
 }
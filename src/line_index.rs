@@ -0,0 +1,218 @@
+use crate::{Error, Token};
+
+/// Translates byte offsets into a source (e.g. [`Token::position`](crate::Token::position) or the
+/// position of an [`Error`](crate::Error)) into 1-based line and column numbers.
+///
+/// The line starts are computed once when the index is built, so each lookup is a binary search for
+/// the line followed by counting the columns up to the offset within that line, instead of
+/// scanning the source from the start for every reported error.
+///
+/// Lines are split on `\n`, just like in the line-based tokenization of
+/// [`Tokenizer`](crate::Tokenizer), so the `\r` of a `\r\n` line break is the last character of
+/// its line and a lone `\r` doesn't start a new line. Columns are counted in characters, not bytes.
+/// A tab counts as a single column too, unless a [tab width](LineIndex::with_tab_width) is set.
+///
+/// # Examples
+/// ```rust
+/// # use crossandra::{Error, LineIndex, Tokenizer};
+/// let tok = Tokenizer::default()
+///     .with_literals(&[("a", "a")])
+///     .unwrap()
+///     .with_ignore_whitespace(true);
+/// let source = "a a\r\nřa?\na";
+/// let index = LineIndex::new(source);
+///
+/// let (_, errors) = tok.tokenize_recover(source);
+/// let positions: Vec<_> = errors
+///     .iter()
+///     .map(|err| match err {
+///         Error::BadToken(_, position) => index.line_col(*position),
+///         _ => unreachable!(),
+///     })
+///     .collect();
+/// assert_eq!(positions, [(2, 1), (2, 3)]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct LineIndex<'a> {
+    source: &'a str,
+    line_starts: Vec<usize>,
+    tab_width: usize,
+}
+
+impl<'a> LineIndex<'a> {
+    /// Builds the index for the given `source`.
+    #[must_use]
+    pub fn new(source: &'a str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        Self {
+            source,
+            line_starts,
+            tab_width: 1,
+        }
+    }
+
+    /// Sets the tab width of this [`LineIndex`] and returns itself.
+    ///
+    /// See [`LineIndex::set_tab_width`] for more details.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `tab_width` is 0.
+    #[must_use]
+    pub fn with_tab_width(mut self, tab_width: usize) -> Self {
+        self.set_tab_width(tab_width);
+        self
+    }
+
+    /// Sets the tab width used for computing columns, so that they line up with an editor using
+    /// the same width. A tab advances the column to the next multiple of the width (e.g. with
+    /// a width of 4, `\t\tfoo` has `foo` at column 9, and `ab\tc` has `c` at column 5).
+    ///
+    /// Defaults to 1, i.e. a tab counts as a single column, like any other character.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `tab_width` is 0.
+    pub fn set_tab_width(&mut self, tab_width: usize) {
+        assert!(tab_width > 0, "the tab width has to be positive");
+        self.tab_width = tab_width;
+    }
+
+    /// Returns the number of lines in the source. An empty source and a source ending with `\n`
+    /// have an empty last line.
+    #[must_use]
+    pub fn lines(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    /// Returns the 1-based line and column of the given byte `offset`. Offsets past the end of
+    /// the source are clamped to its length, i.e. the position of the
+    /// [end-of-input token](crate::Tokenizer#emit_eof).
+    ///
+    /// The `offset` should lie on a character boundary, an offset inside of a multi-byte
+    /// character is treated as pointing to the next character.
+    #[must_use]
+    pub fn line_col(&self, offset: usize) -> (usize, usize) {
+        let offset = offset.min(self.source.len());
+        let line = self.line_starts.partition_point(|&start| start <= offset) - 1;
+        let start = self.line_starts[line];
+        let column = self.source.as_bytes()[start..offset]
+            .iter()
+            .filter(|&&byte| !is_continuation_byte(byte))
+            .fold(0, |column, &byte| match byte {
+                b'\t' => (column / self.tab_width + 1) * self.tab_width,
+                _ => column + 1,
+            });
+        (line + 1, column + 1)
+    }
+
+    /// Returns the byte offset of the start of the given 1-based `line`, or [`None`] if there is
+    /// no such line.
+    #[must_use]
+    pub fn line_start(&self, line: usize) -> Option<usize> {
+        self.line_starts.get(line.checked_sub(1)?).copied()
+    }
+
+    /// Converts the line-relative positions of the [`Token`]s and [`Error`]s of each line, as
+    /// returned by `Tokenizer::tokenize_lines` (with the `rayon` feature), into byte offsets into
+    /// the whole source.
+    pub fn to_absolute_positions(&self, lines: &mut [Result<Vec<Token<'_>>, Error>]) {
+        for (line, &start) in lines.iter_mut().zip(&self.line_starts) {
+            match line {
+                Ok(tokens) => {
+                    for token in tokens {
+                        token.position += start;
+                    }
+                }
+                Err(err) => err.shift(start),
+            }
+        }
+    }
+}
+
+fn is_continuation_byte(byte: u8) -> bool {
+    byte & 0b1100_0000 == 0b1000_0000
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LineIndex;
+    use crate::{Error, Token};
+
+    #[test]
+    fn line_col() {
+        let index = LineIndex::new("ab\ncž\r\n\nx\ry");
+        assert_eq!(index.lines(), 4);
+
+        let expected = [
+            (0, (1, 1)),
+            (2, (1, 3)),
+            (3, (2, 1)),
+            (4, (2, 2)),
+            (6, (2, 3)),
+            (7, (2, 4)),
+            (8, (3, 1)),
+            (9, (4, 1)),
+            (11, (4, 3)),
+            (12, (4, 4)),
+            (100, (4, 4)),
+        ];
+        for (offset, position) in expected {
+            assert_eq!(index.line_col(offset), position, "offset {offset}");
+        }
+
+        assert_eq!(index.line_start(0), None);
+        assert_eq!(index.line_start(2), Some(3));
+        assert_eq!(index.line_start(4), Some(9));
+        assert_eq!(index.line_start(5), None);
+    }
+
+    #[test]
+    fn tab_width() {
+        let index = LineIndex::new("\t\tfoo\nab\tc\n\tž\tx");
+        assert_eq!(index.line_col(2), (1, 3));
+        assert_eq!(index.line_col(15), (3, 4));
+
+        let index = index.with_tab_width(4);
+        assert_eq!(index.line_col(1), (1, 5));
+        assert_eq!(index.line_col(2), (1, 9));
+        assert_eq!(index.line_col(4), (1, 11));
+        assert_eq!(index.line_col(9), (2, 5));
+        assert_eq!(index.line_col(15), (3, 9));
+    }
+
+    #[test]
+    #[should_panic = "the tab width has to be positive"]
+    fn zero_tab_width() {
+        let _ = LineIndex::new("").with_tab_width(0);
+    }
+
+    #[test]
+    fn empty() {
+        let index = LineIndex::new("");
+        assert_eq!(index.lines(), 1);
+        assert_eq!(index.line_col(0), (1, 1));
+
+        let index = LineIndex::new("a\n");
+        assert_eq!(index.lines(), 2);
+        assert_eq!(index.line_col(2), (2, 1));
+    }
+
+    #[test]
+    fn absolute_positions() {
+        let source = "ab c\n\nd ?";
+        let mut lines = vec![
+            Ok(vec![
+                Token::from(("word", "ab", 0)),
+                Token::from(("word", "c", 3)),
+            ]),
+            Ok(vec![]),
+            Err(Error::BadToken('?', 2)),
+        ];
+        LineIndex::new(source).to_absolute_positions(&mut lines);
+        assert_eq!(lines[0].as_ref().unwrap()[1].position, 3);
+        assert!(matches!(lines[2], Err(Error::BadToken('?', 8))));
+    }
+}
@@ -1,50 +1,467 @@
-use fancy_regex::Regex;
-use rustc_hash::FxHashSet;
+use std::sync::Arc;
+
+use fancy_regex::{Regex, RegexBuilder};
+use rustc_hash::FxHashMap;
 
 use crate::error::Error;
 
-pub(crate) fn prepare(patterns: Vec<(String, String)>) -> Result<Vec<(String, Regex)>, Error> {
-    compile(adjust(patterns))
+pub(crate) type Pattern = (String, Arc<Regex>, RegexOptions);
+
+/// A matcher returning the byte length of its match at the start of the given input.
+pub(crate) type Matcher = dyn Fn(&str) -> Option<usize> + Send + Sync;
+
+/// A named [pattern function](crate::Tokenizer#pattern-functions).
+#[derive(Clone)]
+pub(crate) struct PatternFn {
+    pub name: String,
+    pub matcher: Arc<Matcher>,
+}
+
+impl std::fmt::Debug for PatternFn {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PatternFn")
+            .field("name", &self.name)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "regex-cache")]
+type CacheKey = (String, RegexOptions, Option<usize>);
+
+/// Regexes compiled so far, keyed by the anchored pattern, its options and backtrack limit.
+#[cfg(feature = "regex-cache")]
+static CACHE: std::sync::OnceLock<std::sync::Mutex<FxHashMap<CacheKey, Arc<Regex>>>> =
+    std::sync::OnceLock::new();
+
+/// The maximum number of regexes in the [`CACHE`].
+#[cfg(feature = "regex-cache")]
+const CACHE_CAPACITY: usize = 256;
+
+/// Locks the [`CACHE`], initializing it on the first use.
+#[cfg(feature = "regex-cache")]
+fn cache() -> std::sync::MutexGuard<'static, FxHashMap<CacheKey, Arc<Regex>>> {
+    CACHE
+        .get_or_init(std::sync::Mutex::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+}
+
+/// Flags used when compiling a [pattern](crate::Tokenizer#patterns), equivalent to the respective
+/// inline flags. All of them are disabled by default.
+///
+/// # Examples
+/// ```
+/// # use crossandra::{RegexOptions, Tokenizer};
+/// let options = RegexOptions { case_insensitive: true, ..Default::default() };
+/// let tok = Tokenizer::default()
+///     .with_patterns_ext(vec![("keyword".into(), "select|from".into(), options)])
+///     .expect("the pattern should be valid");
+/// # assert_eq!(tok.tokenize("SELECT").count(), 1);
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RegexOptions {
+    /// Letters match both upper and lower case (`(?i)`).
+    pub case_insensitive: bool,
+    /// `.` matches any character, including `\n` (`(?s)`).
+    pub dot_matches_new_line: bool,
+    /// Whitespace in the pattern is ignored and `#` starts a comment (`(?x)`).
+    pub ignore_whitespace: bool,
+    /// How the pattern is anchored, see [`AnchorMode`].
+    pub anchor: AnchorMode,
+}
+
+/// How a [pattern](crate::Tokenizer#patterns) is anchored when compiled.
+///
+/// Patterns are always anchored to the start of the remaining source, as the tokenizer scans the
+/// source from left to right. They can additionally be anchored to its end, so that the token has
+/// to reach the end of the source (or of the line, when using `Tokenizer::tokenize_lines` with
+/// the `rayon` feature), e.g. for a trailing comment
+/// or for validating whole inputs with [`Tokenizer::match_full`](crate::Tokenizer::match_full).
+///
+/// # Examples
+/// ```
+/// # use crossandra::{AnchorMode, RegexOptions, Tokenizer};
+/// let options = RegexOptions { anchor: AnchorMode::StartAndEnd, ..Default::default() };
+/// let tok = Tokenizer::default()
+///     .with_patterns_ext(vec![("number".into(), r"\d+".into(), options)])
+///     .unwrap();
+/// assert!(tok.match_full("123").is_ok());
+/// assert!(tok.match_full("123 ").is_err());
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AnchorMode {
+    /// Anchored to the start only (`^`).
+    #[default]
+    Start,
+    /// Anchored to both the start and the end (`^...$`).
+    StartAndEnd,
+}
+
+impl RegexOptions {
+    fn build(
+        self,
+        pattern: &str,
+        backtrack_limit: Option<usize>,
+    ) -> Result<Regex, Box<fancy_regex::Error>> {
+        let mut builder = RegexBuilder::new(pattern);
+        builder
+            .case_insensitive(self.case_insensitive)
+            .dot_matches_new_line(self.dot_matches_new_line)
+            .ignore_whitespace(self.ignore_whitespace);
+        if let Some(limit) = backtrack_limit {
+            builder.backtrack_limit(limit);
+        }
+        builder.build().map_err(Box::new)
+    }
+
+    #[cfg(not(feature = "regex-cache"))]
+    fn build_shared(
+        self,
+        pattern: &str,
+        backtrack_limit: Option<usize>,
+    ) -> Result<Arc<Regex>, Box<fancy_regex::Error>> {
+        self.build(pattern, backtrack_limit).map(Arc::new)
+    }
+
+    /// Returns the cached regex for the pattern, compiling it only on the first use.
+    ///
+    /// Once the cache is full, the regexes no longer used by any tokenizer are evicted. If all of
+    /// them are still in use, the new regex isn't cached at all.
+    #[cfg(feature = "regex-cache")]
+    fn build_shared(
+        self,
+        pattern: &str,
+        backtrack_limit: Option<usize>,
+    ) -> Result<Arc<Regex>, Box<fancy_regex::Error>> {
+        let key = (pattern.to_owned(), self, backtrack_limit);
+        if let Some(regex) = cache().get(&key) {
+            return Ok(Arc::clone(regex));
+        }
+
+        // compile without holding the lock, a concurrent duplicate compilation is harmless
+        let regex = Arc::new(self.build(pattern, backtrack_limit)?);
+        let mut cache = cache();
+        if cache.len() >= CACHE_CAPACITY {
+            cache.retain(|_, regex| Arc::strong_count(regex) > 1);
+        }
+        if cache.len() < CACHE_CAPACITY {
+            cache.insert(key, Arc::clone(&regex));
+        }
+        Ok(regex)
+    }
+}
+
+/// The prefix of the names of the groups wrapping the patterns in a [`Combined`] regex.
+const GROUP_PREFIX: &str = "__crossandra_";
+
+/// All patterns combined into a single alternation, each wrapped in a named group, so that a single
+/// regex call finds the first matching pattern.
+#[derive(Debug, Clone)]
+pub(crate) struct Combined {
+    regex: Arc<Regex>,
+    /// The capture group index of each pattern, in declaration order.
+    groups: Vec<usize>,
+}
+
+impl Combined {
+    /// Combines the given patterns, or returns [`None`] if there are too few of them to benefit
+    /// from it or if any of them can't be embedded in a larger regex.
+    pub(crate) fn new(patterns: &[Pattern], backtrack_limit: Option<usize>) -> Option<Self> {
+        // the group names can't clash with any groups inside the patterns
+        if patterns.len() < 2
+            || patterns.iter().any(|(_, regex, _)| {
+                has_backref(regex.as_str())
+                    || regex
+                        .capture_names()
+                        .flatten()
+                        .any(|name| name.starts_with(GROUP_PREFIX))
+            })
+        {
+            return None;
+        }
+
+        let alternation = patterns
+            .iter()
+            .enumerate()
+            .map(|(i, (_, regex, options))| {
+                // the patterns are already anchored, the flags have to be applied inline
+                let flags: String = [
+                    (options.case_insensitive, 'i'),
+                    (options.dot_matches_new_line, 's'),
+                    (options.ignore_whitespace, 'x'),
+                ]
+                .iter()
+                .filter_map(|&(enabled, flag)| enabled.then_some(flag))
+                .collect();
+                if flags.is_empty() {
+                    format!("(?P<{GROUP_PREFIX}{i}>{})", regex.as_str())
+                } else {
+                    format!("(?P<{GROUP_PREFIX}{i}>(?{flags}:{}))", regex.as_str())
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("|");
+
+        // anchor the whole alternation as well, otherwise a failed match would scan the rest of the
+        // source, as the anchors of the alternatives aren't recognized by the regex engine
+        let regex = RegexOptions::default()
+            .build_shared(&format!("^(?:{alternation})"), backtrack_limit)
+            .ok()?;
+        let indices: FxHashMap<_, _> = regex
+            .capture_names()
+            .enumerate()
+            .filter_map(|(index, name)| Some((name?.to_owned(), index)))
+            .collect();
+        let groups = (0..patterns.len())
+            .map(|i| indices.get(&format!("{GROUP_PREFIX}{i}")).copied())
+            .collect::<Option<_>>()?;
+
+        Some(Self { regex, groups })
+    }
+
+    /// Finds the first pattern matching the start of the source. Returns its index along with the
+    /// match, or [`None`] if no pattern matches.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the regex engine fails, e.g. by exceeding the backtrack limit.
+    pub(crate) fn find<'s>(
+        &self,
+        source: &'s str,
+    ) -> Result<Option<(usize, fancy_regex::Match<'s>)>, Box<fancy_regex::Error>> {
+        let Some(captures) = self.regex.captures(source).map_err(Box::new)? else {
+            return Ok(None);
+        };
+        Ok(self
+            .groups
+            .iter()
+            .enumerate()
+            .find_map(|(i, &group)| Some((i, captures.get(group)?))))
+    }
+}
+
+/// Returns whether the pattern might contain a backreference (or a conditional), whose group
+/// numbers would be shifted when embedded in a larger regex. Errs on the side of caution.
+fn has_backref(pattern: &str) -> bool {
+    if pattern.contains("(?P=") || pattern.contains("(?(") {
+        return true;
+    }
+
+    let mut escaped = false;
+    pattern.chars().any(|c| {
+        let backref = escaped && (c.is_ascii_digit() || c == 'k' || c == 'g');
+        escaped = !escaped && c == '\\';
+        backref
+    })
+}
+
+/// Returns whether a match of the pattern might depend on the text before it (e.g. through a
+/// look-behind or a word boundary), or start later than where the pattern is matched (`\K`). Errs
+/// on the side of caution.
+fn depends_on_preceding(pattern: &str) -> bool {
+    if pattern.contains("(?<=") || pattern.contains("(?<!") {
+        return true;
+    }
+
+    let mut escaped = false;
+    pattern.chars().any(|c| {
+        let assertion = escaped && matches!(c, 'b' | 'B' | 'A' | 'G' | 'K' | '<' | '>');
+        escaped = !escaped && c == '\\';
+        assertion
+    })
+}
+
+/// Returns whether the regex matches an empty string, i.e. whether it could match without making
+/// progress.
+pub(crate) fn matches_empty(regex: &Regex) -> bool {
+    matches!(regex.find(""), Ok(Some(_)))
+}
+
+/// Compiles the regex of allowed [escape sequences](crate::Tokenizer#escapes), anchored to the
+/// start of the text following a backslash.
+pub(crate) fn prepare_escapes(name: &str, escapes: &str) -> Result<Arc<Regex>, Error> {
+    RegexOptions::default()
+        .build_shared(&format!("^(?:{escapes})"), None)
+        .map_err(|e| Error::InvalidRegex {
+            name: name.into(),
+            source: e,
+        })
+}
+
+pub(crate) fn prepare(
+    patterns: Vec<(String, String)>,
+    backtrack_limit: Option<usize>,
+) -> Result<Vec<Pattern>, Error> {
+    prepare_with_options(
+        patterns
+            .into_iter()
+            .map(|(name, pattern)| (name, pattern, RegexOptions::default()))
+            .collect(),
+        backtrack_limit,
+    )
+}
+
+pub(crate) fn prepare_with_options(
+    patterns: Vec<(String, String, RegexOptions)>,
+    backtrack_limit: Option<usize>,
+) -> Result<Vec<Pattern>, Error> {
+    compile(adjust(patterns), backtrack_limit)
+}
+
+/// Uses the already compiled regexes as they are, or compiles them again anchored to the start if
+/// `anchor` is set.
+pub(crate) fn prepare_compiled(
+    patterns: &[(&str, Regex)],
+    anchor: bool,
+    backtrack_limit: Option<usize>,
+) -> Result<Vec<Pattern>, Error> {
+    if anchor {
+        return prepare(
+            patterns
+                .iter()
+                .map(|(name, regex)| ((*name).to_owned(), regex.as_str().to_owned()))
+                .collect(),
+            backtrack_limit,
+        );
+    }
+    Ok(patterns
+        .iter()
+        .map(|(name, regex)| {
+            (
+                (*name).to_owned(),
+                Arc::new(regex.clone()),
+                RegexOptions::default(),
+            )
+        })
+        .collect())
+}
+
+/// Compiles the already prepared patterns again with a different backtrack limit.
+pub(crate) fn recompile(
+    patterns: &[Pattern],
+    backtrack_limit: Option<usize>,
+) -> Result<Vec<Pattern>, Error> {
+    compile(
+        patterns
+            .iter()
+            .map(|(name, regex, options)| (name.clone(), regex.as_str().to_owned(), *options))
+            .collect(),
+        backtrack_limit,
+    )
 }
 
-fn compile(patterns: Vec<(String, String)>) -> Result<Vec<(String, Regex)>, Error> {
+fn compile(
+    patterns: Vec<(String, String, RegexOptions)>,
+    backtrack_limit: Option<usize>,
+) -> Result<Vec<Pattern>, Error> {
     patterns
         .into_iter()
-        .map(|(key, val)| {
-            Regex::new(&val)
-                .map(|regex| (key, regex))
-                .map_err(|e| Error::InvalidRegex(Box::new(e)))
+        .map(|(key, val, options)| {
+            options
+                .build_shared(&val, backtrack_limit)
+                .map_err(|e| Error::InvalidRegex {
+                    name: key.clone(),
+                    source: e,
+                })
+                .map(|regex| (key, regex, options))
         })
         .collect()
 }
 
-fn force_start_anchor(pattern: &str) -> String {
-    let mut prev_char: Option<char> = None;
+fn force_anchor(pattern: &str, mode: AnchorMode) -> String {
+    let stripped = strip_anchors(pattern);
+    match mode {
+        AnchorMode::Start => format!("^(?:{stripped})"),
+        AnchorMode::StartAndEnd => format!("^(?:{stripped})$"),
+    }
+}
+
+/// Removes all `^` anchors from the pattern, keeping escaped ones and ones inside character
+/// classes.
+fn strip_anchors(pattern: &str) -> String {
+    let mut escaped = false;
+    // `^` is never an anchor inside a (possibly nested) character class
+    let mut class_depth = 0_usize;
+    // whether the class has just been opened, i.e. a `]` is its first member, not its end
+    let mut class_start = false;
+    // whether the class has just been opened and a `^` would negate it
+    let mut negatable = false;
+
+    pattern
+        .chars()
+        .filter(|&c| {
+            let (was_class_start, was_negatable) = (class_start, negatable);
+            class_start = false;
+            negatable = false;
 
-    let anchor_indices: FxHashSet<usize> = pattern
-        .char_indices()
-        .filter(|(_, c)| {
-            let prev = prev_char.unwrap_or('\0');
-            prev_char = Some(*c);
-            *c == '^' && !matches!(prev, '[' | '\\')
+            if escaped {
+                escaped = false;
+                return true;
+            }
+
+            match c {
+                '\\' => escaped = true,
+                '[' => {
+                    class_depth += 1;
+                    class_start = true;
+                    negatable = true;
+                }
+                '^' if was_negatable => class_start = true,
+                ']' if class_depth > 0 && !was_class_start => class_depth -= 1,
+                '^' if class_depth == 0 => return false,
+                _ => {}
+            }
+            true
         })
-        .map(|(i, _)| i)
-        .collect();
+        .collect()
+}
 
-    format!(
-        "^(?:{})",
-        pattern
-            .chars()
-            .enumerate()
-            .filter_map(|(i, char)| (!anchor_indices.contains(&i)).then_some(char))
-            .collect::<String>()
-    )
+/// Compiles the prepared pattern again without its `^` anchors, so that it finds its first match
+/// anywhere in a text. Returns [`None`] if it fails to compile, or if a search could skip a
+/// position where the pattern matches on its own, i.e. if its matches depend on the preceding text.
+pub(crate) fn unanchor(
+    (_, regex, options): &Pattern,
+    backtrack_limit: Option<usize>,
+) -> Option<Regex> {
+    let stripped = strip_anchors(regex.as_str());
+    if depends_on_preceding(&stripped) {
+        return None;
+    }
+    options.build(&stripped, backtrack_limit).ok()
 }
 
-fn adjust(patterns: Vec<(String, String)>) -> Vec<(String, String)> {
+/// Anchors the given regex `pattern` exactly like the [`Tokenizer`](crate::Tokenizer) does before
+/// compiling it, e.g. to reuse a [pattern](crate::Tokenizer#patterns) in other regex-based tooling
+/// with the same behavior.
+///
+/// Any `^` anchors in the pattern are removed (escaped ones and ones inside character classes are
+/// kept) and the whole pattern is wrapped in a group anchored according to
+/// [`RegexOptions::anchor`]. With [`RegexOptions::ignore_whitespace`], a trailing comment is
+/// terminated first, so that it doesn't swallow the closing parenthesis. The other flags aren't
+/// part of the returned pattern and have to be set when compiling it.
+///
+/// # Examples
+/// ```
+/// # use crossandra::{anchor_pattern, AnchorMode, RegexOptions};
+/// assert_eq!(anchor_pattern(r"x|^y", &RegexOptions::default()), r"^(?:x|y)");
+/// assert_eq!(anchor_pattern(r"[^a]\^", &RegexOptions::default()), r"^(?:[^a]\^)");
+///
+/// let options = RegexOptions { anchor: AnchorMode::StartAndEnd, ..Default::default() };
+/// assert_eq!(anchor_pattern(r"\d+", &options), r"^(?:\d+)$");
+/// ```
+#[must_use]
+pub fn anchor_pattern(pattern: &str, options: &RegexOptions) -> String {
+    if options.ignore_whitespace {
+        force_anchor(&format!("{pattern}\n"), options.anchor)
+    } else {
+        force_anchor(pattern, options.anchor)
+    }
+}
+
+fn adjust(patterns: Vec<(String, String, RegexOptions)>) -> Vec<(String, String, RegexOptions)> {
     patterns
         .into_iter()
-        .map(|(name, pattern)| (name, force_start_anchor(&pattern)))
+        .map(|(name, pattern, options)| (name, anchor_pattern(&pattern, &options), options))
         .collect()
 }
 
@@ -52,19 +469,31 @@ fn adjust(patterns: Vec<(String, String)>) -> Vec<(String, String)> {
 mod tests {
     use crate::{
         error::Error,
-        patterns::{compile, force_start_anchor, prepare},
+        patterns::{
+            anchor_pattern, compile, depends_on_preceding, force_anchor, has_backref, prepare,
+            prepare_with_options, AnchorMode, Combined, RegexOptions,
+        },
     };
 
     #[test]
     fn compile_ok() {
-        let patterns = vec![("foo".into(), String::new()), ("bar".into(), r"\d+".into())];
-        assert!(compile(patterns).is_ok());
+        let patterns = vec![
+            ("foo".into(), String::new(), RegexOptions::default()),
+            ("bar".into(), r"\d+".into(), RegexOptions::default()),
+        ];
+        assert!(compile(patterns, None).is_ok());
     }
 
     #[test]
     fn compile_err() {
-        let patterns = vec![("foo".into(), String::new()), ("bar".into(), r"+".into())];
-        assert!(matches!(compile(patterns), Err(Error::InvalidRegex(_))));
+        let patterns = vec![
+            ("foo".into(), String::new(), RegexOptions::default()),
+            ("bar".into(), r"+".into(), RegexOptions::default()),
+        ];
+        assert!(matches!(
+            compile(patterns, None),
+            Err(Error::InvalidRegex { name, .. }) if name == "bar"
+        ));
     }
 
     #[test]
@@ -77,20 +506,77 @@ mod tests {
             (r"^x|^y", r"^(?:x|y)"),
             (r"^x|\^y", r"^(?:x|\^y)"),
             (r"ba[^rz]", r"^(?:ba[^rz])"),
-            (r"^\^^[^]^", r"^(?:\^[^])"),
+            (r"^\^^[^]^", r"^(?:\^[^]^)"),
+            (r"\\^x", r"^(?:\\x)"),
+            (r"\\\^x", r"^(?:\\\^x)"),
+            (r"\\\\^x", r"^(?:\\\\x)"),
+            (r"a\\|^b", r"^(?:a\\|b)"),
+            (r"é^x", r"^(?:éx)"),
+            (r"[ab^cd]", r"^(?:[ab^cd])"),
+            (r"[^a^b]", r"^(?:[^a^b])"),
+            (r"[^^]^x", r"^(?:[^^]x)"),
+            (r"[]^]^x", r"^(?:[]^]x)"),
+            (r"[^]^]^x", r"^(?:[^]^]x)"),
+            (r"[\]^]^x", r"^(?:[\]^]x)"),
+            (r"\[^x]", r"^(?:\[x])"),
+            (r"[[:alpha:]^]^x", r"^(?:[[:alpha:]^]x)"),
+            (r"[a[^b]^]|^x", r"^(?:[a[^b]^]|x)"),
         ];
         for (inp, out) in tests {
-            assert_eq!(force_start_anchor(inp), out);
+            assert_eq!(force_anchor(inp, AnchorMode::Start), out);
         }
+
+        assert_eq!(force_anchor(r"^x|y", AnchorMode::StartAndEnd), r"^(?:x|y)$");
+
+        let options = RegexOptions {
+            ignore_whitespace: true,
+            ..Default::default()
+        };
+        assert_eq!(anchor_pattern("^a # b", &options), "^(?:a # b\n)");
+        assert_eq!(
+            anchor_pattern("^a", &RegexOptions::default()),
+            force_anchor("a", AnchorMode::Start)
+        );
+    }
+
+    #[test]
+    fn end_anchor() {
+        let options = RegexOptions {
+            anchor: AnchorMode::StartAndEnd,
+            ..Default::default()
+        };
+        let patterns = prepare_with_options(
+            vec![
+                ("comment".into(), "# .*".into(), options),
+                ("word".into(), r"\w+".into(), options),
+            ],
+            None,
+        )
+        .unwrap();
+        assert!(patterns[0].1.is_match("# note").unwrap());
+        assert!(!patterns[1].1.is_match("ab cd").unwrap());
+        assert!(patterns[1].1.is_match("abcd").unwrap());
+
+        let options = RegexOptions {
+            ignore_whitespace: true,
+            ..options
+        };
+        let patterns = prepare_with_options(
+            vec![("word".into(), r"\w+  # a word".into(), options)],
+            None,
+        )
+        .unwrap();
+        assert!(patterns[0].1.is_match("abcd").unwrap());
+        assert!(!patterns[0].1.is_match("ab cd").unwrap());
     }
 
     #[test]
     fn prepare_ok() {
-        let Ok(patterns) = prepare(vec![("digit".into(), "[0-9]".into())]) else {
+        let Ok(patterns) = prepare(vec![("digit".into(), "[0-9]".into())], None) else {
             panic!("prepare returned an Err")
         };
         match &patterns[..] {
-            [(name, pat)] => {
+            [(name, pat, _)] => {
                 assert_eq!(name, "digit");
                 assert_eq!(pat.as_str(), "^(?:[0-9])");
             }
@@ -100,11 +586,182 @@ mod tests {
 
     #[test]
     fn prepare_err() {
-        assert!(prepare(vec![("digit".into(), "[0-9".into())]).is_err());
-        assert!(prepare(vec![
-            ("digit".into(), "[0-9]".into()),
-            ("digit".into(), "[0-9]".into())
-        ])
+        assert!(prepare(vec![("digit".into(), "[0-9".into())], None).is_err());
+        assert!(prepare(
+            vec![
+                ("digit".into(), "[0-9]".into()),
+                ("digit".into(), "[0-9]".into())
+            ],
+            None
+        )
         .is_ok());
     }
+
+    #[test]
+    fn prepare_with_flags() {
+        let options = RegexOptions {
+            case_insensitive: true,
+            dot_matches_new_line: true,
+            ignore_whitespace: true,
+            anchor: AnchorMode::Start,
+        };
+        let Ok(patterns) = prepare_with_options(
+            vec![(
+                "comment".into(),
+                r"^ /\* .*? \*/  # a block comment".into(),
+                options,
+            )],
+            None,
+        ) else {
+            panic!("prepare_with_options returned an Err")
+        };
+        let [(_, pat, pat_options)] = &patterns[..] else {
+            panic!("prepare_with_options returned a vec of length != 1")
+        };
+        assert_eq!(pat_options, &options);
+        assert_eq!(
+            pat.find("/* A\nb */").unwrap().unwrap().as_str(),
+            "/* A\nb */"
+        );
+        assert!(pat.find("x/* */").unwrap().is_none());
+
+        let Ok(patterns) = prepare_with_options(
+            vec![(
+                "kw".into(),
+                "if".into(),
+                RegexOptions {
+                    case_insensitive: true,
+                    ..Default::default()
+                },
+            )],
+            None,
+        ) else {
+            panic!("prepare_with_options returned an Err")
+        };
+        assert_eq!(patterns[0].1.as_str(), "^(?:if)");
+        assert!(patterns[0].1.is_match("IF").unwrap());
+        assert!(!patterns[0].1.is_match("xIF").unwrap());
+    }
+
+    #[cfg(feature = "regex-cache")]
+    #[test]
+    fn cached() {
+        use std::sync::Arc;
+
+        use super::{cache, CACHE_CAPACITY};
+
+        let first = prepare(vec![("digit".into(), "[0-9]".into())], None).unwrap();
+        let second = prepare(vec![("other".into(), "^[0-9]".into())], None).unwrap();
+        assert!(Arc::ptr_eq(&first[0].1, &second[0].1));
+
+        let options = RegexOptions {
+            case_insensitive: true,
+            ..Default::default()
+        };
+        let third =
+            prepare_with_options(vec![("digit".into(), "[0-9]".into(), options)], None).unwrap();
+        assert!(!Arc::ptr_eq(&first[0].1, &third[0].1));
+
+        // unused regexes are evicted to make room, the ones still in use are kept
+        let unused = prepare(
+            (0..CACHE_CAPACITY)
+                .map(|i| (format!("unused{i}"), format!("unused{i}")))
+                .collect(),
+            None,
+        )
+        .unwrap();
+        drop(unused);
+        prepare(vec![("new".into(), "new".into())], None).unwrap();
+        assert!(cache().len() <= CACHE_CAPACITY);
+        let fourth = prepare(vec![("digit".into(), "[0-9]".into())], None).unwrap();
+        assert!(Arc::ptr_eq(&first[0].1, &fourth[0].1));
+    }
+
+    #[test]
+    fn combined() {
+        let case_insensitive = RegexOptions {
+            case_insensitive: true,
+            ..Default::default()
+        };
+        let patterns = prepare_with_options(
+            vec![
+                ("kw".into(), "if|else".into(), case_insensitive),
+                ("p0".into(), r"(?P<p0>\d)+".into(), RegexOptions::default()),
+                ("name".into(), r"\w+".into(), RegexOptions::default()),
+                ("op".into(), r"[+-]".into(), RegexOptions::default()),
+            ],
+            None,
+        )
+        .unwrap();
+        let combined = Combined::new(&patterns, None).expect("the patterns should be combinable");
+
+        let find = |source| {
+            combined
+                .find(source)
+                .unwrap()
+                .map(|(index, tok)| (index, tok.as_str()))
+        };
+        // declaration order takes precedence
+        assert_eq!(find("ELSE x"), Some((0, "ELSE")));
+        assert_eq!(find("123abc"), Some((1, "123")));
+        assert_eq!(find("abc123"), Some((2, "abc123")));
+        assert_eq!(find("+1"), Some((3, "+")));
+        assert_eq!(find("?"), None);
+        assert_eq!(find(" if"), None);
+
+        assert!(Combined::new(&patterns[..1], None).is_none());
+        let with_backref = prepare(
+            vec![("a".into(), "a".into()), ("b".into(), r"(b)\1".into())],
+            None,
+        )
+        .unwrap();
+        assert!(Combined::new(&with_backref, None).is_none());
+
+        // other named groups are fine, only ones starting with the prefix would clash
+        let with_groups = prepare(
+            vec![
+                ("p".into(), "p+".into()),
+                ("pp".into(), r"(?P<p>p)(?P<pp>p)".into()),
+            ],
+            None,
+        )
+        .unwrap();
+        assert!(Combined::new(&with_groups, None).is_some());
+        let clashing = prepare(
+            vec![
+                ("a".into(), "a".into()),
+                ("b".into(), r"(?P<__crossandra_0>b)".into()),
+            ],
+            None,
+        )
+        .unwrap();
+        assert!(Combined::new(&clashing, None).is_none());
+    }
+
+    #[test]
+    fn backrefs() {
+        for pattern in [
+            r"(a)\1",
+            r"(?P<x>a)(?P=x)",
+            r"(?<x>a)\k<x>",
+            r"(a)?(?(1)b|c)",
+        ] {
+            assert!(has_backref(pattern), "{pattern:?}");
+        }
+        for pattern in [r"\d+", r"(?P<x>a)+", r"\\d", r"[\\]1", r"a(?=b)"] {
+            assert!(!has_backref(pattern), "{pattern:?}");
+        }
+    }
+
+    #[test]
+    fn preceding_text() {
+        for pattern in [
+            r"(?<!a)b", r"(?<=a)b", r"\bif", r"a\Bb", r"\Ax", r"a\Kb", r"\<x\>",
+        ] {
+            assert!(depends_on_preceding(pattern), "{pattern:?}");
+        }
+        for pattern in [r"\d+", r"(?<x>a)", r"a(?=b)", r"a(?!b)$", r"\\b"] {
+            assert!(!depends_on_preceding(pattern), "{pattern:?}");
+        }
+    }
 }
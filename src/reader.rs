@@ -0,0 +1,348 @@
+use std::{
+    borrow::Cow,
+    io::{self, BufRead},
+};
+
+use crate::{
+    stream::{Core, Ignored, EOF},
+    Error, OwnedToken, Token, Tokenizer,
+};
+
+/// The minimum number of bytes to have buffered before trying to match a token.
+const LOOKAHEAD: usize = 8 * 1024;
+/// The default maximum number of bytes a single token can take up.
+const DEFAULT_MAX_BUFFER_SIZE: usize = 16 * 1024 * 1024;
+
+/// An [`Iterator`] of [`OwnedToken`]s, incrementally reading the source from a [`BufRead`].
+///
+/// Created by [`Tokenizer::tokenize_reader`].
+pub struct ReaderTokens<'a, R> {
+    tokenizer: &'a Tokenizer<'a>,
+    reader: R,
+    ignored: Ignored,
+    /// The decoded part of the source that has been read so far (minus the compacted prefix).
+    buffer: String,
+    /// Bytes read from the reader that don't form a complete UTF-8 character yet.
+    pending: Vec<u8>,
+    /// The position of the first unconsumed byte in `buffer`.
+    start: usize,
+    /// The position of the beginning of `buffer` in the whole source.
+    offset: usize,
+    max_buffer_size: usize,
+    eof: bool,
+    /// Whether an error has been yielded, which suppresses the end-of-input token.
+    failed: bool,
+    done: bool,
+}
+
+impl<'a, R: BufRead> ReaderTokens<'a, R> {
+    pub(crate) fn new(tokenizer: &'a Tokenizer<'a>, reader: R, ignored: Ignored) -> Self {
+        Self {
+            tokenizer,
+            reader,
+            ignored,
+            buffer: String::new(),
+            pending: Vec::new(),
+            start: 0,
+            offset: 0,
+            max_buffer_size: DEFAULT_MAX_BUFFER_SIZE,
+            eof: false,
+            failed: false,
+            done: false,
+        }
+    }
+
+    /// Sets the maximum number of bytes a single token (along with the ignored characters
+    /// preceding it) can take up and returns itself. If a token doesn't fit, an
+    /// [`Error::BufferOverflow`] is returned and the iteration stops.
+    ///
+    /// Defaults to 16 MiB.
+    #[must_use]
+    pub fn with_max_buffer_size(mut self, max_buffer_size: usize) -> Self {
+        self.max_buffer_size = max_buffer_size;
+        self
+    }
+
+    /// Reads the next chunk from the reader into the buffer, dropping the consumed part.
+    fn fill(&mut self) -> Result<(), Error> {
+        self.buffer.drain(..self.start);
+        self.offset += self.start;
+        self.start = 0;
+
+        let chunk = loop {
+            match self.reader.fill_buf() {
+                Ok(chunk) => break chunk,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => return Err(Error::Io(err)),
+            }
+        };
+
+        if chunk.is_empty() {
+            self.eof = true;
+            return if self.pending.is_empty() {
+                Ok(())
+            } else {
+                Err(invalid_utf8())
+            };
+        }
+
+        let length = chunk.len();
+        self.pending.extend_from_slice(chunk);
+        self.reader.consume(length);
+
+        // a character might be split between chunks, keep its beginning for the next read
+        let valid = match std::str::from_utf8(&self.pending) {
+            Ok(decoded) => decoded.len(),
+            Err(err) if err.error_len().is_none() => err.valid_up_to(),
+            Err(_) => return Err(invalid_utf8()),
+        };
+        let decoded = std::str::from_utf8(&self.pending[..valid]).map_err(|_| invalid_utf8())?;
+        self.buffer.push_str(decoded);
+        self.pending.drain(..valid);
+
+        Ok(())
+    }
+
+    fn fail(&mut self, err: Error) -> Option<Result<OwnedToken, Error>> {
+        self.done = true;
+        Some(Err(err))
+    }
+}
+
+impl<R: BufRead> Iterator for ReaderTokens<'_, R> {
+    type Item = Result<OwnedToken, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let lookahead = LOOKAHEAD.min(self.max_buffer_size);
+        loop {
+            let remaining = self.buffer.len() - self.start;
+            if !self.eof && remaining < lookahead {
+                if let Err(err) = self.fill() {
+                    return self.fail(err);
+                }
+                continue;
+            }
+
+            let mut core = Core::new(
+                self.tokenizer,
+                &self.buffer[self.start..],
+                Cow::Borrowed(&self.ignored),
+            );
+            let result = core.next();
+            let consumed = core.consumed();
+            let trailing_skip = core.trailing_skip();
+            let base = self.offset + self.start;
+
+            let Some(result) = result else {
+                // only ignored characters are left in the buffer
+                if self.eof {
+                    self.done = true;
+                    return (self.tokenizer.emit_eof && !self.failed).then(|| {
+                        Ok(Token::from((EOF, "", self.offset + self.buffer.len())).into_owned())
+                    });
+                }
+                match trailing_skip {
+                    // the skip pattern might continue past the end of the buffer, match it again
+                    Some(position) => {
+                        self.start += position;
+                        if self.buffer.len() - self.start >= self.max_buffer_size {
+                            return self.fail(Error::BufferOverflow {
+                                position: self.offset + self.start,
+                                limit: self.max_buffer_size,
+                            });
+                        }
+                        if let Err(err) = self.fill() {
+                            return self.fail(err);
+                        }
+                    }
+                    None => self.start = self.buffer.len(),
+                }
+                continue;
+            };
+
+            // the match might continue past the end of the buffer (or only seem unterminated
+            // because of it), read more and try again
+            let incomplete = !self.eof
+                && (consumed == remaining || matches!(result, Err(Error::Unterminated { .. })));
+            if consumed > self.max_buffer_size || incomplete && remaining >= self.max_buffer_size {
+                let position = match &result {
+                    Ok(token) => token.position,
+                    Err(err) => err.position().unwrap_or(0),
+                };
+                return self.fail(Error::BufferOverflow {
+                    position: base + position,
+                    limit: self.max_buffer_size,
+                });
+            }
+            if incomplete {
+                if let Err(err) = self.fill() {
+                    return self.fail(err);
+                }
+                continue;
+            }
+
+            let result = match result {
+                Ok(token) => Ok(OwnedToken {
+                    position: base + token.position,
+                    ..Token::into_owned(token)
+                }),
+                Err(mut err) => {
+                    err.shift(base);
+                    self.failed = true;
+                    Err(err)
+                }
+            };
+            self.start += consumed;
+            return Some(result);
+        }
+    }
+}
+
+fn invalid_utf8() -> Error {
+    Error::Io(io::Error::new(
+        io::ErrorKind::InvalidData,
+        "stream did not contain valid UTF-8",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufReader, Cursor};
+
+    use super::LOOKAHEAD;
+    use crate::{common, Error, OwnedToken, Tokenizer};
+
+    fn prepare_tokenizer<'a>() -> Tokenizer<'a> {
+        Tokenizer::default()
+            .with_literals(&[("lt", "<"), ("le", "<="), ("arrow", "<==>")])
+            .unwrap()
+            .with_patterns(vec![
+                common::STRING.clone(),
+                common::C_NAME.clone(),
+                common::UNSIGNED_INT.clone(),
+            ])
+            .unwrap()
+            .with_ignore_whitespace(true)
+    }
+
+    fn stringify(results: impl Iterator<Item = Result<OwnedToken, Error>>) -> Vec<String> {
+        results
+            .map(|result| match result {
+                Ok(token) => format!("{token:?}"),
+                Err(err) => err.to_string(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn matches_tokenize() {
+        let tok = prepare_tokenizer();
+        let source = "x <= 'łódź' <==> 12_3 <== @ \"a\\\"b\" é\n".repeat(300);
+        assert!(source.len() > LOOKAHEAD);
+
+        let expected = stringify(tok.tokenize_owned(&source));
+        // small odd-sized chunks split both tokens and multi-byte characters
+        for capacity in [1, 7, 4096] {
+            let reader = BufReader::with_capacity(capacity, source.as_bytes());
+            assert_eq!(stringify(tok.tokenize_reader(reader)), expected);
+        }
+    }
+
+    #[test]
+    fn long_tokens() {
+        let tok = prepare_tokenizer();
+        let string = format!("'{}'", "a".repeat(3 * LOOKAHEAD));
+        let source = format!("x {string} y");
+
+        let tokens: Vec<_> = tok
+            .tokenize_reader(Cursor::new(&source))
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(tokens.len(), 3);
+        assert_eq!(tokens[1].value, string);
+        assert_eq!(tokens[2].position, string.len() + 3);
+
+        for reader in [
+            BufReader::new(Cursor::new(&source)),
+            BufReader::with_capacity(100, Cursor::new(&source)),
+        ] {
+            let results: Vec<_> = tok
+                .tokenize_reader(reader)
+                .with_max_buffer_size(LOOKAHEAD)
+                .collect();
+            assert!(matches!(
+                &results[..],
+                [
+                    Ok(_),
+                    Err(Error::BufferOverflow {
+                        position: 2,
+                        limit: LOOKAHEAD
+                    })
+                ]
+            ));
+        }
+    }
+
+    #[test]
+    fn skip_patterns() {
+        let tok = prepare_tokenizer()
+            .with_skip_patterns(&[("comment", r"//[^\n]*"), ("block", r"/\*(?s:.)*?\*/")])
+            .unwrap();
+        // the skipped runs are longer than the lookahead, so they get split between reads
+        let comments = "// <= 'a' //\n/* y\n 'b' */".repeat(400);
+        assert!(comments.len() > LOOKAHEAD);
+        let source = format!("x {comments}\ny");
+
+        let expected = stringify(tok.tokenize_owned(&source));
+        assert_eq!(expected.len(), 2);
+        for capacity in [1, 7, 4096] {
+            let reader = BufReader::with_capacity(capacity, source.as_bytes());
+            assert_eq!(stringify(tok.tokenize_reader(reader)), expected);
+        }
+    }
+
+    #[test]
+    fn errors() {
+        let tok = prepare_tokenizer();
+
+        let results: Vec<_> = tok.tokenize_reader(Cursor::new("x 'abc")).collect();
+        assert!(matches!(
+            &results[..],
+            [Ok(_), Err(Error::Unterminated { name, start: 2, end: 3 }), ..] if name == "string"
+        ));
+
+        let results: Vec<_> = tok
+            .tokenize_reader(Cursor::new(b"x \xff y".as_slice()))
+            .collect();
+        assert!(matches!(
+            &results[..],
+            [Err(Error::Io(err))] if err.kind() == std::io::ErrorKind::InvalidData
+        ));
+
+        let results: Vec<_> = tok
+            .tokenize_reader(Cursor::new(b"x \xc3".as_slice()))
+            .collect();
+        assert!(matches!(&results[..], [Err(Error::Io(_))]));
+
+        assert_eq!(tok.tokenize_reader(Cursor::new("  \n ")).count(), 0);
+    }
+
+    #[test]
+    fn eof() {
+        let tok = prepare_tokenizer().with_emit_eof(true);
+        let source = "x <= 'a' ";
+        let expected = stringify(tok.tokenize_owned(source));
+        assert!(expected.last().unwrap().contains("\"eof\""));
+        assert_eq!(
+            stringify(tok.tokenize_reader(Cursor::new(source))),
+            expected
+        );
+
+        let results: Vec<_> = tok.tokenize_reader(Cursor::new("x @ y")).collect();
+        assert!(matches!(&results[..], [Ok(_), Err(_), Ok(_)]));
+    }
+}
@@ -0,0 +1,113 @@
+use std::iter::FusedIterator;
+
+use crate::{Error, Token, Tokenizer};
+
+/// An [`Iterator`] of [`Token`]s that stops at the first [`Error`] and keeps track of the part of
+/// the source that hasn't been consumed yet.
+///
+/// Created by [`Tokenizer::tokenize_with_remainder`].
+pub struct TokensWithRemainder<'a> {
+    source: &'a str,
+    tokens: Box<dyn FusedIterator<Item = Result<Token<'a>, Error>> + 'a>,
+    /// The position of the first unconsumed byte of the source.
+    position: usize,
+    done: bool,
+}
+
+impl<'a> TokensWithRemainder<'a> {
+    pub(crate) fn new(tokenizer: &'a Tokenizer<'a>, source: &'a str) -> Self {
+        Self {
+            source,
+            tokens: tokenizer.tokenize(source),
+            position: 0,
+            done: false,
+        }
+    }
+
+    /// Returns the part of the source that hasn't been consumed yet, as a slice of the original
+    /// source.
+    ///
+    /// That is the rest of the source after the last yielded [`Token`], or, once the iteration is
+    /// over, the rest of the source starting at the position of the [`Error`] (or an empty slice if
+    /// the whole source was tokenized).
+    #[must_use]
+    pub fn remaining(&self) -> &'a str {
+        &self.source[self.position..]
+    }
+
+    /// Returns the position of the first unconsumed byte of the source, i.e. the position of
+    /// [`remaining`](TokensWithRemainder::remaining) in the source.
+    #[must_use]
+    pub fn position(&self) -> usize {
+        self.position
+    }
+}
+
+impl<'a> Iterator for TokensWithRemainder<'a> {
+    type Item = Result<Token<'a>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        match self.tokens.next() {
+            Some(Ok(token)) => {
+                self.position = token.position + token.value.len();
+                Some(Ok(token))
+            }
+            Some(Err(err)) => {
+                self.done = true;
+                if let Some(position) = err.position() {
+                    self.position = position;
+                }
+                Some(Err(err))
+            }
+            None => {
+                self.done = true;
+                self.position = self.source.len();
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{common, Error, Token, Tokenizer};
+
+    #[test]
+    fn remainder() {
+        let fast = Tokenizer::default()
+            .with_literals(&[("a", "a"), ("b", "b")])
+            .unwrap()
+            .with_ignore_whitespace(true);
+        let core = fast
+            .clone()
+            .with_patterns(vec![common::STRING.clone()])
+            .unwrap();
+
+        for tok in [&fast, &core] {
+            let source = "a b ?ab";
+            let mut tokens = tok.tokenize_with_remainder(source);
+            assert_eq!(tokens.remaining(), source);
+            assert_eq!(tokens.next().unwrap().unwrap(), Token::from(("a", "a", 0)));
+            assert_eq!(tokens.remaining(), " b ?ab");
+            assert!(tokens.next().unwrap().is_ok());
+            assert!(matches!(tokens.next(), Some(Err(Error::BadToken('?', 4)))));
+            assert!(tokens.next().is_none());
+            assert_eq!(tokens.remaining(), "?ab");
+            assert_eq!(tokens.position(), 4);
+            // the remainder is a slice of the source
+            assert!(std::ptr::eq(tokens.remaining(), &source[4..]));
+
+            let mut tokens = tok.tokenize_with_remainder("a b ");
+            assert_eq!(tokens.by_ref().count(), 2);
+            assert_eq!(tokens.remaining(), "");
+        }
+
+        let mut tokens = core.tokenize_with_remainder("a 'b");
+        assert_eq!(tokens.by_ref().count(), 2);
+        assert_eq!(tokens.remaining(), "'b");
+    }
+}
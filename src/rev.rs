@@ -0,0 +1,68 @@
+use crate::{stream::Ignored, tree::LiteralTree, Error, Token, Tokenizer};
+
+/// Matches the literals of a [`Tokenizer`] from the end of the source towards its start.
+///
+/// Works on a copy of the source with the characters in reverse order and a tree of the reversed
+/// literals. Reversing characters keeps their byte lengths, so a position `i` in the reversed
+/// source corresponds to the position `source.len() - i` in the original one.
+pub(crate) struct Reverse<'a> {
+    tokenizer: &'a Tokenizer<'a>,
+    source: &'a str,
+    reversed: String,
+    tree: LiteralTree<'a>,
+    ignored: Ignored,
+    position: usize,
+}
+
+impl<'a> Reverse<'a> {
+    pub fn new(tok: &'a Tokenizer<'a>, source: &'a str, ignored: Ignored) -> Self {
+        let mut tree = LiteralTree::default();
+        for (literal, &name) in tok.tree.entries() {
+            tree.insert(&literal.chars().rev().collect::<String>(), name);
+        }
+        Self {
+            tokenizer: tok,
+            source,
+            reversed: source.chars().rev().collect(),
+            tree,
+            ignored,
+            position: 0,
+        }
+    }
+}
+
+impl<'a> Iterator for Reverse<'a> {
+    type Item = Result<Token<'a>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let remaining = &self.reversed[self.position..];
+        let (index, char) = self.ignored.find_token_start(remaining)?;
+        self.position += index;
+        let end = self.source.len() - self.position;
+
+        let Some((value, &name)) = self.tree.match_longest_prefix(&remaining[index..]) else {
+            self.position += char.len_utf8();
+            return Some(Err(Error::BadToken(char, end - char.len_utf8())));
+        };
+        let size = value.len();
+        self.position += size;
+
+        if self
+            .tokenizer
+            .max_token_length
+            .is_some_and(|max| size > max)
+        {
+            return Some(Err(Error::TokenTooLong {
+                name: name.into(),
+                length: size,
+                position: end - size,
+            }));
+        }
+        Some(Ok(Token {
+            name,
+            value: &self.source[end - size..end],
+            position: end - size,
+            captures: Vec::new(),
+        }))
+    }
+}
@@ -0,0 +1,143 @@
+use std::ops::Range;
+
+use crate::{
+    stream::{Core, EOF},
+    Error, Token,
+};
+
+/// Why a part of the source was skipped, as reported by
+/// [`Tokenizer::tokenize_with_skipped`](crate::Tokenizer::tokenize_with_skipped).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SkipReason<'a> {
+    /// A run of [ignored characters](crate::Tokenizer#ignored_characters) (including whitespace,
+    /// if [ignored](crate::Tokenizer#ignore_whitespace)).
+    Ignored,
+    /// A match of the [skip pattern](crate::Tokenizer#skip_patterns) of the given name.
+    SkipPattern(&'a str),
+}
+
+/// Either a [`Token`] or a skipped part of the source, as yielded by
+/// [`Tokenizer::tokenize_with_skipped`](crate::Tokenizer::tokenize_with_skipped).
+#[derive(Debug, PartialEq, Eq)]
+pub enum TokenOrSkipped<'a> {
+    Token(Token<'a>),
+    /// A part of the source that didn't produce any token, as a byte range consistent with
+    /// [`Token::position`].
+    Skipped {
+        span: Range<usize>,
+        reason: SkipReason<'a>,
+    },
+}
+
+/// Yields the spans skipped by the [`Core`] before each token, followed by the token itself.
+pub(crate) struct WithSkipped<'a> {
+    core: Core<'a>,
+    /// The position of the [`EOF`] token, if it should be emitted.
+    eof: Option<usize>,
+    pending: Option<Result<Token<'a>, Error>>,
+    done: bool,
+}
+
+impl<'a> WithSkipped<'a> {
+    pub fn new(core: Core<'a>, eof: Option<usize>) -> Self {
+        Self {
+            core: core.with_skipped_spans(),
+            eof,
+            pending: None,
+            done: false,
+        }
+    }
+}
+
+impl<'a> Iterator for WithSkipped<'a> {
+    type Item = Result<TokenOrSkipped<'a>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.pending.is_none() && !self.done {
+            self.pending = self.core.next();
+            self.done = self.pending.is_none();
+            if matches!(self.pending, Some(Err(_))) {
+                self.eof = None;
+            }
+        }
+        if let Some((span, reason)) = self.core.next_skipped() {
+            return Some(Ok(TokenOrSkipped::Skipped { span, reason }));
+        }
+        if let Some(result) = self.pending.take() {
+            return Some(result.map(TokenOrSkipped::Token));
+        }
+        let position = self.eof.take()?;
+        Some(Ok(TokenOrSkipped::Token(Token::from((EOF, "", position)))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{common, Error, SkipReason, TokenOrSkipped, Tokenizer};
+
+    #[test]
+    fn skipped_spans() {
+        let tok = Tokenizer::default()
+            .with_patterns(vec![common::WORD.clone()])
+            .unwrap()
+            .with_skip_patterns(&[("comment", "/\\*.*?\\*/")])
+            .unwrap()
+            .with_ignore_whitespace(true)
+            .with_emit_eof(true);
+
+        // the tokens and skipped spans cover the whole source, in order
+        let source = "  a /*x*/ b/**/\n";
+        let mut end = 0;
+        for item in tok.tokenize_with_skipped(source) {
+            let span = match item.unwrap() {
+                TokenOrSkipped::Token(token) => token.position..token.position + token.value.len(),
+                TokenOrSkipped::Skipped { span, .. } => span,
+            };
+            assert_eq!(span.start, end);
+            end = span.end;
+        }
+        assert_eq!(end, source.len());
+
+        let items: Vec<_> = tok
+            .tokenize_with_skipped("a/**/ ")
+            .map(Result::unwrap)
+            .map(|item| match item {
+                TokenOrSkipped::Token(token) => (
+                    token.name,
+                    token.position..token.position + token.value.len(),
+                ),
+                TokenOrSkipped::Skipped { span, reason } => match reason {
+                    SkipReason::Ignored => ("ignored", span),
+                    SkipReason::SkipPattern(name) => (name, span),
+                },
+            })
+            .collect();
+        assert_eq!(
+            items,
+            [
+                ("word", 0..1),
+                ("comment", 1..5),
+                ("ignored", 5..6),
+                ("eof", 6..6),
+            ]
+        );
+
+        // no end-of-input token after an error
+        let items: Vec<_> = tok.tokenize_with_skipped("a ? ").collect();
+        assert!(matches!(
+            items.as_slice(),
+            [
+                Ok(TokenOrSkipped::Token(_)),
+                Ok(TokenOrSkipped::Skipped {
+                    span: _,
+                    reason: SkipReason::Ignored
+                }),
+                Err(Error::BadToken('?', 2)),
+                Ok(TokenOrSkipped::Skipped {
+                    span: _,
+                    reason: SkipReason::Ignored
+                }),
+            ]
+        ));
+    }
+}
@@ -0,0 +1,89 @@
+use crate::{Error, Token};
+
+/// An [`Iterator`] grouping [`Token`]s into [`Vec`]s delimited by tokens of a given name (e.g.
+/// statements delimited by `;`), with the delimiters left out.
+///
+/// Created by [`TokenStream::split_on`](crate::TokenStream::split_on). Like
+/// [`str::split_terminator`], a group is yielded between every two delimiters (even if empty),
+/// except for an empty group after the last delimiter. An [`Error`] ends the current group, which
+/// is yielded (unless empty) before the error itself.
+pub struct SplitOn<'n, I> {
+    tokens: I,
+    name: &'n str,
+    error: Option<Error>,
+}
+
+impl<'n, I> SplitOn<'n, I> {
+    pub(crate) fn new(tokens: I, name: &'n str) -> Self {
+        Self {
+            tokens,
+            name,
+            error: None,
+        }
+    }
+}
+
+impl<'a, I: Iterator<Item = Result<Token<'a>, Error>>> Iterator for SplitOn<'_, I> {
+    type Item = Result<Vec<Token<'a>>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(err) = self.error.take() {
+            return Some(Err(err));
+        }
+
+        let mut group = Vec::new();
+        loop {
+            match self.tokens.next() {
+                Some(Ok(token)) if token.name == self.name => return Some(Ok(group)),
+                Some(Ok(token)) => group.push(token),
+                Some(Err(err)) if group.is_empty() => return Some(Err(err)),
+                Some(Err(err)) => {
+                    self.error = Some(err);
+                    return Some(Ok(group));
+                }
+                None => return (!group.is_empty()).then_some(Ok(group)),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Error, Tokenizer};
+
+    #[test]
+    fn statements() {
+        let tok = Tokenizer::default()
+            .with_literals(&[("semicolon", ";")])
+            .unwrap()
+            .with_patterns(vec![("word".into(), r"\w+".into())])
+            .unwrap()
+            .with_ignore_whitespace(true);
+        let values = |source| {
+            tok.token_stream(source)
+                .split_on("semicolon")
+                .map(|group| {
+                    group.map(|tokens| tokens.into_iter().map(|t| t.value).collect::<Vec<_>>())
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let groups = values("let x; print x;; end");
+        assert_eq!(groups.len(), 4);
+        assert_eq!(groups[0].as_ref().unwrap(), &["let", "x"]);
+        assert_eq!(groups[1].as_ref().unwrap(), &["print", "x"]);
+        assert!(groups[2].as_ref().unwrap().is_empty());
+        assert_eq!(groups[3].as_ref().unwrap(), &["end"]);
+
+        assert_eq!(values("a;").len(), 1);
+        assert!(values("").is_empty());
+        assert!(values(" ").is_empty());
+
+        let groups = values("a ? b; ?");
+        assert!(matches!(
+            &groups[..],
+            [Ok(a), Err(Error::BadToken('?', 2)), Ok(b), Err(Error::BadToken('?', 7))]
+                if a == &["a"] && b == &["b"]
+        ));
+    }
+}
@@ -0,0 +1,52 @@
+use crate::Token;
+
+/// A point in the source at which tokenization can be resumed using
+/// [`Tokenizer::tokenize_from`](crate::Tokenizer::tokenize_from), e.g. to only retokenize the part
+/// of a buffer after an edit.
+///
+/// Every match only depends on the source from its start onward, so resuming after any token
+/// yields exactly the same tokens as a full scan would, as long as the source before the state is
+/// unchanged. The byte position is therefore all the state there is.
+///
+/// # Examples
+/// ```rust
+/// # use crossandra::{common, Tokenizer, TokenizerState};
+/// let tok = Tokenizer::default()
+///     .with_patterns(vec![common::WORD.clone()])
+///     .unwrap()
+///     .with_ignore_whitespace(true);
+/// let tokens = tok.try_tokenize("one two three").unwrap();
+///
+/// let state = TokenizerState::after(&tokens[0]);
+/// let edited = "one two four";
+/// let resumed: Vec<_> = tok
+///     .tokenize_from(edited, state)
+///     .map(|token| token.unwrap().value)
+///     .collect();
+/// assert_eq!(resumed, ["two", "four"]);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct TokenizerState {
+    position: usize,
+}
+
+impl TokenizerState {
+    /// Creates a state at the given byte position, which should be at the end of a token (or at
+    /// the start of the source).
+    #[must_use]
+    pub fn at(position: usize) -> Self {
+        Self { position }
+    }
+
+    /// Creates a state right after the given [`Token`].
+    #[must_use]
+    pub fn after(token: &Token<'_>) -> Self {
+        Self::at(token.position + token.value.len())
+    }
+
+    /// Returns the byte position of the state in the source.
+    #[must_use]
+    pub fn position(&self) -> usize {
+        self.position
+    }
+}
@@ -1,13 +1,28 @@
-use std::str::CharIndices;
+use std::{borrow::Cow, collections::VecDeque, iter::FusedIterator, ops::Range, str::CharIndices};
 
+use fancy_regex::Regex;
 use rustc_hash::{FxHashMap, FxHashSet};
 
-use crate::{error::Error, tree::Tree, Token, Tokenizer};
+use crate::{error::Error, patterns, SkipReason, Token, Tokenizer};
 
 pub(crate) fn build_hashmap<'a>(hm: &[(&'a str, &'a str)]) -> FxHashMap<&'a str, &'a str> {
     hm.iter().map(|(k, v)| (*v, *k)).collect()
 }
 
+/// Returns the offset of the first backslash in the value not followed by one of the allowed
+/// escape sequences.
+fn find_invalid_escape(value: &str, escapes: &Regex) -> Option<usize> {
+    let mut offset = 0;
+    while let Some(index) = value[offset..].find('\\') {
+        let backslash = offset + index;
+        match escapes.find(&value[backslash + 1..]) {
+            Ok(Some(escape)) if escape.end() > 0 => offset = backslash + 1 + escape.end(),
+            _ => return Some(backslash),
+        }
+    }
+    None
+}
+
 fn prepare_literal_map<'a>(tok: &'a Tokenizer) -> FxHashMap<char, &'a str> {
     tok.literals
         .iter()
@@ -15,161 +30,824 @@ fn prepare_literal_map<'a>(tok: &'a Tokenizer) -> FxHashMap<char, &'a str> {
         .collect()
 }
 
+/// A set of ignored characters, with a lookup table for the ASCII ones.
+#[derive(Debug, Clone)]
+pub(crate) struct Ignored {
+    ascii: [bool; 128],
+    other: FxHashSet<char>,
+    whitespace: bool,
+}
+
+impl Ignored {
+    /// `whitespace` additionally ignores all non-ASCII whitespace, the ASCII one is expected to be
+    /// in `characters` already.
+    pub fn new(characters: FxHashSet<char>, whitespace: bool) -> Self {
+        let mut ascii = [false; 128];
+        for &c in &characters {
+            if c.is_ascii() {
+                ascii[c as usize] = true;
+            }
+        }
+        let other = characters.into_iter().filter(|c| !c.is_ascii()).collect();
+        Self {
+            ascii,
+            other,
+            whitespace,
+        }
+    }
+
+    pub fn contains(&self, c: char) -> bool {
+        if c.is_ascii() {
+            self.ascii[c as usize]
+        } else {
+            (self.whitespace && c.is_whitespace()) || self.other.contains(&c)
+        }
+    }
+
+    /// Returns the first character of the source that isn't ignored along with its position.
+    pub fn find_token_start(&self, source: &str) -> Option<(usize, char)> {
+        // skip runs of ignored ASCII characters bytewise, without decoding them
+        let mut index = 0;
+        loop {
+            index += source.as_bytes()[index..]
+                .iter()
+                .take_while(|&&b| b.is_ascii() && self.ascii[b as usize])
+                .count();
+            let c = source[index..].chars().next()?;
+            if !self.contains(c) {
+                return Some((index, c));
+            }
+            index += c.len_utf8();
+        }
+    }
+}
+
+fn mirror(c: char) -> char {
+    match c {
+        '(' => ')',
+        '[' => ']',
+        '{' => '}',
+        '<' => '>',
+        _ => c,
+    }
+}
+
+/// A matched token as its name, value, size in bytes and the regex that matched it (if any).
+type Match<'a> = (&'a str, &'a str, usize, Option<&'a Regex>);
+
+/// A token skipped by [`Core::advance`] as its name, position, size in bytes and the regex that
+/// matched it (if any).
+type Skipped<'a> = (&'a str, usize, usize, Option<&'a Regex>);
+
+/// Logs a matching decision at the trace level if the `log` feature is enabled, and compiles to
+/// nothing otherwise.
+macro_rules! trace {
+    ($($arg:tt)+) => {
+        #[cfg(feature = "log")]
+        log::trace!($($arg)+);
+    };
+}
+
+/// Returns whether the regex matches at the start of the `closed` source, using (a part of) the
+/// closing sequence appended after its first `len` bytes.
+fn matches_closed(regex: &Regex, closed: &str, len: usize) -> bool {
+    matches!(regex.find(closed), Ok(Some(tok)) if tok.start() == 0 && tok.end() > len)
+}
+
+/// Finds the line equal to the marker, starting with the line after the `start` position. Returns
+/// the position right after the marker.
+fn find_heredoc_end(source: &str, start: usize, marker: &str) -> Option<usize> {
+    let mut line_start = start + source[start..].find('\n')? + 1;
+    loop {
+        let line_end = source[line_start..]
+            .find('\n')
+            .map_or(source.len(), |index| line_start + index);
+        let line = &source[line_start..line_end];
+        if line.strip_suffix('\r').unwrap_or(line) == marker {
+            return Some(line_start + marker.len());
+        }
+        if line_end == source.len() {
+            return None;
+        }
+        line_start = line_end + 1;
+    }
+}
+
+/// Cuts the source right after the first character past the `max_token_length` (if any), which is
+/// all that's needed to tell whether a token starting there is too long.
+fn cut_to_limit(source: &str, max_token_length: Option<usize>) -> &str {
+    let Some(max) = max_token_length else {
+        return source;
+    };
+    let mut end = max.saturating_add(1);
+    while end < source.len() && !source.is_char_boundary(end) {
+        end += 1;
+    }
+    source.get(..end).unwrap_or(source)
+}
+
+/// Collects the named groups captured by the regex at the start of the source, looking at the same
+/// part of it as [`Core::match_token_within_limit`] did. Patterns without named groups aren't
+/// matched again.
+fn named_captures<'a>(
+    regex: &'a Regex,
+    source: &'a str,
+    max_token_length: Option<usize>,
+) -> Vec<(&'a str, &'a str)> {
+    if regex.capture_names().all(|name| name.is_none()) {
+        return Vec::new();
+    }
+    let Some(captures) = [cut_to_limit(source, max_token_length), source]
+        .into_iter()
+        .find_map(|source| regex.captures(source).ok().flatten())
+    else {
+        return Vec::new();
+    };
+    regex
+        .capture_names()
+        .flatten()
+        .filter_map(|name| Some((name, captures.name(name)?.as_str())))
+        .collect()
+}
+
 pub(crate) struct Core<'a> {
     tokenizer: &'a Tokenizer<'a>,
-    chunk_size: usize,
+    source: &'a str,
     remaining_source: &'a str,
-    ignored: FxHashSet<char>,
+    ignored: Cow<'a, Ignored>,
     position: usize,
+    /// The position of the last skip pattern match, if it reached the end of the source.
+    trailing_skip: Option<usize>,
+    /// The skipped spans not taken yet, if they are recorded at all.
+    skipped: Option<VecDeque<(Range<usize>, SkipReason<'a>)>>,
+    /// The source followed by each closing sequence tried by [`Core::find_unterminated`], built
+    /// once per closing sequence so that the remaining source is never copied.
+    closed_sources: Vec<(String, String)>,
 }
 
 impl<'a> Core<'a> {
-    pub fn new(tok: &'a Tokenizer<'a>, source: &'a str, ignored: FxHashSet<char>) -> Self {
+    pub fn new(tok: &'a Tokenizer<'a>, source: &'a str, ignored: Cow<'a, Ignored>) -> Self {
         Self {
             tokenizer: tok,
-            chunk_size: tok.literals.keys().map(|x| x.len()).max().unwrap_or(1),
+            source,
             remaining_source: source,
             ignored,
             position: 0,
+            trailing_skip: None,
+            skipped: None,
+            closed_sources: Vec::new(),
         }
     }
 
-    fn handle(
-        &self,
-        remaining_source: &'a str,
-        chunk_size: usize,
-    ) -> Result<(&'a str, &'a str, usize), char> {
-        let mut break_path = None;
-        let mut tree = &self.tokenizer.tree;
+    /// Returns the number of bytes of the source consumed so far, including ignored characters.
+    pub fn consumed(&self) -> usize {
+        self.position
+    }
 
-        for (i, v) in remaining_source.char_indices().take(chunk_size) {
-            let Tree::Node(ref node) = tree else { continue };
+    /// Returns the position of the last [skip pattern](crate::Tokenizer#skip_patterns) match if it
+    /// reached the end of the source, i.e. it might continue past it.
+    pub fn trailing_skip(&self) -> Option<usize> {
+        self.trailing_skip
+    }
 
-            if let Some(token) = node.get(&None) {
-                if let Tree::Leaf(token_name) = token {
-                    break_path = Some((token_name, i));
-                } else {
-                    unreachable!("key None can never lead to a Node")
-                }
-            };
+    /// Enables recording the skipped spans, see [`Core::next_skipped`].
+    pub fn with_skipped_spans(mut self) -> Self {
+        self.skipped = Some(VecDeque::new());
+        self
+    }
+
+    /// Takes the earliest recorded skipped span, along with the reason it was skipped. The spans
+    /// preceding a token are recorded by the time the token is yielded.
+    pub fn next_skipped(&mut self) -> Option<(Range<usize>, SkipReason<'a>)> {
+        self.skipped.as_mut()?.pop_front()
+    }
+
+    /// Records the next `size` bytes of the source as skipped, if recording is enabled.
+    fn record_skipped(&mut self, size: usize, reason: SkipReason<'a>) {
+        if let Some(skipped) = &mut self.skipped {
+            if size > 0 {
+                skipped.push_back((self.position..self.position + size, reason));
+            }
+        }
+    }
 
-            match node.get(&Some(v)) {
-                Some(Tree::Leaf(token_name)) => {
-                    let next_char_index = i + v.len_utf8();
-                    return Ok((
-                        token_name,
-                        (&remaining_source[..next_char_index]),
-                        next_char_index,
-                    ));
+    /// Matches the skip patterns against the remaining source, returning the name and size of the
+    /// first match in bytes.
+    fn match_skip_pattern(&self) -> Result<Option<(&'a str, usize)>, Error> {
+        for (name, pattern, _) in &self.tokenizer.skip_patterns {
+            match pattern.find(self.remaining_source) {
+                Ok(Some(tok)) if tok.start() == 0 && tok.end() > 0 => {
+                    trace!(
+                        "{}: skip pattern {name} matched {:?}",
+                        self.position,
+                        tok.as_str()
+                    );
+                    return Ok(Some((name, tok.end())));
+                }
+                Ok(_) => {}
+                Err(_) => {
+                    return Err(Error::Timeout {
+                        name: name.clone(),
+                        position: self.position,
+                    })
                 }
-                Some(new_tree) => tree = new_tree,
-                None => match node.get(&None) {
-                    None => break,
-                    Some(Tree::Leaf(token_name)) => {
-                        return Ok(((token_name), (&remaining_source[..i]), i));
+            }
+        }
+        Ok(None)
+    }
+
+    fn match_literal(&self) -> Option<Match<'a>> {
+        let (value, &name) = self
+            .tokenizer
+            .tree
+            .match_longest_prefix(self.remaining_source)?;
+        trace!("{}: literal {name} matched {value:?}", self.position);
+        Some((name, value, value.len(), None))
+    }
+
+    /// Matches all patterns against the remaining source, in their declaration order. A pattern
+    /// the regex engine gives up on yields an [`Error::Timeout`].
+    ///
+    /// Only matches at the very start count, as precompiled patterns might not be anchored.
+    fn match_patterns(&self) -> impl Iterator<Item = Result<Match<'a>, Error>> + '_ {
+        self.tokenizer
+            .patterns
+            .iter()
+            .filter_map(
+                |(name, pattern, _)| match pattern.find(self.remaining_source) {
+                    // empty matches would make no progress, so they are not considered a match
+                    Ok(Some(tok)) if tok.start() == 0 && tok.end() > 0 => {
+                        trace!(
+                            "{}: pattern {name} matched {:?}",
+                            self.position,
+                            tok.as_str()
+                        );
+                        Some(Ok((
+                            name.as_str(),
+                            tok.as_str(),
+                            tok.end(),
+                            Some(&**pattern),
+                        )))
+                    }
+                    Ok(_) => {
+                        trace!("{}: pattern {name} didn't match", self.position);
+                        None
+                    }
+                    Err(_) => {
+                        trace!("{}: pattern {name} timed out", self.position);
+                        Some(Err(Error::Timeout {
+                            name: name.clone(),
+                            position: self.position,
+                        }))
                     }
-                    _ => unreachable!("key None can never lead to a Node"),
                 },
+            )
+    }
+
+    /// Finds the first pattern matching the remaining source, using the combined regex if possible.
+    fn match_first_pattern(&self) -> Option<Result<Match<'a>, Error>> {
+        if let Some(combined) = &self.tokenizer.combined {
+            match combined.find(self.remaining_source) {
+                Ok(Some((index, tok))) if tok.start() == 0 && tok.end() > 0 => {
+                    let (name, pattern, _) = &self.tokenizer.patterns[index];
+                    trace!(
+                        "{}: pattern {name} matched {:?} (combined)",
+                        self.position,
+                        tok.as_str()
+                    );
+                    return Some(Ok((name, tok.as_str(), tok.end(), Some(&**pattern))));
+                }
+                // a later match means that no pattern matches at the start either
+                Ok(found) if found.as_ref().is_none_or(|(_, tok)| tok.start() > 0) => {
+                    trace!("{}: no pattern matched (combined)", self.position);
+                    return None;
+                }
+                // an empty match has to be skipped and errors have to be attributed to a pattern,
+                // retry the patterns one by one
+                _ => {}
             }
         }
+        self.match_patterns().next()
+    }
+
+    /// Matches all pattern functions against the remaining source, in the order they were added.
+    /// Empty matches and lengths not ending at a character boundary are not considered a match.
+    fn match_pattern_fns(&self) -> impl Iterator<Item = Match<'a>> + '_ {
+        let source = self.remaining_source;
+        self.tokenizer
+            .pattern_fns
+            .iter()
+            .filter_map(move |pattern| {
+                let size = (pattern.matcher)(source).filter(|&size| size > 0)?;
+                let value = source.get(..size)?;
+                trace!(
+                    "{}: pattern function {} matched {value:?}",
+                    self.position,
+                    pattern.name
+                );
+                Some((pattern.name.as_str(), value, size, None))
+            })
+    }
 
-        let chunk_length = remaining_source
-            .chars()
-            .take(chunk_size)
-            .map(char::len_utf8)
-            .sum();
+    /// Matches all balanced spans starting at the remaining source, in the order they were added.
+    fn match_balanced(&self) -> impl Iterator<Item = Match<'a>> + '_ {
+        let source = self.remaining_source;
+        self.tokenizer
+            .balanced
+            .iter()
+            .filter(|(_, open, _)| source.starts_with(*open))
+            .filter_map(move |(name, open, close)| {
+                let mut depth = 0_usize;
+                let (index, c) = source.char_indices().find(|&(_, c)| {
+                    if c == *close && depth == 1 {
+                        return true;
+                    }
+                    if c == *open {
+                        depth += 1;
+                    } else if c == *close {
+                        depth -= 1;
+                    }
+                    false
+                })?;
+                let size = index + c.len_utf8();
+                trace!(
+                    "{}: balanced span {name} matched {:?}",
+                    self.position,
+                    &source[..size]
+                );
+                Some((name.as_str(), &source[..size], size, None))
+            })
+    }
 
-        let chunk = &remaining_source[..chunk_length];
+    /// Matches all heredocs starting at the remaining source, in the order they were added. A
+    /// heredoc the regex engine gives up on yields an [`Error::Timeout`].
+    fn match_heredocs(&self) -> impl Iterator<Item = Result<Match<'a>, Error>> + '_ {
+        let source = self.remaining_source;
+        self.tokenizer
+            .heredocs
+            .iter()
+            .filter_map(move |(name, regex, _)| {
+                let captures = match regex.captures(source) {
+                    Ok(Some(captures)) => captures,
+                    Ok(None) => return None,
+                    Err(_) => {
+                        return Some(Err(Error::Timeout {
+                            name: name.clone(),
+                            position: self.position,
+                        }))
+                    }
+                };
+                let opener = captures.get(0).filter(|opener| opener.end() > 0)?;
+                let marker = captures.get(1).unwrap_or(opener).as_str();
+                let size = find_heredoc_end(source, opener.end(), marker)?;
+                trace!(
+                    "{}: heredoc {name} matched {:?}",
+                    self.position,
+                    &source[..size]
+                );
+                Some(Ok((name.as_str(), &source[..size], size, Some(&**regex))))
+            })
+    }
 
-        if let Tree::Node(ref node) = tree {
-            if let Some(t) = node.get(&None) {
-                if let Tree::Leaf(token_name) = t {
-                    break_path = Some((token_name, chunk_length));
-                } else {
-                    unreachable!("key None can never lead to a Node")
-                }
+    /// Finds the longest match among all literals, patterns, pattern functions, balanced spans and
+    /// heredocs, preferring the earlier ones on ties.
+    fn match_longest(&self) -> Option<Result<Match<'a>, Error>> {
+        let mut longest = self.match_literal();
+        for candidate in self.match_patterns() {
+            let candidate = match candidate {
+                Ok(candidate) => candidate,
+                Err(err) => return Some(Err(err)),
             };
+            if longest.is_none_or(|best| candidate.2 > best.2) {
+                longest = Some(candidate);
+            }
+        }
+        for candidate in self.match_pattern_fns().chain(self.match_balanced()) {
+            if longest.is_none_or(|best| candidate.2 > best.2) {
+                longest = Some(candidate);
+            }
+        }
+        for candidate in self.match_heredocs() {
+            let candidate = match candidate {
+                Ok(candidate) => candidate,
+                Err(err) => return Some(Err(err)),
+            };
+            if longest.is_none_or(|best| candidate.2 > best.2) {
+                longest = Some(candidate);
+            }
+        }
+        longest.map(Ok)
+    }
+
+    /// Finds the token at the start of the remaining source, following the
+    /// [`longest_match`](crate::Tokenizer#longest_match) setting.
+    fn match_token(&self) -> Option<Result<Match<'a>, Error>> {
+        if self.tokenizer.longest_match {
+            self.match_longest()
+        } else {
+            self.match_literal()
+                .map(Ok)
+                .or_else(|| self.match_first_pattern())
+                .or_else(|| self.match_pattern_fns().next().map(Ok))
+                .or_else(|| self.match_balanced().next().map(Ok))
+                .or_else(|| self.match_heredocs().next())
+        }
+    }
+
+    /// Finds the token at the start of the remaining source like [`Core::match_token`], but only
+    /// looks at the source up to the first character past the
+    /// [`max_token_length`](crate::Tokenizer#max_token_length), so that a too long match is cut
+    /// off there instead of possibly spanning the rest of the source. The whole remaining source
+    /// is only tried if nothing matches within that part.
+    fn match_token_within_limit(&mut self) -> Option<Result<Match<'a>, Error>> {
+        let remaining = self.remaining_source;
+        let window = cut_to_limit(remaining, self.tokenizer.max_token_length);
+        if window.len() == remaining.len() {
+            return self.match_token();
+        }
+
+        self.remaining_source = window;
+        let matched = self.match_token();
+        self.remaining_source = remaining;
+        matched.or_else(|| self.match_token())
+    }
+
+    /// Returns the name and value of the token matched at `offset` bytes into the source, without
+    /// consuming anything. Errors are treated as no match.
+    pub fn probe(&mut self, offset: usize) -> Option<(&'a str, &'a str)> {
+        self.remaining_source = self.source.get(offset..)?;
+        self.position = offset;
+        let (name, value, _, _) = self.match_token()?.ok()?;
+        Some((name, value))
+    }
 
-            match node.get(&None) {
-                None => {}
-                Some(Tree::Leaf(token_name)) => {
-                    return Ok(((token_name), (chunk), chunk_length));
+    /// Finds the first position in the source where a token matches, as if trying each character
+    /// boundary in turn like [`Core::probe`]. Errors are treated as no match.
+    ///
+    /// The searches only filter out positions where no rule can match: they look for the first
+    /// characters of literals and balanced spans, and for every pattern and heredoc with its
+    /// anchors removed. A search is only repeated once its previous result is passed, and each
+    /// candidate is still matched as usual. Pattern functions can't be searched for, so with any
+    /// of them every position is a candidate, as it is for a pattern whose matches depend on the
+    /// preceding text, or once the regex engine gives up on searching for it.
+    pub fn find_first(&mut self) -> Option<Token<'a>> {
+        let source = self.source;
+        let tok = self.tokenizer;
+        let first_chars: FxHashSet<char> = tok
+            .literals
+            .keys()
+            .filter_map(|literal| literal.chars().next())
+            .chain(tok.balanced.iter().map(|&(_, open, _)| open))
+            .collect();
+        let find_char = |offset: usize| {
+            source[offset..]
+                .char_indices()
+                .find(|(_, c)| first_chars.contains(c))
+                .map(|(index, _)| offset + index)
+        };
+        let search = |regex: &mut Option<Regex>, offset: usize| {
+            match regex.as_ref()?.find_from_pos(source, offset) {
+                Ok(found) => Some(found.map(|found| found.start())),
+                // the regex engine gave up, so don't search with it again
+                Err(_) => {
+                    *regex = None;
+                    None
                 }
-                _ => unreachable!("key None can never lead to a Node"),
             }
+        };
+
+        let mut regexes: Vec<_> = tok
+            .patterns
+            .iter()
+            .chain(&tok.heredocs)
+            .map(|pattern| patterns::unanchor(pattern, tok.backtrack_limit))
+            .collect();
+        // the next candidate of each search, `None` once there are no more, every position is a
+        // candidate for the patterns that can't be searched for
+        let mut next_matches: Vec<_> = regexes
+            .iter_mut()
+            .map(|regex| search(regex, 0).unwrap_or(Some(0)))
+            .collect();
+        let mut next_char = find_char(0);
+
+        let mut offset = 0;
+        while offset < source.len() {
+            for (regex, next) in regexes.iter_mut().zip(&mut next_matches) {
+                if next.is_some_and(|next| next < offset) {
+                    *next = search(regex, offset).unwrap_or(Some(offset));
+                }
+            }
+            if next_char.is_some_and(|next| next < offset) {
+                next_char = find_char(offset);
+            }
+
+            let candidate = if tok.pattern_fns.is_empty() {
+                next_matches
+                    .iter()
+                    .flatten()
+                    .copied()
+                    .chain(next_char)
+                    .min()?
+            } else {
+                offset
+            };
+            self.remaining_source = source.get(candidate..)?;
+            self.position = candidate;
+            if let Some(Ok((name, value, _, regex))) = self.match_token() {
+                return Some(Token {
+                    name,
+                    value,
+                    position: candidate,
+                    captures: regex.map_or_else(Vec::new, |regex| {
+                        named_captures(regex, &source[candidate..], None)
+                    }),
+                });
+            }
+            offset = candidate
+                + self
+                    .remaining_source
+                    .chars()
+                    .next()
+                    .map_or(1, char::len_utf8);
         }
+        None
+    }
 
-        if let Some((s, len)) = break_path {
-            let bytes = remaining_source.chars().take(len).map(char::len_utf8).sum();
-            return Ok(((s), (&remaining_source[..bytes]), len));
+    /// Finds a pattern that doesn't match the remaining source, but would if the source was
+    /// terminated by a sequence closing its beginning (e.g. `"` for `"`, `*/` for `/*`, or `)` for
+    /// `(`). Returns the name of the pattern and the length of the opening sequence in bytes.
+    ///
+    /// Only the patterns matching the bare opening sequence once it's closed (e.g. `""` or `/**/`)
+    /// are tried on the whole remaining source, so that a position that can't start any pattern
+    /// (e.g. in a run of untokenizable characters) costs only a few matches on short strings.
+    ///
+    /// A balanced span or a heredoc starting at the remaining source is always unterminated, as it
+    /// would have matched otherwise.
+    fn find_unterminated(&mut self) -> Option<(&'a str, usize)> {
+        let tokenizer = self.tokenizer;
+        let remaining = self.remaining_source;
+        if let Some((name, open, _)) = tokenizer
+            .balanced
+            .iter()
+            .find(|(_, open, _)| remaining.starts_with(*open))
+        {
+            return Some((name, open.len_utf8()));
+        }
+        for (name, regex, _) in &tokenizer.heredocs {
+            if let Ok(Some(opener)) = regex.find(remaining) {
+                if opener.end() > 0 {
+                    return Some((name, opener.end()));
+                }
+            }
         }
 
-        if let Some(&name) = self.tokenizer.literals.get(chunk) {
-            Ok(((name), (chunk), chunk_length))
-        } else {
-            Err(remaining_source
-                .chars()
-                .next()
-                .expect("the chunk will never be empty"))
+        for (opener_end, c) in remaining.char_indices().take(3) {
+            let opener = &remaining[..opener_end + c.len_utf8()];
+            let closer: String = opener.chars().rev().map(mirror).collect();
+            let closed_opener = format!("{opener}{closer}");
+
+            let patterns = tokenizer.patterns.iter();
+            for (name, pattern, _) in patterns.chain(&tokenizer.skip_patterns) {
+                if !matches_closed(pattern, &closed_opener, opener.len())
+                    || !matches_closed(pattern, self.closed_remaining(&closer), remaining.len())
+                {
+                    continue;
+                }
+                return Some((name, opener.len()));
+            }
         }
+
+        None
     }
-}
 
-impl<'a> Iterator for Core<'a> {
-    type Item = Result<Token<'a>, Error>;
+    /// Returns the remaining source followed by the closing sequence.
+    fn closed_remaining(&mut self, closer: &str) -> &str {
+        let index = match self
+            .closed_sources
+            .iter()
+            .position(|(known, _)| known == closer)
+        {
+            Some(index) => index,
+            None => {
+                let closed = format!("{}{closer}", self.source);
+                self.closed_sources.push((closer.into(), closed));
+                self.closed_sources.len() - 1
+            }
+        };
+        let offset = self.source.len() - self.remaining_source.len();
+        &self.closed_sources[index].1[offset..]
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        let (index, _) = self
-            .remaining_source
-            .char_indices()
-            .find(|(_, c)| !self.ignored.contains(c))?;
+    /// Finds the first pattern (or pattern function) matching an empty string at the start of the
+    /// remaining source.
+    fn find_zero_width(&self) -> Option<&'a str> {
+        self.tokenizer
+            .patterns
+            .iter()
+            .find(|(_, pattern, _)| {
+                matches!(pattern.find(self.remaining_source), Ok(Some(tok)) if tok.end() == 0)
+            })
+            .map(|(name, _, _)| name.as_str())
+            .or_else(|| {
+                self.tokenizer
+                    .pattern_fns
+                    .iter()
+                    .find(|pattern| (pattern.matcher)(self.remaining_source) == Some(0))
+                    .map(|pattern| pattern.name.as_str())
+            })
+    }
+}
+
+impl<'a> Core<'a> {
+    /// Moves past the next token without constructing it.
+    fn advance(&mut self) -> Option<Result<Skipped<'a>, Error>> {
+        let char = loop {
+            let Some((index, char)) = self.ignored.find_token_start(self.remaining_source) else {
+                self.record_skipped(self.remaining_source.len(), SkipReason::Ignored);
+                self.position += self.remaining_source.len();
+                self.remaining_source = "";
+                return None;
+            };
+            self.record_skipped(index, SkipReason::Ignored);
+            self.remaining_source = &self.remaining_source[index..];
+            self.position += index;
 
-        self.remaining_source = &self.remaining_source[index..];
-        self.position += index;
+            match self.match_skip_pattern() {
+                Ok(Some((name, size))) => {
+                    self.record_skipped(size, SkipReason::SkipPattern(name));
+                    self.remaining_source = &self.remaining_source[size..];
+                    self.trailing_skip = self.remaining_source.is_empty().then_some(self.position);
+                    self.position += size;
+                }
+                Ok(None) => break char,
+                Err(err) => {
+                    self.remaining_source = &self.remaining_source[char.len_utf8()..];
+                    self.position += char.len_utf8();
+                    return Some(Err(err));
+                }
+            }
+        };
         let start_position = self.position;
 
-        let handling_result = self.handle(self.remaining_source, self.chunk_size);
+        let matched = self.match_token_within_limit();
+
+        let char_bytes = char.len_utf8();
+        let matched = match matched.transpose() {
+            Ok(matched) => matched,
+            Err(err) => {
+                self.remaining_source = &self.remaining_source[char_bytes..];
+                self.position += char_bytes;
+                return Some(Err(err));
+            }
+        };
 
-        if let Ok((name, value, size)) = handling_result {
+        if let Some((name, value, size, regex)) = matched {
+            trace!("{start_position}: {name} won with {size} bytes");
             self.remaining_source = &self.remaining_source[size..];
             self.position += size;
-            return Some(Ok(Token {
-                name,
-                value,
-                position: start_position,
-            }));
+            if self
+                .tokenizer
+                .max_token_length
+                .is_some_and(|max| size > max)
+            {
+                return Some(Err(Error::TokenTooLong {
+                    name: name.into(),
+                    length: size,
+                    position: start_position,
+                }));
+            }
+            let invalid_escape = regex
+                .and(self.tokenizer.escapes.get(name))
+                .and_then(|escapes| find_invalid_escape(value, escapes));
+            if let Some(offset) = invalid_escape {
+                return Some(Err(Error::InvalidEscape {
+                    name: name.into(),
+                    position: start_position + offset,
+                }));
+            }
+            return Some(Ok((name, start_position, size, regex)));
         }
 
-        for (name, pattern) in &self.tokenizer.patterns {
-            let Ok(Some(tok)) = pattern.find(self.remaining_source) else {
-                continue;
-            };
-            self.remaining_source = &self.remaining_source[tok.end()..];
-            self.position += tok.end() - tok.start();
-            return Some(Ok(Token {
-                name,
-                value: tok.as_str(),
-                position: start_position,
+        if let Some((name, opener)) = self.find_unterminated() {
+            self.remaining_source = &self.remaining_source[opener..];
+            self.position += opener;
+            return Some(Err(Error::Unterminated {
+                name: name.into(),
+                start: start_position,
+                end: start_position + opener,
             }));
         }
 
-        let char = handling_result.unwrap_err();
-        let char_bytes = char.len_utf8();
+        let zero_width = self.find_zero_width();
         self.remaining_source = &self.remaining_source[char_bytes..];
         self.position += char_bytes;
-        Some(Err(Error::BadToken(char, start_position)))
+        Some(Err(match zero_width {
+            Some(name) => Error::ZeroWidthMatch {
+                name: name.into(),
+                position: start_position,
+            },
+            None => Error::BadToken(char, start_position),
+        }))
+    }
+
+    /// Counts the remaining tokens, stopping at the first error.
+    pub fn count_tokens(mut self) -> Result<usize, Error> {
+        let mut count = 0;
+        while let Some(result) = self.advance() {
+            result?;
+            count += 1;
+        }
+        Ok(count)
     }
 }
 
+impl<'a> Iterator for Core<'a> {
+    type Item = Result<Token<'a>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let source = self.source;
+        Some(self.advance()?.map(|(name, position, size, regex)| Token {
+            name,
+            value: &source[position..position + size],
+            position,
+            captures: regex.map_or_else(Vec::new, |regex| {
+                named_captures(regex, &source[position..], self.tokenizer.max_token_length)
+            }),
+        }))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        // every token and error consumes at least one byte
+        (0, Some(self.remaining_source.len()))
+    }
+}
+
+// once only ignored characters are left, they are never consumed, so `advance` keeps returning
+// `None`
+impl FusedIterator for Core<'_> {}
+
+/// The name of the token marking the end of the input (see
+/// [`emit_eof`](crate::Tokenizer#emit_eof)).
+pub(crate) const EOF: &str = "eof";
+
+/// Appends an [`EOF`] token at the `end` position after the tokens, unless there was an error.
+pub(crate) struct WithEof<I> {
+    tokens: I,
+    end: usize,
+    failed: bool,
+    done: bool,
+}
+
+impl<I> WithEof<I> {
+    pub fn new(tokens: I, end: usize) -> Self {
+        Self {
+            tokens,
+            end,
+            failed: false,
+            done: false,
+        }
+    }
+}
+
+impl<'a, I: Iterator<Item = Result<Token<'a>, Error>>> Iterator for WithEof<I> {
+    type Item = Result<Token<'a>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        match self.tokens.next() {
+            Some(result) => {
+                self.failed |= result.is_err();
+                Some(result)
+            }
+            None => {
+                self.done = true;
+                (!self.failed).then(|| Ok(Token::from((EOF, "", self.end))))
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.done {
+            return (0, Some(0));
+        }
+        let (lower, upper) = self.tokens.size_hint();
+        (lower, upper.and_then(|upper| upper.checked_add(1)))
+    }
+}
+
+impl<'a, I: Iterator<Item = Result<Token<'a>, Error>>> FusedIterator for WithEof<I> {}
+
 pub(crate) struct Fast<'a> {
     literal_map: FxHashMap<char, &'a str>,
-    ignored: FxHashSet<char>,
+    ignored: Ignored,
     source: &'a str,
     char_indices: CharIndices<'a>,
 }
 
 impl<'a> Fast<'a> {
-    pub fn new(tok: &'a Tokenizer<'a>, source: &'a str, ignored: FxHashSet<char>) -> Self {
+    pub fn new(tok: &'a Tokenizer<'a>, source: &'a str, ignored: Ignored) -> Self {
         Self {
             source,
             char_indices: source.char_indices(),
@@ -179,25 +857,49 @@ impl<'a> Fast<'a> {
     }
 }
 
+impl Fast<'_> {
+    /// Counts the remaining tokens, stopping at the first error.
+    pub fn count_tokens(self) -> Result<usize, Error> {
+        let mut count = 0;
+        for (index, char) in self.char_indices {
+            if self.ignored.contains(char) {
+                continue;
+            }
+            if !self.literal_map.contains_key(&char) {
+                return Err(Error::BadToken(char, index));
+            }
+            count += 1;
+        }
+        Ok(count)
+    }
+}
+
 impl<'a> Iterator for Fast<'a> {
     type Item = Result<Token<'a>, Error>;
 
     fn next(&mut self) -> Option<Self::Item> {
         let (index, char) = self
             .char_indices
-            .find(|&(_, c)| !self.ignored.contains(&c))?;
+            .find(|&(_, c)| !self.ignored.contains(c))?;
 
         match self.literal_map.get(&char) {
             Some(&name) => Some(Ok(Token {
                 name,
                 value: &self.source[index..index + char.len_utf8()],
                 position: index,
+                captures: Vec::new(),
             })),
             None => Some(Err(Error::BadToken(char, index))),
         }
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.char_indices.as_str().len()))
+    }
 }
 
+impl FusedIterator for Fast<'_> {}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -211,6 +913,27 @@ mod tests {
         );
     }
 
+    #[test]
+    fn ignored_skipping() {
+        let ignored = Ignored::new(FxHashSet::from_iter([' ', '\t', 'é', '\u{3000}']), false);
+        assert!(ignored.contains(' '));
+        assert!(ignored.contains('é'));
+        assert!(!ignored.contains('e'));
+
+        assert_eq!(ignored.find_token_start("x"), Some((0, 'x')));
+        assert_eq!(ignored.find_token_start("  \tx "), Some((3, 'x')));
+        assert_eq!(ignored.find_token_start(" é\u{3000} ü"), Some((7, 'ü')));
+        assert_eq!(ignored.find_token_start(" é \t"), None);
+        assert_eq!(ignored.find_token_start(""), None);
+        assert!(!ignored.contains('\u{a0}'));
+
+        let ignored = Ignored::new(FxHashSet::from_iter([' ']), true);
+        assert!(ignored.contains('\u{a0}'));
+        assert!(ignored.contains('\u{3000}'));
+        assert!(!ignored.contains('é'));
+        assert_eq!(ignored.find_token_start(" \u{a0}\u{3000}x"), Some((6, 'x')));
+    }
+
     #[test]
     fn literal_map_preparation() {
         assert_eq!(
@@ -0,0 +1,59 @@
+/// Metadata about a tokenization, as returned by
+/// [`Tokenizer::summarize`](crate::Tokenizer::summarize).
+///
+/// Every character of a successfully tokenized source is either part of a token or ignored, so
+/// the summary tells apart e.g. an empty source from one consisting only of ignored characters.
+///
+/// # Examples
+/// ```rust
+/// # use crossandra::{common, Tokenizer};
+/// let tok = Tokenizer::default()
+///     .with_patterns(vec![common::WORD.clone()])
+///     .unwrap()
+///     .with_ignore_whitespace(true);
+///
+/// let empty = tok.summarize("").unwrap();
+/// let blank = tok.summarize(" \n ").unwrap();
+/// assert!(!empty.has_tokens() && !blank.has_tokens());
+/// assert_eq!((empty.skipped(), blank.skipped()), (0, 3));
+///
+/// let summary = tok.summarize("hello world").unwrap();
+/// assert_eq!(summary.tokens(), 2);
+/// assert_eq!(summary.bytes_consumed(), 10);
+/// assert_eq!(summary.skipped(), 1);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct TokenizeSummary {
+    pub(crate) tokens: usize,
+    pub(crate) bytes_consumed: usize,
+    pub(crate) skipped: usize,
+}
+
+impl TokenizeSummary {
+    /// Returns the number of produced tokens, not counting the
+    /// [end-of-input token](crate::Tokenizer#emit_eof).
+    #[must_use]
+    pub fn tokens(&self) -> usize {
+        self.tokens
+    }
+
+    /// Returns whether any token was produced.
+    #[must_use]
+    pub fn has_tokens(&self) -> bool {
+        self.tokens > 0
+    }
+
+    /// Returns the number of bytes consumed by the produced tokens.
+    #[must_use]
+    pub fn bytes_consumed(&self) -> usize {
+        self.bytes_consumed
+    }
+
+    /// Returns the number of skipped characters, i.e.
+    /// [ignored](crate::Tokenizer#ignored_characters) ones and ones matched by
+    /// [skip patterns](crate::Tokenizer#skip_patterns).
+    #[must_use]
+    pub fn skipped(&self) -> usize {
+        self.skipped
+    }
+}
@@ -1,3 +1,5 @@
+use std::cmp::Ordering;
+
 /// Represents a lexical token with a name/type and its raw value from the source code.
 ///
 /// Used to represent the output of the [`Tokenizer`][crate::Tokenizer] struct.
@@ -5,25 +7,92 @@
 /// # Examples
 /// ```
 /// # use crossandra::Token;
-/// let num = Token { name: "int".into(), value: "23".into(), position: 3 };
+/// let num = Token { name: "int".into(), value: "23".into(), position: 3, captures: vec![] };
 /// let kw = Token::from(("keyword", "if", 0));
 /// assert_eq!(num, Token::from(("int", "23", 3)));
-/// # assert_eq!(kw, Token { name: "keyword".into(), value: "if".into(), position: 0 });
-/// # assert_eq!(format!("{num:?}"), "Token { name: \"int\", value: \"23\", position: 3 }");
+/// # assert_eq!(
+/// #     kw,
+/// #     Token { name: "keyword".into(), value: "if".into(), position: 0, captures: vec![] }
+/// # );
+/// # assert_eq!(
+/// #     format!("{num:?}"),
+/// #     "Token { name: \"int\", value: \"23\", position: 3, captures: [] }"
+/// # );
 /// ```
-#[derive(Debug, PartialEq, Eq)]
+///
+/// With the `serde` feature enabled, tokens can be serialized. As a [`Token`] borrows from both the
+/// [`Tokenizer`][crate::Tokenizer] and the source, it can't be deserialized on its own;
+/// deserialize into an [`OwnedToken`] instead.
+///
+/// Tokens are ordered by their position first, then by their name (and the rest of the fields),
+/// so a sorted collection of tokens (e.g. a [`BTreeSet`](std::collections::BTreeSet)) follows the
+/// source order.
+#[derive(Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Token<'a> {
     /// The type or category of the token (e.g., "int", "identifier", "operator").
     pub name: &'a str,
     /// The actual text/value from the source code that this token represents.
     pub value: &'a str,
     /// The position of the token in the source code.
-    /// * For [`Tokenizer::tokenize`], this is the byte offset from the start of the entire source.
-    /// * For [`Tokenizer::tokenize_lines`], this is the byte offset from the start of each line.
+    /// * For [`Tokenizer::tokenize`](crate::Tokenizer::tokenize), this is the byte offset from
+    ///   the start of the entire source.
+    /// * For `Tokenizer::tokenize_lines` (with the `rayon` feature), this is the byte offset from
+    ///   the start of each line.
     ///
     /// For instance, tokenizing `"aa\naa"` for the token `a` will yield positions of `[0, 1, 3, 4]`
     /// and `[[0, 1], [0, 1]]`, respectively.
     pub position: usize,
+    /// The named groups captured by the pattern that matched this token, as pairs of the group
+    /// name and the captured text, in the order of the groups in the pattern. Groups that didn't
+    /// participate in the match are left out.
+    ///
+    /// Empty for literals and for patterns without named groups, which are never matched again to
+    /// extract them.
+    ///
+    /// ```
+    /// # use crossandra::Tokenizer;
+    /// let tok = Tokenizer::default()
+    ///     .with_patterns(vec![("pair".into(), r"(?P<key>\w+)=(?P<value>\w+)".into())])
+    ///     .unwrap();
+    /// let token = tok.tokenize("answer=42").next().unwrap().unwrap();
+    /// assert_eq!(token.captures, [("key", "answer"), ("value", "42")]);
+    /// ```
+    pub captures: Vec<(&'a str, &'a str)>,
+}
+
+impl Token<'_> {
+    /// Converts the token into an [`OwnedToken`] by copying its name, value and captures.
+    #[must_use]
+    pub fn into_owned(self) -> OwnedToken {
+        OwnedToken {
+            name: self.name.into(),
+            value: self.value.into(),
+            position: self.position,
+            captures: self
+                .captures
+                .into_iter()
+                .map(|(name, value)| (name.into(), value.into()))
+                .collect(),
+        }
+    }
+}
+
+impl Ord for Token<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.position, self.name, self.value, &self.captures).cmp(&(
+            other.position,
+            other.name,
+            other.value,
+            &other.captures,
+        ))
+    }
+}
+
+impl PartialOrd for Token<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
 }
 
 impl<'a> From<(&'a str, &'a str, usize)> for Token<'a> {
@@ -32,6 +101,104 @@ impl<'a> From<(&'a str, &'a str, usize)> for Token<'a> {
             name: value.0,
             value: value.1,
             position: value.2,
+            captures: Vec::new(),
         }
     }
 }
+
+/// An owned version of [`Token`], holding copies of its name, value and captures, so it isn't tied
+/// to the lifetimes of the [`Tokenizer`][crate::Tokenizer] and the source.
+///
+/// With the `serde` feature enabled, it serializes the same way as a [`Token`], so it can be used
+/// to deserialize previously serialized tokens. It is ordered the same way as a [`Token`] too.
+///
+/// # Examples
+/// ```
+/// # use crossandra::{OwnedToken, Token};
+/// let token = Token::from(("int", "23", 3)).into_owned();
+/// assert_eq!(token.name, "int");
+/// assert_eq!(token.value, "23");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OwnedToken {
+    /// See [`Token::name`].
+    pub name: String,
+    /// See [`Token::value`].
+    pub value: String,
+    /// See [`Token::position`].
+    pub position: usize,
+    /// See [`Token::captures`].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub captures: Vec<(String, String)>,
+}
+
+impl Ord for OwnedToken {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.position, &self.name, &self.value, &self.captures).cmp(&(
+            other.position,
+            &other.name,
+            &other.value,
+            &other.captures,
+        ))
+    }
+}
+
+impl PartialOrd for OwnedToken {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl From<Token<'_>> for OwnedToken {
+    fn from(token: Token<'_>) -> Self {
+        token.into_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeSet, HashSet};
+
+    use super::{OwnedToken, Token};
+
+    #[test]
+    fn ordering() {
+        let tokens = [
+            Token::from(("b", "x", 1)),
+            Token::from(("a", "y", 1)),
+            Token::from(("c", "z", 0)),
+            Token::from(("a", "y", 1)),
+        ];
+        let sorted: Vec<_> = tokens
+            .iter()
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .map(|token| (token.name, token.position))
+            .collect();
+        assert_eq!(sorted, [("c", 0), ("a", 1), ("b", 1)]);
+        assert_eq!(tokens.iter().collect::<HashSet<_>>().len(), 3);
+
+        let owned: BTreeSet<OwnedToken> = tokens.into_iter().map(Token::into_owned).collect();
+        let owned: Vec<_> = owned.iter().map(|token| token.name.as_str()).collect();
+        assert_eq!(owned, ["c", "a", "b"]);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_roundtrip() {
+        let json = serde_json::to_string(&Token::from(("int", "23", 3))).unwrap();
+        assert_eq!(
+            json,
+            r#"{"name":"int","value":"23","position":3,"captures":[]}"#
+        );
+
+        let token: OwnedToken = serde_json::from_str(&json).unwrap();
+        assert_eq!(token, Token::from(("int", "23", 3)).into_owned());
+        assert_eq!(serde_json::to_string(&token).unwrap(), json);
+
+        let token: OwnedToken =
+            serde_json::from_str(r#"{"name":"int","value":"23","position":3}"#).unwrap();
+        assert!(token.captures.is_empty());
+    }
+}
@@ -0,0 +1,121 @@
+use std::collections::VecDeque;
+
+use crate::{Error, SplitOn, Token};
+
+/// A wrapper over an [`Iterator`] of tokenization results (e.g. the one returned by
+/// [`Tokenizer::tokenize`](crate::Tokenizer::tokenize)), allowing arbitrary lookahead.
+///
+/// Unlike [`std::iter::Peekable`], any number of upcoming items can be peeked at. Peeked items,
+/// including errors, are cached and still returned by [`next`](Iterator::next) in order.
+///
+/// # Examples
+/// ```rust
+/// # use crossandra::{Token, TokenStream, Tokenizer};
+/// let tok = Tokenizer::default()
+///     .with_literals(&[("a", "a"), ("b", "b")])
+///     .unwrap();
+/// let mut stream = tok.token_stream("abx");
+///
+/// assert_eq!(stream.peek().unwrap().as_ref().unwrap().name, "a");
+/// assert!(stream.peek_n(2).unwrap().is_err());
+/// assert!(stream.peek_n(3).is_none());
+/// assert_eq!(stream.next().unwrap().unwrap(), Token::from(("a", "a", 0)));
+/// ```
+pub struct TokenStream<I: Iterator> {
+    inner: I,
+    peeked: VecDeque<I::Item>,
+}
+
+impl<I: Iterator> TokenStream<I> {
+    /// Creates a new [`TokenStream`] wrapping the given [`Iterator`].
+    pub fn new(inner: I) -> Self {
+        Self {
+            inner,
+            peeked: VecDeque::new(),
+        }
+    }
+
+    /// Returns a reference to the next item without consuming it, or [`None`] if there are no
+    /// items left.
+    pub fn peek(&mut self) -> Option<&I::Item> {
+        self.peek_n(0)
+    }
+
+    /// Returns a reference to the `n`-th upcoming item (counting from zero) without consuming it,
+    /// or [`None`] if there are not enough items left.
+    pub fn peek_n(&mut self, n: usize) -> Option<&I::Item> {
+        while self.peeked.len() <= n {
+            self.peeked.push_back(self.inner.next()?);
+        }
+        self.peeked.get(n)
+    }
+}
+
+impl<'a, I: Iterator<Item = Result<Token<'a>, Error>>> TokenStream<I> {
+    /// Groups the [`Token`]s into [`Vec`]s delimited by tokens of the given name, e.g. to split
+    /// the source into statements. The groups are built lazily, see [`SplitOn`] for details.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use crossandra::Tokenizer;
+    /// let tok = Tokenizer::default()
+    ///     .with_literals(&[("semicolon", ";"), ("a", "a"), ("b", "b")])
+    ///     .unwrap();
+    /// let lengths: Vec<_> = tok
+    ///     .token_stream("ab;a;")
+    ///     .split_on("semicolon")
+    ///     .map(|statement| statement.unwrap().len())
+    ///     .collect();
+    /// assert_eq!(lengths, [2, 1]);
+    /// ```
+    pub fn split_on(self, name: &str) -> SplitOn<'_, Self> {
+        SplitOn::new(self, name)
+    }
+}
+
+impl<I: Iterator> Iterator for TokenStream<I> {
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.peeked.pop_front().or_else(|| self.inner.next())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (lower, upper) = self.inner.size_hint();
+        let peeked = self.peeked.len();
+        (
+            lower.saturating_add(peeked),
+            upper.and_then(|upper| upper.checked_add(peeked)),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Error, Token, Tokenizer};
+
+    #[test]
+    fn lookahead() {
+        let tok = Tokenizer::default()
+            .with_literals(&[("a", "a"), ("b", "b")])
+            .unwrap();
+        let mut stream = tok.token_stream("ab?a");
+
+        assert!(matches!(
+            stream.peek_n(2),
+            Some(Err(Error::BadToken('?', 2)))
+        ));
+        assert!(matches!(stream.peek(), Some(Ok(token)) if token.name == "a"));
+        assert!(matches!(stream.peek_n(1), Some(Ok(token)) if token.name == "b"));
+        assert!(stream.peek_n(4).is_none());
+
+        assert_eq!(stream.next().unwrap().unwrap(), Token::from(("a", "a", 0)));
+        assert_eq!(stream.next().unwrap().unwrap(), Token::from(("b", "b", 1)));
+        // peeking the error didn't discard it
+        assert!(matches!(stream.next(), Some(Err(Error::BadToken('?', 2)))));
+        assert!(matches!(stream.peek(), Some(Ok(token)) if token.position == 3));
+        assert_eq!(stream.next().unwrap().unwrap(), Token::from(("a", "a", 3)));
+        assert!(stream.peek().is_none());
+        assert!(stream.next().is_none());
+    }
+}
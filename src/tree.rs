@@ -1,72 +1,875 @@
+// without the `std` feature, there is no `Tokenizer` removing literals from a tree
+#![cfg_attr(not(feature = "std"), allow(dead_code))]
+
+use alloc::{string::String, vec, vec::Vec};
+
+#[cfg(feature = "std")]
 use rustc_hash::FxHashMap;
 
+#[cfg(feature = "std")]
+type Map<K, V> = FxHashMap<K, V>;
+#[cfg(not(feature = "std"))]
+type Map<K, V> = alloc::collections::BTreeMap<K, V>;
+
+/// The maximum number of children stored in a [`Vec`] before switching to a hash map.
+const SMALL_CHILDREN_LIMIT: usize = 8;
+
+/// A prefix tree (trie) of literals used by the [`Tokenizer`](crate::Tokenizer) to find the
+/// longest matching literal. Each literal is associated with a value of type `V`, which is the
+/// literal name for the [`Tokenizer`](crate::Tokenizer) (see [`LiteralTree`]).
+///
+/// Each [`Node`](Tree::Node) maps the next character of a literal to a subtree, with the [`None`]
+/// key holding the value of the literal ending at that node (if any). Literals that aren't a prefix
+/// of any other literal end with a [`Leaf`](Tree::Leaf). See [`Children`] for how the mapping is
+/// stored.
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub(crate) enum Tree<'a> {
-    Leaf(&'a str),
-    Node(FxHashMap<Option<char>, Tree<'a>>),
+pub enum Tree<V> {
+    /// The value of the literal ending here.
+    Leaf(V),
+    /// The subtrees for each following character, or the value of the literal ending here.
+    Node(Children<V>),
+}
+
+/// A [`Tree`] mapping literals to their names, as used by the [`Tokenizer`](crate::Tokenizer).
+pub type LiteralTree<'a> = Tree<&'a str>;
+
+impl<V> Default for Tree<V> {
+    fn default() -> Self {
+        Tree::Node(Children::default())
+    }
+}
+
+/// The children of a [`Node`](Tree::Node), mapping the next character of a literal (or [`None`]
+/// for the end of a literal) to a subtree.
+///
+/// Most nodes only have a few children, so they are kept in a sorted [`Vec`], which is much
+/// smaller than a hash map. Nodes with many children (e.g. the root of a large tree) switch to
+/// a hash map to keep the lookups fast (or a [`BTreeMap`](alloc::collections::BTreeMap) without
+/// the `std` feature).
+///
+/// The representation is private, so it can change without breaking the API of [`Tree`]. Use the
+/// methods of [`Tree`] (e.g. [`Tree::entries`]) to inspect the stored literals.
+#[derive(Debug, Clone)]
+pub struct Children<V> {
+    repr: ChildrenRepr<V>,
+    /// Whether all keys of this node and its subtrees are ASCII, so that a source can be matched
+    /// byte by byte. Might be `false` even when they are, but never the other way around.
+    ascii: bool,
+}
+
+#[derive(Debug, Clone)]
+enum ChildrenRepr<V> {
+    Small(Vec<(Option<char>, Tree<V>)>),
+    Large(Map<Option<char>, Tree<V>>),
 }
 
-pub(crate) fn generate_tree<'a>(literals: &FxHashMap<&'a str, &'a str>) -> Tree<'a> {
-    let mut sorted_items: Vec<_> = literals.iter().collect();
-    sorted_items.sort_by_key(|(k, _)| std::cmp::Reverse(k.len()));
+impl<V> Default for Children<V> {
+    fn default() -> Self {
+        Children {
+            repr: ChildrenRepr::Small(Vec::new()),
+            ascii: true,
+        }
+    }
+}
+
+impl<V> Children<V> {
+    /// Returns the number of children.
+    #[must_use]
+    pub(crate) fn len(&self) -> usize {
+        match &self.repr {
+            ChildrenRepr::Small(children) => children.len(),
+            ChildrenRepr::Large(children) => children.len(),
+        }
+    }
+
+    /// Returns `true` if there are no children.
+    #[must_use]
+    pub(crate) fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the subtree for the given key.
+    #[inline]
+    #[must_use]
+    pub(crate) fn get(&self, key: &Option<char>) -> Option<&Tree<V>> {
+        match &self.repr {
+            ChildrenRepr::Small(children) => children
+                .binary_search_by_key(key, |(k, _)| *k)
+                .ok()
+                .map(|i| &children[i].1),
+            ChildrenRepr::Large(children) => children.get(key),
+        }
+    }
+
+    /// Returns the subtree for the given key mutably.
+    pub(crate) fn get_mut(&mut self, key: &Option<char>) -> Option<&mut Tree<V>> {
+        match &mut self.repr {
+            ChildrenRepr::Small(children) => children
+                .binary_search_by_key(key, |(k, _)| *k)
+                .ok()
+                .map(|i| &mut children[i].1),
+            ChildrenRepr::Large(children) => children.get_mut(key),
+        }
+    }
+
+    /// Sets the subtree for the given key, returning the previous one (if any).
+    pub(crate) fn insert(&mut self, key: Option<char>, tree: Tree<V>) -> Option<Tree<V>> {
+        self.ascii &= key.is_none_or(|c| c.is_ascii()) && tree.is_ascii();
+        let children = match &mut self.repr {
+            ChildrenRepr::Large(children) => return children.insert(key, tree),
+            ChildrenRepr::Small(children) => children,
+        };
 
-    let mut root: Tree<'a> = Tree::Node(FxHashMap::default());
+        match children.binary_search_by_key(&key, |(k, _)| *k) {
+            Ok(i) => Some(core::mem::replace(&mut children[i].1, tree)),
+            Err(_) if children.len() == SMALL_CHILDREN_LIMIT => {
+                let mut large: Map<_, _> = children.drain(..).collect();
+                large.insert(key, tree);
+                self.repr = ChildrenRepr::Large(large);
+                None
+            }
+            Err(i) => {
+                // most nodes never grow, don't leave any unused capacity behind
+                children.reserve_exact(1);
+                children.insert(i, (key, tree));
+                None
+            }
+        }
+    }
 
-    for (k, v) in sorted_items {
-        let mut current = &mut root;
+    /// Removes the subtree for the given key, returning it (if any).
+    pub(crate) fn remove(&mut self, key: &Option<char>) -> Option<Tree<V>> {
+        match &mut self.repr {
+            ChildrenRepr::Small(children) => {
+                let i = children.binary_search_by_key(key, |(k, _)| *k).ok()?;
+                Some(children.remove(i).1)
+            }
+            ChildrenRepr::Large(children) => children.remove(key),
+        }
+    }
 
-        // iterate over the characters in the key
-        let mut chars = k.chars().peekable();
-        while let Some(c) = chars.next() {
-            let Tree::Node(ref mut map) = current else {
-                continue;
+    /// Returns an [`Iterator`] over the keys and subtrees. The order is unspecified.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (&Option<char>, &Tree<V>)> {
+        let (small, large) = match &self.repr {
+            ChildrenRepr::Small(children) => (Some(children.iter().map(|(k, v)| (k, v))), None),
+            ChildrenRepr::Large(children) => (None, Some(children.iter())),
+        };
+        small
+            .into_iter()
+            .flatten()
+            .chain(large.into_iter().flatten())
+    }
+
+    /// Returns an [`Iterator`] over the subtrees. The order is unspecified.
+    pub(crate) fn values(&self) -> impl Iterator<Item = &Tree<V>> {
+        self.iter().map(|(_, tree)| tree)
+    }
+
+    /// Recomputes the [`ascii`](Children::ascii) flag from the direct children, e.g. after
+    /// removing a non-ASCII literal.
+    fn update_ascii(&mut self) {
+        let ascii = self
+            .iter()
+            .all(|(key, tree)| key.is_none_or(|c| c.is_ascii()) && tree.is_ascii());
+        self.ascii = ascii;
+    }
+}
+
+impl<V: PartialEq> PartialEq for Children<V> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().all(|(k, v)| other.get(k) == Some(v))
+    }
+}
+
+impl<V: Eq> Eq for Children<V> {}
+
+#[cfg(test)]
+impl<V> FromIterator<(Option<char>, Tree<V>)> for Children<V> {
+    fn from_iter<I: IntoIterator<Item = (Option<char>, Tree<V>)>>(iter: I) -> Self {
+        let mut children = Children::default();
+        for (key, tree) in iter {
+            children.insert(key, tree);
+        }
+        children
+    }
+}
+
+impl<V> Tree<V> {
+    /// Returns the number of literals stored in the tree.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        match self {
+            Tree::Leaf(_) => 1,
+            Tree::Node(node) => node.values().map(Tree::len).sum(),
+        }
+    }
+
+    /// Returns `true` if the tree contains no literals.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the length (in bytes) of the longest literal stored in the tree, or 0 if the tree
+    /// is empty.
+    #[must_use]
+    pub fn max_literal_len(&self) -> usize {
+        let mut longest = 0;
+        let mut stack = vec![(self, 0)];
+
+        while let Some((tree, depth)) = stack.pop() {
+            match tree {
+                Tree::Leaf(_) => longest = longest.max(depth),
+                Tree::Node(node) => stack.extend(
+                    node.iter()
+                        .map(|(key, subtree)| (subtree, depth + key.map_or(0, char::len_utf8))),
+                ),
+            }
+        }
+
+        longest
+    }
+
+    /// Returns whether the given literal is stored in the tree. Unlike
+    /// [`match_longest_prefix`](Tree::match_longest_prefix), only exact matches count, i.e. a mere
+    /// prefix of a stored literal is not contained in the tree.
+    #[must_use]
+    pub fn contains_literal(&self, literal: &str) -> bool {
+        let mut tree = self;
+
+        for c in literal.chars() {
+            let Tree::Node(node) = tree else {
+                return false;
             };
 
-            // if there is a character after the current character
-            if chars.peek().is_some() {
-                // move down the tree
-                current = map
-                    .entry(Some(c))
-                    .or_insert(Tree::Node(FxHashMap::default()));
-            } else {
-                // else we reached the end and insert the value at the current position
-                map.entry(Some(c))
-                    // if the current subtree is a node, insert the value as a subtree
-                    .and_modify(|inner_tree| {
-                        if let Tree::Node(node) = inner_tree {
-                            node.insert(None, Tree::Leaf(v));
+            match node.get(&Some(c)) {
+                Some(subtree) => tree = subtree,
+                None => return false,
+            }
+        }
+
+        match tree {
+            Tree::Leaf(_) => true,
+            Tree::Node(node) => matches!(node.get(&None), Some(Tree::Leaf(_))),
+        }
+    }
+
+    /// Returns an [`Iterator`] over all (literal, value) pairs stored in the tree, using
+    /// a depth-first traversal. The order of the pairs is unspecified.
+    pub fn entries(&self) -> impl Iterator<Item = (String, &V)> + '_ {
+        let mut stack = vec![(String::new(), self)];
+
+        core::iter::from_fn(move || {
+            while let Some((prefix, tree)) = stack.pop() {
+                match tree {
+                    Tree::Leaf(value) => return Some((prefix, value)),
+                    Tree::Node(node) => {
+                        for (key, subtree) in node.iter() {
+                            let mut literal = prefix.clone();
+                            literal.extend(key);
+                            stack.push((literal, subtree));
                         }
-                    })
-                    // if the current subtree is a node, insert the value as a leaf
-                    .or_insert(Tree::Leaf(v));
+                    }
+                }
+            }
+            None
+        })
+    }
+
+    /// Finds the longest literal stored in the tree that is a prefix of the given source.
+    /// Returns the matched part of the source along with the value of the literal.
+    ///
+    /// An empty literal is never matched, as a zero-width match would prevent any progress.
+    #[must_use]
+    pub fn match_longest_prefix<'s>(&self, source: &'s str) -> Option<(&'s str, &V)> {
+        let longest = if self.is_ascii() {
+            // a non-ASCII byte maps to a character that can't be a key, so it ends the match just
+            // like an unknown character would, and the source doesn't have to be decoded at all
+            self.descend_longest(source.bytes().map(|byte| (char::from(byte), 1)))
+        } else {
+            self.descend_longest(source.chars().map(|c| (c, c.len_utf8())))
+        };
+        longest.map(|(end, value)| (&source[..end], value))
+    }
+
+    /// Descends the tree along the given characters (with their lengths in bytes), returning the
+    /// length of the longest literal found along with its value.
+    #[inline]
+    fn descend_longest(&self, chars: impl Iterator<Item = (char, usize)>) -> Option<(usize, &V)> {
+        let mut longest = None;
+        let mut tree = self;
+        let mut i = 0;
+
+        for (c, length) in chars {
+            let Tree::Node(node) = tree else {
+                break;
+            };
+
+            // a shorter literal ends here, remember it in case the longer one doesn't match
+            if let Some(Tree::Leaf(value)) = node.get(&None).filter(|_| i > 0) {
+                longest = Some((i, value));
+            }
+
+            match node.get(&Some(c)) {
+                Some(Tree::Leaf(value)) => return Some((i + length, value)),
+                Some(subtree) => tree = subtree,
+                None => return longest,
+            }
+            i += length;
+        }
+
+        // the whole source was consumed, it might still end with a literal
+        if let Tree::Node(node) = tree {
+            if let Some(Tree::Leaf(value)) = node.get(&None).filter(|_| i > 0) {
+                longest = Some((i, value));
+            }
+        }
+
+        longest
+    }
+
+    /// Returns whether all literals stored in the tree are known to be ASCII.
+    fn is_ascii(&self) -> bool {
+        match self {
+            Tree::Leaf(_) => true,
+            Tree::Node(node) => node.ascii,
+        }
+    }
+
+    /// Finds all literals stored in the tree that are a prefix of the given source, ordered from
+    /// the shortest to the longest. Returns the matched parts of the source along with the values
+    /// of the literals.
+    ///
+    /// Just like in [`match_longest_prefix`](Tree::match_longest_prefix), an empty literal is never
+    /// matched.
+    #[must_use]
+    pub fn match_all_prefixes<'s>(&self, source: &'s str) -> Vec<(&'s str, &V)> {
+        let mut matches = Vec::new();
+        let mut tree = self;
+
+        for (i, c) in source.char_indices() {
+            let Tree::Node(node) = tree else {
+                break;
+            };
+
+            if let Some(Tree::Leaf(value)) = node.get(&None).filter(|_| i > 0) {
+                matches.push((&source[..i], value));
+            }
 
-                break; // needed to satisfy the borrow checker
+            match node.get(&Some(c)) {
+                Some(Tree::Leaf(value)) => {
+                    matches.push((&source[..i + c.len_utf8()], value));
+                    return matches;
+                }
+                Some(subtree) => tree = subtree,
+                None => return matches,
             }
         }
+
+        if let Tree::Node(node) = tree {
+            if let Some(Tree::Leaf(value)) = node.get(&None).filter(|_| !source.is_empty()) {
+                matches.push((source, value));
+            }
+        }
+
+        matches
     }
 
+    /// Inserts the given literal into the tree, associating it with the given value. If the
+    /// literal was already present, its value is replaced and the old one is returned.
+    ///
+    /// The resulting tree only depends on the stored literals and their values, not on the order
+    /// of insertion. In particular, a literal that is a prefix of another one ends up under the
+    /// [`None`] key of the same node whether it's inserted before or after the longer literal.
+    pub fn insert(&mut self, literal: &str, value: V) -> Option<V> {
+        let mut chars = literal.chars();
+        let Some(c) = chars.next() else {
+            // the literal ends here
+            return match self {
+                Tree::Leaf(old) => Some(core::mem::replace(old, value)),
+                Tree::Node(node) => match node.insert(None, Tree::Leaf(value)) {
+                    Some(Tree::Leaf(old)) => Some(old),
+                    _ => None,
+                },
+            };
+        };
+
+        if let Tree::Leaf(_) = self {
+            // a longer literal continues past this one, keep the value under the `None` key
+            let leaf = core::mem::take(self);
+            let mut node = Children::default();
+            node.insert(None, leaf);
+            *self = Tree::Node(node);
+        }
+        let Tree::Node(node) = self else {
+            unreachable!("leaves are converted to nodes above");
+        };
+
+        node.ascii &= literal.is_ascii();
+        let rest = chars.as_str();
+        if let Some(subtree) = node.get_mut(&Some(c)) {
+            return subtree.insert(rest, value);
+        }
+
+        let subtree = if rest.is_empty() {
+            Tree::Leaf(value)
+        } else {
+            let mut subtree = Tree::default();
+            subtree.insert(rest, value);
+            subtree
+        };
+        node.insert(Some(c), subtree);
+        None
+    }
+
+    /// Removes the given literal from the tree, pruning the nodes that no longer lead to any value.
+    /// Returns whether the literal was present in the tree.
+    pub(crate) fn remove(&mut self, literal: &str) -> bool {
+        let Tree::Node(node) = self else {
+            return false;
+        };
+
+        let mut chars = literal.chars();
+        let Some(c) = chars.next() else {
+            return node.remove(&None).is_some();
+        };
+
+        let removed = match node.get_mut(&Some(c)) {
+            None => false,
+            Some(Tree::Leaf(_)) => chars.as_str().is_empty(),
+            Some(subtree) => subtree.remove(chars.as_str()),
+        };
+
+        if removed {
+            match node.get_mut(&Some(c)) {
+                // the literal ended here, or the subtree has no values left
+                Some(Tree::Leaf(_)) => {
+                    node.remove(&Some(c));
+                }
+                Some(Tree::Node(subtree)) if subtree.is_empty() => {
+                    node.remove(&Some(c));
+                }
+                // the subtree holds only a value for the current prefix, collapse it into a leaf
+                Some(Tree::Node(subtree))
+                    if subtree.len() == 1 && matches!(subtree.get(&None), Some(Tree::Leaf(_))) =>
+                {
+                    if let Some(leaf) = subtree.remove(&None) {
+                        node.insert(Some(c), leaf);
+                    }
+                }
+                _ => {}
+            }
+            if !node.ascii {
+                node.update_ascii();
+            }
+        }
+
+        removed
+    }
+}
+
+/// Builds a tree of the given literals. The iteration order of the map doesn't matter, as
+/// [`Tree::insert`] is order-independent, so no sorting is needed for a deterministic tree.
+pub(crate) fn generate_tree<V: Clone>(literals: &Map<&str, V>) -> Tree<V> {
+    let mut root = Tree::default();
+    for (literal, value) in literals {
+        root.insert(literal, value.clone());
+    }
     root
 }
 
 #[cfg(test)]
 mod tests {
-    use rustc_hash::FxHashMap;
+    use alloc::{
+        format,
+        string::{String, ToString},
+        vec,
+        vec::Vec,
+    };
 
     use super::{
-        generate_tree,
+        generate_tree, Children, Tree,
         Tree::{Leaf, Node},
     };
 
     macro_rules! hashmap {
         { $( $key:expr => $value:expr ),* $(,)? } => {{
-            FxHashMap::from_iter([$( ($key, $value), )*])
+            FromIterator::from_iter([$( ($key, $value), )*])
         }};
     }
 
+    #[test]
+    fn entries() {
+        let tree = generate_tree(&hashmap! {
+            "ABC" => "x",
+            "A" => "y",
+            "B" => "z",
+        });
+
+        let mut entries: Vec<_> = tree.entries().collect();
+        entries.sort();
+        assert_eq!(
+            entries,
+            vec![("A".into(), &"y"), ("ABC".into(), &"x"), ("B".into(), &"z")]
+        );
+        assert_eq!(generate_tree::<&str>(&hashmap! {}).entries().count(), 0);
+
+        let tree = generate_tree(&hashmap! {
+            "+" => "a",
+            "++" => "b",
+            "+++" => "c",
+            "é" => "e",
+        });
+        let mut entries: Vec<_> = tree.entries().collect();
+        entries.sort();
+        assert_eq!(
+            entries,
+            vec![
+                ("+".into(), &"a"),
+                ("++".into(), &"b"),
+                ("+++".into(), &"c"),
+                ("é".into(), &"e")
+            ]
+        );
+    }
+
+    #[test]
+    fn insertion_order() {
+        let literals = [
+            ("ab", 1),
+            ("ac", 2),
+            ("a", 3),
+            ("abc", 4),
+            ("abd", 5),
+            ("ba", 6),
+            ("b", 7),
+        ];
+        let build = |order: &mut dyn Iterator<Item = &(&str, i32)>| {
+            let mut tree = Tree::default();
+            for (literal, value) in order {
+                tree.insert(literal, *value);
+            }
+            tree
+        };
+
+        let forward = build(&mut literals.iter());
+        let backward = build(&mut literals.iter().rev());
+        assert_eq!(forward, backward);
+        assert_eq!(format!("{forward:?}"), format!("{backward:?}"));
+        assert_eq!(forward, generate_tree(&literals.into_iter().collect()));
+        assert_eq!(forward.match_longest_prefix("abx"), Some(("ab", &1)));
+    }
+
+    #[test]
+    fn max_literal_len() {
+        assert_eq!(generate_tree::<&str>(&hashmap! {}).max_literal_len(), 0);
+
+        let tree = generate_tree(&hashmap! {
+            "+" => "a",
+            "+++" => "b",
+            "éé" => "c",
+            "-" => "d",
+        });
+        assert_eq!(tree.max_literal_len(), 4);
+
+        // a wide node (stored in a hash map) with a value at its end
+        let mut tree = Tree::default();
+        for c in 'a'..='z' {
+            tree.insert(&format!("x{c}"), 0);
+        }
+        tree.insert("x", 1);
+        assert_eq!(tree.max_literal_len(), 2);
+    }
+
+    #[test]
+    fn len_and_contains() {
+        let tree = generate_tree(&hashmap! {
+            "ABC" => "x",
+            "A" => "y",
+            "B" => "z",
+            "é" => "e",
+        });
+
+        assert_eq!(tree.len(), 4);
+        assert!(!tree.is_empty());
+        for literal in ["ABC", "A", "B", "é"] {
+            assert!(
+                tree.contains_literal(literal),
+                "{literal:?} is not contained"
+            );
+        }
+        for literal in ["AB", "ABCD", "BA", "C", "e", ""] {
+            assert!(!tree.contains_literal(literal), "{literal:?} is contained");
+        }
+
+        let tree = generate_tree::<&str>(&hashmap! {});
+        assert_eq!(tree.len(), 0);
+        assert!(tree.is_empty());
+        assert!(!tree.contains_literal(""));
+    }
+
+    #[test]
+    fn longest_prefix() {
+        let tree = generate_tree(&hashmap! {
+            "ABC" => "x",
+            "A" => "y",
+            "B" => "z",
+            "é" => "e",
+            "éab" => "f",
+            "Bé" => "w",
+        });
+
+        assert_eq!(tree.match_longest_prefix("ABCD"), Some(("ABC", &"x")));
+        assert_eq!(tree.match_longest_prefix("ABD"), Some(("A", &"y")));
+        assert_eq!(tree.match_longest_prefix("AB"), Some(("A", &"y")));
+        assert_eq!(tree.match_longest_prefix("A"), Some(("A", &"y")));
+        assert_eq!(tree.match_longest_prefix("BA"), Some(("B", &"z")));
+        assert_eq!(tree.match_longest_prefix("éaz"), Some(("é", &"e")));
+        assert_eq!(tree.match_longest_prefix("éab"), Some(("éab", &"f")));
+        assert_eq!(tree.match_longest_prefix("Béx"), Some(("Bé", &"w")));
+        assert_eq!(tree.match_longest_prefix("Bè"), Some(("B", &"z")));
+        assert_eq!(tree.match_longest_prefix("CBA"), None);
+        assert_eq!(tree.match_longest_prefix(""), None);
+    }
+
+    #[test]
+    fn ascii_path() {
+        let mut tree = generate_tree(&hashmap! {
+            "+" => "a",
+            "+=" => "b",
+            "Ã" => "c",
+        });
+        assert!(!tree.is_ascii());
+
+        // the ASCII path walks bytes, so the lead byte of `Ã` (0xC3) must not match it
+        assert!(tree.remove("Ã"));
+        assert!(tree.is_ascii());
+        assert_eq!(tree.match_longest_prefix("+Ã"), Some(("+", &"a")));
+        assert_eq!(tree.match_longest_prefix("+=é"), Some(("+=", &"b")));
+        assert_eq!(tree.match_longest_prefix("Ã+"), None);
+        assert_eq!(tree.match_longest_prefix("é"), None);
+
+        tree.insert("+é", "d");
+        assert!(!tree.is_ascii());
+        assert_eq!(tree.match_longest_prefix("+éx"), Some(("+é", &"d")));
+        assert_eq!(tree.match_longest_prefix("+Ã"), Some(("+", &"a")));
+
+        // removing the only non-ASCII literal deep in the tree enables the ASCII path again
+        assert!(tree.remove("+é"));
+        assert!(tree.is_ascii());
+        assert_eq!(tree, generate_tree(&hashmap! { "+" => "a", "+=" => "b" }));
+    }
+
+    #[test]
+    fn all_prefixes() {
+        let tree = generate_tree(&hashmap! {
+            "+" => "a",
+            "++" => "b",
+            "+++" => "c",
+            "+-" => "d",
+            "é" => "e",
+            "éab" => "f",
+        });
+
+        assert_eq!(
+            tree.match_all_prefixes("+++"),
+            vec![("+", &"a"), ("++", &"b"), ("+++", &"c")]
+        );
+        assert_eq!(
+            tree.match_all_prefixes("++-"),
+            vec![("+", &"a"), ("++", &"b")]
+        );
+        assert_eq!(
+            tree.match_all_prefixes("+-+"),
+            vec![("+", &"a"), ("+-", &"d")]
+        );
+        assert_eq!(
+            tree.match_all_prefixes("++"),
+            vec![("+", &"a"), ("++", &"b")]
+        );
+        assert_eq!(
+            tree.match_all_prefixes("éabc"),
+            vec![("é", &"e"), ("éab", &"f")]
+        );
+        assert_eq!(tree.match_all_prefixes("éa"), vec![("é", &"e")]);
+        assert!(tree.match_all_prefixes("-").is_empty());
+        assert!(tree.match_all_prefixes("").is_empty());
+
+        for source in ["+++", "++-", "+-+", "éabc", "éa", "-"] {
+            assert_eq!(
+                tree.match_all_prefixes(source).last().copied(),
+                tree.match_longest_prefix(source)
+            );
+        }
+    }
+
+    #[test]
+    fn generic_values() {
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        enum Kind {
+            Operator(u8),
+            Keyword,
+        }
+
+        let tree = generate_tree(&hashmap! {
+            "+" => Kind::Operator(1),
+            "*" => Kind::Operator(2),
+            "**" => Kind::Operator(3),
+            "if" => Kind::Keyword,
+        });
+
+        assert_eq!(
+            tree.match_longest_prefix("**2"),
+            Some(("**", &Kind::Operator(3)))
+        );
+        assert_eq!(
+            tree.match_longest_prefix("*2"),
+            Some(("*", &Kind::Operator(2)))
+        );
+        assert_eq!(
+            tree.match_longest_prefix("if x"),
+            Some(("if", &Kind::Keyword))
+        );
+        assert_eq!(tree.len(), 4);
+    }
+
+    #[test]
+    fn insertion() {
+        let expected = generate_tree(&hashmap! {
+            "ABC" => "x",
+            "A" => "y",
+            "B" => "z",
+        });
+
+        for order in [["ABC", "A", "B"], ["A", "ABC", "B"], ["B", "A", "ABC"]] {
+            let mut tree = Tree::default();
+            for literal in order {
+                let name = match literal {
+                    "ABC" => "x",
+                    "A" => "y",
+                    _ => "z",
+                };
+                assert_eq!(tree.insert(literal, name), None);
+            }
+            assert_eq!(tree, expected);
+        }
+
+        // inserting a shorter literal along an existing path sets the value of the interior node
+        let mut tree = generate_tree(&hashmap! { "ABC" => "x", "B" => "z" });
+        assert_eq!(tree.insert("A", "y"), None);
+        assert_eq!(tree, expected);
+
+        let mut tree = expected.clone();
+        assert_eq!(tree.insert("A", "w"), Some("y"));
+        assert_eq!(tree.insert("ABC", "v"), Some("x"));
+        assert_eq!(tree.match_longest_prefix("AB"), Some(("A", &"w")));
+        assert_eq!(tree.match_longest_prefix("ABC"), Some(("ABC", &"v")));
+        assert_eq!(tree.len(), 3);
+    }
+
+    #[test]
+    fn empty_literal_never_matches() {
+        let mut tree = generate_tree(&hashmap! { "+" => "a", "++" => "b" });
+        tree.insert("", "empty");
+        assert!(tree.contains_literal(""));
+
+        assert_eq!(tree.match_longest_prefix("-"), None);
+        assert_eq!(tree.match_longest_prefix(""), None);
+        assert_eq!(tree.match_longest_prefix("+-"), Some(("+", &"a")));
+        assert!(tree.match_all_prefixes("-").is_empty());
+        assert!(tree.match_all_prefixes("").is_empty());
+        assert_eq!(
+            tree.match_all_prefixes("++"),
+            vec![("+", &"a"), ("++", &"b")]
+        );
+    }
+
+    #[test]
+    fn large_fan_out() {
+        let literals: Vec<String> = ('a'..='z').map(|c| format!("{c}{c}")).collect();
+        let mut tree = Tree::default();
+        for literal in &literals {
+            assert_eq!(tree.insert(literal, literal.as_str()), None);
+            assert_eq!(tree.insert(&literal[..1], "single"), None);
+        }
+
+        let Node(children) = &tree else {
+            panic!("tree was not a Node");
+        };
+        assert_eq!(children.len(), 26);
+        assert_eq!(tree.len(), 52);
+        assert_eq!(tree.match_longest_prefix("qqq"), Some(("qq", &"qq")));
+        assert_eq!(tree.match_longest_prefix("qa"), Some(("q", &"single")));
+
+        for literal in &literals {
+            assert!(tree.remove(literal));
+        }
+        for c in 'a'..='u' {
+            assert!(tree.remove(&c.to_string()));
+        }
+        assert!(!tree.contains_literal("zz"));
+        assert_eq!(tree.match_longest_prefix("zz"), Some(("z", &"single")));
+        assert_eq!(tree.len(), 5);
+
+        // the representation doesn't affect equality
+        let expected: Children<_> = ('v'..='z').map(|c| (Some(c), Leaf("single"))).collect();
+        assert_eq!(tree, Node(expected));
+    }
+
+    #[test]
+    fn removal() {
+        let mut tree = generate_tree(&hashmap! {
+            "ABC" => "x",
+            "A" => "y",
+            "B" => "z",
+        });
+
+        assert!(!tree.remove("AB"));
+        assert!(!tree.remove("ABCD"));
+        assert!(!tree.remove("C"));
+
+        assert!(tree.remove("A"));
+        assert_eq!(
+            tree,
+            generate_tree(&hashmap! {
+                "ABC" => "x",
+                "B" => "z",
+            })
+        );
+        assert!(!tree.remove("A"));
+
+        assert!(tree.remove("ABC"));
+        assert_eq!(tree, Node(hashmap! { Some('B') => Leaf("z") }));
+
+        assert!(tree.remove("B"));
+        assert_eq!(tree, generate_tree(&hashmap! {}));
+    }
+
+    #[test]
+    fn removal_collapses_nodes() {
+        let mut tree = generate_tree(&hashmap! {
+            "+" => "a",
+            "++" => "b",
+            "+++" => "c",
+        });
+
+        assert!(tree.remove("+++"));
+        assert_eq!(
+            tree,
+            generate_tree(&hashmap! {
+                "+" => "a",
+                "++" => "b",
+            })
+        );
+
+        assert!(tree.remove("+"));
+        assert_eq!(tree, generate_tree(&hashmap! { "++" => "b" }));
+    }
+
     #[test]
     fn empty_tree() {
-        let tree = generate_tree(&hashmap! {});
-        assert!(matches!(tree, Node(FxHashMap { .. })));
+        let tree = generate_tree::<&str>(&hashmap! {});
+        assert!(matches!(tree, Node(_)));
     }
 
     #[test]
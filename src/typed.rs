@@ -0,0 +1,127 @@
+use rustc_hash::FxHashMap;
+
+use crate::{Error, Token, Tokenizer};
+
+/// A [`Token`] along with its kind, as produced by [`Typed`].
+#[derive(Debug, PartialEq, Eq)]
+pub struct TypedToken<'a, K> {
+    /// The kind assigned to the name of the token.
+    pub kind: K,
+    /// The token itself.
+    pub token: Token<'a>,
+}
+
+/// A view of a [`Tokenizer`] producing [`TypedToken`]s, i.e. tokens with a kind of type `K`
+/// (usually a user-defined enum) assigned to each token name, instead of matching on the names.
+///
+/// Created by [`Tokenizer::typed`], which checks that every token the [`Tokenizer`] can produce
+/// has a kind, so the lookup can't fail during tokenization.
+#[derive(Debug, Clone)]
+pub struct Typed<'a, K> {
+    tokenizer: &'a Tokenizer<'a>,
+    kinds: FxHashMap<String, K>,
+}
+
+impl<'a, K: Clone> Typed<'a, K> {
+    pub(crate) fn new(tokenizer: &'a Tokenizer<'a>, kinds: &[(&str, K)]) -> Result<Self, Error> {
+        let kinds: FxHashMap<_, _> = kinds
+            .iter()
+            .map(|(name, kind)| ((*name).to_owned(), kind.clone()))
+            .collect();
+        if let Some(name) = tokenizer
+            .token_names()
+            .into_iter()
+            .find(|name| !kinds.contains_key(*name))
+        {
+            return Err(Error::MissingKind(name.into()));
+        }
+        Ok(Self { tokenizer, kinds })
+    }
+
+    /// Returns the kind assigned to the given token name, if any.
+    #[must_use]
+    pub fn kind(&self, name: &str) -> Option<&K> {
+        self.kinds.get(name)
+    }
+
+    /// Tokenizes the given source code like [`Tokenizer::tokenize`], assigning each [`Token`] its
+    /// kind.
+    pub fn tokenize(
+        &self,
+        source: &'a str,
+    ) -> impl Iterator<Item = Result<TypedToken<'a, K>, Error>> + '_ {
+        self.tokenizer.tokenize(source).map(|token| {
+            token.map(|token| TypedToken {
+                kind: self.kinds[token.name].clone(),
+                token,
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{common, Error, Tokenizer};
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum Kind {
+        Operator,
+        Number,
+        End,
+    }
+
+    #[test]
+    fn kinds() {
+        let tok = Tokenizer::default()
+            .with_literals(&[("add", "+"), ("sub", "-")])
+            .unwrap()
+            .with_patterns(vec![common::UNSIGNED_INT.clone()])
+            .unwrap()
+            .with_emit_eof(true)
+            .with_ignore_whitespace(true);
+
+        let typed = tok
+            .typed(&[
+                ("add", Kind::Operator),
+                ("sub", Kind::Operator),
+                ("unsigned_int", Kind::Number),
+                ("eof", Kind::End),
+                ("unused", Kind::End),
+            ])
+            .unwrap();
+        assert_eq!(typed.kind("sub"), Some(&Kind::Operator));
+        assert_eq!(typed.kind("mul"), None);
+
+        let tokens: Vec<_> = typed
+            .tokenize("1 + 2")
+            .map(|token| token.map(|token| (token.kind, token.token.value)))
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            tokens,
+            [
+                (Kind::Number, "1"),
+                (Kind::Operator, "+"),
+                (Kind::Number, "2"),
+                (Kind::End, ""),
+            ]
+        );
+        assert!(matches!(
+            typed.tokenize("1 ?").nth(1),
+            Some(Err(Error::BadToken('?', 2)))
+        ));
+
+        assert!(matches!(
+            tok.typed(&[("add", Kind::Operator), ("unsigned_int", Kind::Number)]),
+            Err(Error::MissingKind(name)) if name == "sub"
+        ));
+        assert!(matches!(
+            tok.typed(&[
+                ("add", Kind::Operator),
+                ("sub", Kind::Operator),
+                ("unsigned_int", Kind::Number),
+            ]),
+            Err(Error::MissingKind(name)) if name == "eof"
+        ));
+    }
+}